@@ -103,7 +103,7 @@ export function getBackendContractBindings(): BackendContractBinding[] {
       operation: 'record_commitment',
       contract: 'SubscriptionLogging',
       method: BLOCKCHAIN_INVOKE_METHODS.recordCommitment,
-      expectedArgKinds: ['BytesN<32>'],
+      expectedArgKinds: ['Address', 'BytesN32'],
     },
   ];
 }
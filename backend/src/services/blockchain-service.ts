@@ -5,6 +5,7 @@ import { getRequestId } from "../middleware/requestContext";
 import crypto from 'crypto';
 import { calculateBackoffDelay } from "../utils/retry";
 import {
+  Address,
   Contract,
   Keypair,
   Memo,
@@ -875,7 +876,14 @@ export class BlockchainService {
     }
 
     try {
-      const args = [xdr.ScVal.scvBytes(commitmentHash)];
+      // The logging contract only accepts commitments from allowlisted writers
+      // (see `add_writer`); the backend logs as its STELLAR_SECRET_KEY account.
+      const secret = await secretProvider.getSecret("STELLAR_SECRET_KEY");
+      if (!secret) {
+        return { commitmentIndex: -1, error: 'STELLAR_SECRET_KEY not configured' };
+      }
+      const writer = new Address(Keypair.fromSecret(secret).publicKey());
+      const args = [writer.toScVal(), xdr.ScVal.scvBytes(commitmentHash)];
       const result = await this.invokeContractWithRetry(
         BLOCKCHAIN_INVOKE_METHODS.recordCommitment,
         args,
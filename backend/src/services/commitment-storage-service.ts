@@ -18,6 +18,7 @@ const EVENT_TYPE_BYTE: Record<string, number> = {
   subscription_cancel: 0x13,
   subscription_pause: 0x14,
   subscription_unpause: 0x15,
+  usage_report: 0x16,
 };
 
 export class CommitmentStorageService {
@@ -1,6 +1,7 @@
 import logger from '../config/logger';
 import { supabase } from '../config/database';
 import {
+  Address,
   Contract,
   Keypair,
   Networks,
@@ -261,7 +262,7 @@ export class ContractUpgradeService {
       .addOperation(contract.call(
         'propose_upgrade',
         this.addressToScVal(sk.publicKey()),
-        xdr.ScVal.scvString(targetContract),
+        new Address(targetContract).toScVal(),
         this.hexToBytesN(newWasmHash),
         this.hexToBytesN(previousWasmHash),
         xdr.ScVal.scvString(description),
@@ -2,7 +2,7 @@
 
 use soroban_sdk::{
     contract, contracterror, contractevent, contractimpl, contracttype, panic_with_error,
-    vec, Address, BytesN, Env, String, Vec,
+    vec, Address, BytesN, Env, IntoVal, String, Symbol, Vec,
 };
 
 // ============================================================================
@@ -29,10 +29,12 @@ enum DataKey {
     Proposal(u64),
     RollbackWasmHash,
     RollbackContractId,
+    RollbackProposalId,
     RollbackConsumed,
     UpgradesPaused,
     TimelockOverride,
     ApprovedBy(u64),
+    UpgradeTarget(Address),
 }
 
 // ============================================================================
@@ -55,7 +57,7 @@ pub enum ProposalState {
 pub struct UpgradeProposal {
     pub id: u64,
     pub description: String,
-    pub target_contract: String,
+    pub target_contract: Address,
     pub new_wasm_hash: BytesN<32>,
     pub proposer: Address,
     pub state: ProposalState,
@@ -87,6 +89,7 @@ pub enum UpgradeError {
     NoRollbackAvailable = 12,
     RollbackAlreadyConsumed = 13,
     InvalidArgument = 14,
+    TargetNotAllowed = 15,
 }
 
 // ============================================================================
@@ -96,7 +99,7 @@ pub enum UpgradeError {
 #[contractevent]
 pub struct UpgradeProposed {
     pub proposal_id: u64,
-    pub target_contract: String,
+    pub target_contract: Address,
     pub new_wasm_hash: BytesN<32>,
     pub proposer: Address,
 }
@@ -117,6 +120,7 @@ pub struct UpgradeReady {
 #[contractevent]
 pub struct UpgradeExecuted {
     pub proposal_id: u64,
+    pub target_contract: Address,
     pub new_wasm_hash: BytesN<32>,
     pub previous_wasm_hash: BytesN<32>,
 }
@@ -124,6 +128,7 @@ pub struct UpgradeExecuted {
 #[contractevent]
 pub struct UpgradeRolledBack {
     pub proposal_id: u64,
+    pub target_contract: Address,
     pub restored_wasm_hash: BytesN<32>,
 }
 
@@ -138,6 +143,12 @@ pub struct GuardianSetChanged {
     pub guardians: Vec<Address>,
 }
 
+#[contractevent]
+pub struct UpgradeTargetChanged {
+    pub target: Address,
+    pub allowed: bool,
+}
+
 #[contractevent]
 pub struct UpgradesPauseToggled {
     pub paused: bool,
@@ -205,9 +216,28 @@ impl ContractUpgradeGovernance {
             .unwrap_or(DEFAULT_TIMELOCK_SECONDS)
     }
 
-    fn save_rollback_slot(env: &Env, wasm_hash: BytesN<32>, contract_id: String) {
+    fn save_rollback_slot(env: &Env, proposal_id: u64, wasm_hash: BytesN<32>, contract_id: Address) {
         env.storage().persistent().set(&DataKey::RollbackWasmHash, &wasm_hash);
         env.storage().persistent().set(&DataKey::RollbackContractId, &contract_id);
+        env.storage().persistent().set(&DataKey::RollbackProposalId, &proposal_id);
+    }
+
+    fn require_upgrade_target(env: &Env, target: &Address) {
+        let allowed: bool = env.storage().persistent()
+            .get(&DataKey::UpgradeTarget(target.clone())).unwrap_or(false);
+        if !allowed { panic_with_error!(env, UpgradeError::TargetNotAllowed); }
+    }
+
+    /// Call the target's `upgrade(new_wasm_hash)` entrypoint. The target
+    /// authorizes the call against its stored governance address, which is
+    /// satisfied because this contract is the direct invoker.
+    fn invoke_upgrade(env: &Env, target: &Address, wasm_hash: &BytesN<32>) {
+        Self::require_upgrade_target(env, target);
+        env.invoke_contract::<()>(
+            target,
+            &Symbol::new(env, "upgrade"),
+            vec![env, wasm_hash.into_val(env)],
+        );
     }
 }
 
@@ -245,10 +275,34 @@ impl ContractUpgradeGovernance {
         GuardianSetChanged { guardians: new_guardians }.publish(&env);
     }
 
+    /// Allow or disallow `target` as an upgrade target (admin only).
+    /// Only contracts with the governed `upgrade` entrypoint can be allowed, and
+    /// they must already report this contract from `get_governance`.
+    pub fn set_upgrade_target(env: Env, target: Address, allowed: bool) {
+        Self::require_admin(&env);
+        if allowed {
+            let governance: Option<Address> = env.invoke_contract(
+                &target,
+                &Symbol::new(&env, "get_governance"),
+                vec![&env],
+            );
+            if governance != Some(env.current_contract_address()) {
+                panic_with_error!(&env, UpgradeError::TargetNotAllowed);
+            }
+        }
+        env.storage().persistent().set(&DataKey::UpgradeTarget(target.clone()), &allowed);
+        UpgradeTargetChanged { target, allowed }.publish(&env);
+    }
+
+    pub fn is_upgrade_target(env: Env, target: Address) -> bool {
+        env.storage().persistent()
+            .get(&DataKey::UpgradeTarget(target)).unwrap_or(false)
+    }
+
     pub fn propose_upgrade(
         env: Env,
         proposer: Address,
-        target_contract: String,
+        target_contract: Address,
         new_wasm_hash: BytesN<32>,
         previous_wasm_hash: BytesN<32>,
         description: String,
@@ -257,6 +311,7 @@ impl ContractUpgradeGovernance {
         Self::require_not_paused(&env);
         if !Self::is_guardian(&env, &proposer) { panic_with_error!(&env, UpgradeError::NotGuardian); }
         proposer.require_auth();
+        Self::require_upgrade_target(&env, &target_contract);
 
         let count: u64 = env.storage().instance()
             .get(&DataKey::ProposalCount).unwrap_or(0);
@@ -337,7 +392,9 @@ impl ContractUpgradeGovernance {
     }
 
     /// Execute an approved upgrade after the timelock has expired.
-    /// Records the rollback slot for the old WASM hash.
+    /// Calls `upgrade(new_wasm_hash)` on the target contract and records the
+    /// rollback slot for the old WASM hash. `new_wasm_hash` must match the
+    /// hash the guardians approved.
     pub fn execute_upgrade(env: Env, proposal_id: u64, executor: Address, new_wasm_hash: BytesN<32>) {
         Self::require_initialized(&env);
         Self::require_not_paused(&env);
@@ -357,21 +414,26 @@ impl ContractUpgradeGovernance {
         if now < proposal.executable_at {
             panic_with_error!(&env, UpgradeError::TimelockNotExpired);
         }
+        if new_wasm_hash != proposal.new_wasm_hash { panic_with_error!(&env, UpgradeError::InvalidArgument); }
 
-        Self::save_rollback_slot(&env, proposal.previous_wasm_hash.clone(), proposal.target_contract.clone());
+        Self::save_rollback_slot(&env, proposal_id, proposal.previous_wasm_hash.clone(), proposal.target_contract.clone());
         env.storage().persistent().set(&DataKey::RollbackConsumed, &false);
 
         proposal.state = ProposalState::Executed;
         env.storage().persistent().set(&DataKey::Proposal(proposal_id), &proposal);
 
+        Self::invoke_upgrade(&env, &proposal.target_contract, &new_wasm_hash);
+
         UpgradeExecuted {
             proposal_id,
+            target_contract: proposal.target_contract.clone(),
             new_wasm_hash,
             previous_wasm_hash: proposal.previous_wasm_hash.clone(),
         }.publish(&env);
     }
 
-    /// Rollback to the previous WASM version.
+    /// Rollback the last executed upgrade by calling `upgrade(previous_wasm_hash)`
+    /// on its target contract.
     /// Admin can rollback directly (emergency). Guardians need rollback to be available.
     pub fn rollback_upgrade(env: Env, caller: Address, previous_wasm_hash: BytesN<32>) {
         Self::require_initialized(&env);
@@ -396,9 +458,21 @@ impl ContractUpgradeGovernance {
             .get(&DataKey::RollbackWasmHash).expect("no rollback slot");
         if stored_hash != previous_wasm_hash { panic_with_error!(&env, UpgradeError::InvalidArgument); }
 
+        let target: Address = env.storage().persistent()
+            .get(&DataKey::RollbackContractId).expect("no rollback slot");
+        let proposal_id: u64 = env.storage().persistent()
+            .get(&DataKey::RollbackProposalId).unwrap_or(0);
+
         env.storage().persistent().set(&DataKey::RollbackConsumed, &true);
+        if let Some(mut proposal) = env.storage().persistent()
+            .get::<_, UpgradeProposal>(&DataKey::Proposal(proposal_id)) {
+            proposal.state = ProposalState::RolledBack;
+            env.storage().persistent().set(&DataKey::Proposal(proposal_id), &proposal);
+        }
+
+        Self::invoke_upgrade(&env, &target, &previous_wasm_hash);
 
-        UpgradeRolledBack { proposal_id: 0, restored_wasm_hash: previous_wasm_hash }.publish(&env);
+        UpgradeRolledBack { proposal_id, target_contract: target, restored_wasm_hash: previous_wasm_hash }.publish(&env);
     }
 
     /// Cancel a proposal (admin only).
@@ -1,11 +1,46 @@
 #![cfg(test)]
 
 use soroban_sdk::{
-    testutils::{Address as _, Ledger}, vec, Address, BytesN, Env, String,
+    contract, contractimpl, symbol_short, testutils::{Address as _, Ledger}, vec, Address,
+    BytesN, Env, String,
 };
 
 use super::*;
 
+/// Stand-in for a SYNCRO contract: exposes the standard governance-gated
+/// `upgrade` entrypoint and records the hash instead of swapping its WASM.
+#[contract]
+struct UpgradeTarget;
+
+#[contractimpl]
+impl UpgradeTarget {
+    pub fn set_governance(env: Env, governance: Address) {
+        env.storage().instance().set(&symbol_short!("gov"), &governance);
+    }
+
+    pub fn get_governance(env: Env) -> Option<Address> {
+        env.storage().instance().get(&symbol_short!("gov"))
+    }
+
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        let governance: Address = env.storage().instance()
+            .get(&symbol_short!("gov")).expect("governance not set");
+        governance.require_auth();
+        env.storage().instance().set(&symbol_short!("wasm"), &new_wasm_hash);
+    }
+
+    pub fn current_wasm(env: Env) -> Option<BytesN<32>> {
+        env.storage().instance().get(&symbol_short!("wasm"))
+    }
+}
+
+fn register_target(env: &Env, governance: &Address) -> Address {
+    let target = env.register(UpgradeTarget, ());
+    UpgradeTargetClient::new(env, &target).set_governance(governance);
+    ContractUpgradeGovernanceClient::new(env, governance).set_upgrade_target(&target, &true);
+    target
+}
+
 fn setup() -> (Env, Address, Address, Address, Address) {
     let env = Env::default();
     env.mock_all_auths();
@@ -91,7 +126,7 @@ fn test_propose_upgrade() {
     let guardians = vec![&env, g1.clone(), g2.clone()];
     client.init(&_admin, &guardians);
 
-    let target = String::from_str(&env, "CAFEBABE");
+    let target = register_target(&env, &contract_id);
     let new_hash = BytesN::from_array(&env, &[1u8; 32]);
     let old_hash = BytesN::from_array(&env, &[0u8; 32]);
     let desc = String::from_str(&env, "Upgrade v2");
@@ -116,7 +151,7 @@ fn test_approve_upgrade_reaches_threshold() {
     let guardians = vec![&env, g1.clone(), g2.clone(), g3.clone()];
     client.init(&_admin, &guardians);
 
-    let target = String::from_str(&env, "CAFEBABE");
+    let target = register_target(&env, &contract_id);
     let new_hash = BytesN::from_array(&env, &[2u8; 32]);
     let old_hash = BytesN::from_array(&env, &[1u8; 32]);
 
@@ -144,7 +179,7 @@ fn test_duplicate_approval_rejected() {
     let guardians = vec![&env, g1.clone(), g2.clone()];
     client.init(&_admin, &guardians);
 
-    let target = String::from_str(&env, "CAFEBABE");
+    let target = register_target(&env, &contract_id);
     let new_hash = BytesN::from_array(&env, &[2u8; 32]);
     let old_hash = BytesN::from_array(&env, &[1u8; 32]);
     let pid = client.propose_upgrade(&g1, &target, &new_hash, &old_hash, &String::from_str(&env, "v2"));
@@ -162,7 +197,7 @@ fn test_execute_upgrade_after_timelock() {
     let guardians = vec![&env, g1.clone(), g2.clone()];
     client.init(&_admin, &guardians);
 
-    let target = String::from_str(&env, "CAFEBABE");
+    let target = register_target(&env, &contract_id);
     let new_hash = BytesN::from_array(&env, &[2u8; 32]);
     let old_hash = BytesN::from_array(&env, &[1u8; 32]);
 
@@ -182,6 +217,7 @@ fn test_execute_upgrade_after_timelock() {
 
     let executed = client.get_proposal(&pid);
     assert_eq!(executed.state, ProposalState::Executed);
+    assert_eq!(UpgradeTargetClient::new(&env, &target).current_wasm(), Some(new_hash));
 
     // Rollback should be available
     assert!(client.is_rollback_available());
@@ -198,7 +234,7 @@ fn test_rollback_upgrade() {
     let guardians = vec![&env, g1.clone(), g2.clone()];
     client.init(&admin, &guardians);
 
-    let target = String::from_str(&env, "CAFEBABE");
+    let target = register_target(&env, &contract_id);
     let new_hash = BytesN::from_array(&env, &[2u8; 32]);
     let old_hash = BytesN::from_array(&env, &[1u8; 32]);
 
@@ -208,9 +244,37 @@ fn test_rollback_upgrade() {
     env.ledger().set_timestamp(env.ledger().timestamp() + DEFAULT_TIMELOCK_SECONDS + 1);
     client.execute_upgrade(&pid, &g1, &new_hash);
 
+    let target_client = UpgradeTargetClient::new(&env, &target);
+    assert_eq!(target_client.current_wasm(), Some(new_hash));
+
     // Admin rollback
     client.rollback_upgrade(&admin, &old_hash);
     assert!(!client.is_rollback_available());
+    assert_eq!(target_client.current_wasm(), Some(old_hash));
+    assert_eq!(client.get_proposal(&pid).state, ProposalState::RolledBack);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #14)")]
+fn test_execute_with_unapproved_hash_fails() {
+    let (env, admin, g1, g2, _g3) = setup();
+    let contract_id = env.register(ContractUpgradeGovernance, ());
+    let client = ContractUpgradeGovernanceClient::new(&env, &contract_id);
+
+    let guardians = vec![&env, g1.clone(), g2.clone()];
+    client.init(&admin, &guardians);
+
+    let target = register_target(&env, &contract_id);
+    let new_hash = BytesN::from_array(&env, &[2u8; 32]);
+    let old_hash = BytesN::from_array(&env, &[1u8; 32]);
+
+    let pid = client.propose_upgrade(&g1, &target, &new_hash, &old_hash, &String::from_str(&env, "v2"));
+    client.approve_upgrade(&pid, &g1);
+    client.approve_upgrade(&pid, &g2);
+    env.ledger().set_timestamp(env.ledger().timestamp() + DEFAULT_TIMELOCK_SECONDS + 1);
+
+    // Only the hash the guardians approved may be installed
+    client.execute_upgrade(&pid, &g1, &BytesN::from_array(&env, &[9u8; 32]));
 }
 
 #[test]
@@ -223,7 +287,7 @@ fn test_rollback_cannot_be_replayed() {
     let guardians = vec![&env, g1.clone(), g2.clone()];
     client.init(&admin, &guardians);
 
-    let target = String::from_str(&env, "CAFEBABE");
+    let target = register_target(&env, &contract_id);
     let new_hash = BytesN::from_array(&env, &[2u8; 32]);
     let old_hash = BytesN::from_array(&env, &[1u8; 32]);
 
@@ -247,7 +311,7 @@ fn test_execute_before_timelock_fails() {
     let guardians = vec![&env, g1.clone(), g2.clone()];
     client.init(&_admin, &guardians);
 
-    let target = String::from_str(&env, "CAFEBABE");
+    let target = register_target(&env, &contract_id);
     let new_hash = BytesN::from_array(&env, &[2u8; 32]);
     let old_hash = BytesN::from_array(&env, &[1u8; 32]);
 
@@ -328,7 +392,7 @@ fn test_cancel_proposal() {
 
     let pid = client.propose_upgrade(
         &g1,
-        &String::from_str(&env, "CAFE"),
+        &register_target(&env, &contract_id),
         &BytesN::from_array(&env, &[2u8; 32]),
         &BytesN::from_array(&env, &[1u8; 32]),
         &String::from_str(&env, "v2"),
@@ -338,3 +402,58 @@ fn test_cancel_proposal() {
     let proposal = client.get_proposal(&pid);
     assert_eq!(proposal.state, ProposalState::Cancelled);
 }
+
+#[test]
+#[should_panic(expected = "Error(Contract, #15)")]
+fn test_propose_for_unlisted_target_fails() {
+    let (env, admin, g1, g2, _g3) = setup();
+    let contract_id = env.register(ContractUpgradeGovernance, ());
+    let client = ContractUpgradeGovernanceClient::new(&env, &contract_id);
+    client.init(&admin, &vec![&env, g1.clone(), g2.clone()]);
+
+    // Governed by this contract, but never allowed by the admin
+    let target = env.register(UpgradeTarget, ());
+    UpgradeTargetClient::new(&env, &target).set_governance(&contract_id);
+    assert!(!client.is_upgrade_target(&target));
+
+    client.propose_upgrade(
+        &g1,
+        &target,
+        &BytesN::from_array(&env, &[2u8; 32]),
+        &BytesN::from_array(&env, &[1u8; 32]),
+        &String::from_str(&env, "v2"),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #15)")]
+fn test_allow_target_governed_elsewhere_fails() {
+    let (env, admin, g1, g2, _g3) = setup();
+    let contract_id = env.register(ContractUpgradeGovernance, ());
+    let client = ContractUpgradeGovernanceClient::new(&env, &contract_id);
+    client.init(&admin, &vec![&env, g1, g2]);
+
+    let target = env.register(UpgradeTarget, ());
+    UpgradeTargetClient::new(&env, &target).set_governance(&Address::generate(&env));
+    client.set_upgrade_target(&target, &true);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #15)")]
+fn test_execute_after_target_removed_fails() {
+    let (env, admin, g1, g2, _g3) = setup();
+    let contract_id = env.register(ContractUpgradeGovernance, ());
+    let client = ContractUpgradeGovernanceClient::new(&env, &contract_id);
+    client.init(&admin, &vec![&env, g1.clone(), g2.clone()]);
+
+    let target = register_target(&env, &contract_id);
+    let new_hash = BytesN::from_array(&env, &[2u8; 32]);
+    let old_hash = BytesN::from_array(&env, &[1u8; 32]);
+    let pid = client.propose_upgrade(&g1, &target, &new_hash, &old_hash, &String::from_str(&env, "v2"));
+    client.approve_upgrade(&pid, &g1);
+    client.approve_upgrade(&pid, &g2);
+    env.ledger().set_timestamp(env.ledger().timestamp() + DEFAULT_TIMELOCK_SECONDS + 1);
+
+    client.set_upgrade_target(&target, &false);
+    client.execute_upgrade(&pid, &g1, &new_hash);
+}
@@ -0,0 +1,44 @@
+#![no_std]
+
+//! Governance-gated `upgrade` entrypoint shared by the SYNCRO contracts that
+//! `ContractUpgradeGovernance` can target.
+//!
+//! A contract stores the address of its governance contract and only lets that
+//! address replace its WASM. The contract admin registers the governance contract
+//! once; after that only the current governance contract can hand over to another
+//! one, so the admin cannot bypass the governance timelock.
+
+use soroban_sdk::{contracttype, Address, BytesN, Env};
+
+#[contracttype]
+#[derive(Clone)]
+enum GovernanceKey {
+    Governance,
+}
+
+/// Store `governance` as the contract allowed to call [`upgrade`].
+///
+/// The first call is authorized by `require_admin`; later calls need the auth of
+/// the governance contract that is currently set.
+pub fn set_governance(env: &Env, governance: &Address, require_admin: impl FnOnce()) {
+    match get_governance(env) {
+        Some(current) => current.require_auth(),
+        None => require_admin(),
+    }
+    env.storage()
+        .instance()
+        .set(&GovernanceKey::Governance, governance);
+}
+
+/// The configured governance contract, if any.
+pub fn get_governance(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&GovernanceKey::Governance)
+}
+
+/// Replace the current contract's WASM. Requires the governance contract's auth,
+/// which holds when it invokes `upgrade` from `execute_upgrade` / `rollback_upgrade`.
+pub fn upgrade(env: &Env, new_wasm_hash: BytesN<32>) {
+    let governance = get_governance(env).expect("Governance not set");
+    governance.require_auth();
+    env.deployer().update_current_contract_wasm(new_wasm_hash);
+}
@@ -9,6 +9,7 @@ use soroban_sdk::{
 enum DataKey {
     Admin,
     Token(Address),
+    Caller(Address),
 }
 
 #[contracttype]
@@ -49,6 +50,12 @@ pub struct TokenAllowlistRevoked {
     pub token: Address,
 }
 
+#[contractevent]
+pub struct CallerUpdated {
+    pub caller: Address,
+    pub allowed: bool,
+}
+
 #[contractevent]
 pub struct RenewalSettled {
     pub payer: Address,
@@ -152,8 +159,31 @@ impl PaymentAdapterContract {
         Ok(())
     }
 
+    /// Allow or disallow `caller` (e.g. the renewal contract) to settle renewals.
+    pub fn set_caller(env: Env, caller: Address, allowed: bool) -> Result<(), PaymentAdapterError> {
+        Self::require_admin(&env)?;
+
+        let key = DataKey::Caller(caller.clone());
+        if allowed {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+
+        CallerUpdated { caller, allowed }.publish(&env);
+        Ok(())
+    }
+
+    pub fn is_caller(env: Env, caller: Address) -> bool {
+        env.storage().persistent().has(&DataKey::Caller(caller))
+    }
+
+    /// Pull `amount_display_units` from `payer` to `merchant`. `caller` must be
+    /// registered with `set_caller` and authorize the call; the payer does not sign,
+    /// the funds move through the allowance the payer granted to this contract.
     pub fn settle_renewal(
         env: Env,
+        caller: Address,
         payer: Address,
         merchant: Address,
         token: Address,
@@ -163,7 +193,10 @@ impl PaymentAdapterContract {
             return Err(PaymentAdapterError::InvalidAmount);
         }
 
-        payer.require_auth();
+        caller.require_auth();
+        if !Self::is_caller(env.clone(), caller) {
+            return Err(PaymentAdapterError::Unauthorized);
+        }
 
         let mut policy = Self::load_policy(&env, &token)?;
         if !policy.active {
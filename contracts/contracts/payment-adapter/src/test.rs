@@ -2,14 +2,15 @@
 
 use super::*;
 use soroban_sdk::{
-    testutils::Address as _,
+    testutils::{Address as _, MockAuth, MockAuthInvoke},
     token::{StellarAssetClient, TokenClient},
-    Address, Env,
+    Address, Env, IntoVal,
 };
 
 struct Ctx {
     env: Env,
     admin: Address,
+    caller: Address,
     payer: Address,
     merchant: Address,
     token: Address,
@@ -23,6 +24,7 @@ fn setup() -> Ctx {
     env.mock_all_auths();
 
     let admin = Address::generate(&env);
+    let caller = Address::generate(&env);
     let payer = Address::generate(&env);
     let merchant = Address::generate(&env);
 
@@ -34,12 +36,14 @@ fn setup() -> Ctx {
     let contract_id = env.register_contract(None, PaymentAdapterContract);
     let client = PaymentAdapterContractClient::new(&env, &contract_id);
     client.init(&admin);
+    client.set_caller(&caller, &true);
 
     token_client.approve(&payer, &contract_id, &1_000_000_000i128, &1_000_000u32);
 
     Ctx {
         env,
         admin,
+        caller,
         payer,
         merchant,
         token,
@@ -57,9 +61,9 @@ fn allowlist_and_settle_with_decimals_awareness() {
     ctx.client.allow_token(&ctx.token, &100);
     assert!(ctx.client.is_allowed(&ctx.token));
 
-    let transferred_raw = ctx
-        .client
-        .settle_renewal(&ctx.payer, &ctx.merchant, &ctx.token, &5);
+    let transferred_raw =
+        ctx.client
+            .settle_renewal(&ctx.caller, &ctx.payer, &ctx.merchant, &ctx.token, &5);
 
     let factor = 10i128.pow(decimals);
     assert_eq!(transferred_raw, 5 * factor);
@@ -77,13 +81,13 @@ fn cap_exceeded_and_revocation_are_enforced() {
 
     ctx.client.allow_token(&ctx.token, &10);
     ctx.client
-        .settle_renewal(&ctx.payer, &ctx.merchant, &ctx.token, &10);
+        .settle_renewal(&ctx.caller, &ctx.payer, &ctx.merchant, &ctx.token, &10);
 
     assert_eq!(ctx.client.available(&ctx.token), 0);
 
     assert!(ctx
         .client
-        .try_settle_renewal(&ctx.payer, &ctx.merchant, &ctx.token, &1)
+        .try_settle_renewal(&ctx.caller, &ctx.payer, &ctx.merchant, &ctx.token, &1)
         .is_err());
 
     ctx.client.revoke_token(&ctx.token);
@@ -91,19 +95,68 @@ fn cap_exceeded_and_revocation_are_enforced() {
 
     assert!(ctx
         .client
-        .try_settle_renewal(&ctx.payer, &ctx.merchant, &ctx.token, &1)
+        .try_settle_renewal(&ctx.caller, &ctx.payer, &ctx.merchant, &ctx.token, &1)
         .is_err());
 }
 
 #[test]
 fn non_allowlisted_token_is_rejected() {
     let ctx = setup();
-    let other_sac = ctx.env.register_stellar_asset_contract_v2(ctx.admin.clone());
+    let other_sac = ctx
+        .env
+        .register_stellar_asset_contract_v2(ctx.admin.clone());
     let other_token = other_sac.address();
 
     assert!(ctx
         .client
-        .try_settle_renewal(&ctx.payer, &ctx.merchant, &other_token, &1)
+        .try_settle_renewal(&ctx.caller, &ctx.payer, &ctx.merchant, &other_token, &1)
         .is_err());
     assert_eq!(ctx.client.available(&other_token), 0);
 }
+
+#[test]
+fn unregistered_caller_cannot_settle() {
+    let ctx = setup();
+    ctx.client.allow_token(&ctx.token, &100);
+
+    let stranger = Address::generate(&ctx.env);
+    let result =
+        ctx.client
+            .try_settle_renewal(&stranger, &ctx.payer, &ctx.merchant, &ctx.token, &1);
+    assert_eq!(result, Err(Ok(PaymentAdapterError::Unauthorized)));
+
+    ctx.client.set_caller(&ctx.caller, &false);
+    let result =
+        ctx.client
+            .try_settle_renewal(&ctx.caller, &ctx.payer, &ctx.merchant, &ctx.token, &1);
+    assert_eq!(result, Err(Ok(PaymentAdapterError::Unauthorized)));
+    assert_eq!(ctx.token_client.balance(&ctx.merchant), 0);
+}
+
+#[test]
+fn settle_needs_only_caller_auth() {
+    let ctx = setup();
+    ctx.client.allow_token(&ctx.token, &100);
+
+    ctx.env.mock_auths(&[MockAuth {
+        address: &ctx.caller,
+        invoke: &MockAuthInvoke {
+            contract: &ctx.contract_id,
+            fn_name: "settle_renewal",
+            args: (
+                ctx.caller.clone(),
+                ctx.payer.clone(),
+                ctx.merchant.clone(),
+                ctx.token.clone(),
+                1i128,
+            )
+                .into_val(&ctx.env),
+            sub_invokes: &[],
+        },
+    }]);
+    ctx.client
+        .settle_renewal(&ctx.caller, &ctx.payer, &ctx.merchant, &ctx.token, &1);
+
+    let factor = 10i128.pow(ctx.token_client.decimals());
+    assert_eq!(ctx.token_client.balance(&ctx.merchant), factor);
+}
@@ -1,25 +1,80 @@
 use soroban_sdk::{
-    contract, contractevent, contractimpl, contracttype, vec, xdr::ToXdr, Address, Bytes, BytesN, Env,
-    String, Vec,
+    contract, contractevent, contractimpl, contracttype, vec, xdr::ToXdr, Address, Bytes, BytesN,
+    Env, String, Vec,
 };
 
+/// Entries whose TTL drops below this many ledgers (~7 days) are extended on access.
+pub const TTL_THRESHOLD: u32 = 120_960;
+
+/// Ledgers an entry is kept live for when it is extended on access (~30 days).
+pub const TTL_EXTEND_TO: u32 = 518_400;
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SubscriptionMetadata {
+    pub owner: Address,
     pub service_id: String,
     pub billing_interval: u64,
     pub expected_amount: i128,
     pub next_renewal: u64,
     pub is_active: bool,
+    pub encrypted_blob: Bytes,
     pub encrypted_data: Option<String>,
+    /// Renewal contract that bills this subscription and reports renewals back
+    pub renewal_contract: Option<Address>,
+    /// Subscription id in `renewal_contract` (0 if not linked)
+    pub renewal_sub_id: u64,
+}
+
+/// Largest number of recipients an encrypted envelope can be shared with.
+pub const MAX_RECIPIENTS: u32 = 8;
+
+/// Why a recipient can decrypt a subscription's envelope.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum RecipientRole {
+    Owner = 1,
+    Merchant = 2,
+    /// Accountant or other read-only auditor
+    Auditor = 3,
+}
+
+/// Subscription details encrypted once under a per-version content key.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EncryptedEnvelope {
+    pub ciphertext: Bytes,
+    /// Version of the content key `ciphertext` is encrypted under
+    pub key_version: u32,
+    pub updated_at: u64,
+}
+
+/// The envelope's content key wrapped to one recipient's public key.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecipientKey {
+    pub recipient: Address,
+    pub role: RecipientRole,
+    pub key_version: u32,
+    pub wrapped_key: Bytes,
 }
 
+/// `Subscription`, `UserSubscriptions`, `Archived` and the envelope entries live
+/// in persistent storage, one entry per key; only the counter is kept in
+/// instance storage.
 #[contracttype]
 #[derive(Clone)]
 pub enum DataKey {
     UserSubscriptions(Address),
     Subscription(BytesN<32>),
     SubscriptionCounter,
+    /// SHA-256 of the XDR metadata of an archived subscription
+    Archived(BytesN<32>),
+    Envelope(BytesN<32>),
+    /// Recipients holding a wrapped key for the current key version
+    Recipients(BytesN<32>),
+    WrappedKey(BytesN<32>, Address, u32),
 }
 
 #[contractevent]
@@ -54,6 +109,55 @@ pub struct SubscriptionCancelledEvent {
     pub service_id: String,
 }
 
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubscriptionLinkedEvent {
+    pub subscription_id: BytesN<32>,
+    pub user: Address,
+    pub renewal_contract: Address,
+    pub renewal_sub_id: u64,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnvelopeKeyRotatedEvent {
+    pub subscription_id: BytesN<32>,
+    pub key_version: u32,
+    pub recipients: Vec<Address>,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecipientGrantedEvent {
+    pub subscription_id: BytesN<32>,
+    pub recipient: Address,
+    pub role: RecipientRole,
+    pub key_version: u32,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecipientRevokedEvent {
+    pub subscription_id: BytesN<32>,
+    pub recipient: Address,
+    pub key_version: u32,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubscriptionArchivedEvent {
+    pub subscription_id: BytesN<32>,
+    pub user: Address,
+    pub metadata_hash: BytesN<32>,
+}
+
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubscriptionRestoredEvent {
+    pub subscription_id: BytesN<32>,
+    pub user: Address,
+}
+
 #[contract]
 pub struct SubscriptionRegistry;
 
@@ -80,14 +184,10 @@ impl SubscriptionRegistry {
             panic!("next_renewal must be greater than 0");
         }
 
-        let mut user_subs: Vec<BytesN<32>> = env
-            .storage()
-            .instance()
-            .get(&DataKey::UserSubscriptions(user.clone()))
-            .unwrap_or_else(|| vec![&env]);
+        let mut user_subs = Self::get_user_subscriptions(env.clone(), user.clone());
 
         for sub_id in user_subs.iter() {
-            if let Some(meta) = env.storage().instance().get::<_, SubscriptionMetadata>(&DataKey::Subscription(sub_id)) {
+            if let Some(meta) = Self::load(&env, &sub_id) {
                 if meta.service_id == service_id && meta.is_active {
                     panic!("duplicate subscription for service");
                 }
@@ -104,6 +204,9 @@ impl SubscriptionRegistry {
         env.storage()
             .instance()
             .set(&DataKey::SubscriptionCounter, &new_counter);
+        env.storage()
+            .instance()
+            .extend_ttl(TTL_THRESHOLD, TTL_EXTEND_TO);
 
         // Create deterministic subscription ID from counter and user hash
         let mut id_bytes = [0u8; 32];
@@ -115,21 +218,23 @@ impl SubscriptionRegistry {
         let subscription_id = BytesN::from_array(&env, &id_bytes);
 
         let metadata = SubscriptionMetadata {
+            owner: user.clone(),
             service_id: service_id.clone(),
             billing_interval,
             expected_amount,
             next_renewal,
             is_active: true,
+            encrypted_blob: encrypted_blob.clone(),
             encrypted_data: None,
+            renewal_contract: None,
+            renewal_sub_id: 0,
         };
-        env.storage()
-            .instance()
-            .set(&DataKey::Subscription(subscription_id.clone()), &metadata);
+        Self::save(&env, &subscription_id, &metadata);
 
         user_subs.push_back(subscription_id.clone());
-        env.storage()
-            .instance()
-            .set(&DataKey::UserSubscriptions(user.clone()), &user_subs);
+        let user_key = DataKey::UserSubscriptions(user.clone());
+        env.storage().persistent().set(&user_key, &user_subs);
+        Self::bump(&env, &user_key);
 
         SubscriptionCreatedEvent {
             subscription_id: subscription_id.clone(),
@@ -146,6 +251,8 @@ impl SubscriptionRegistry {
     }
 
     /// Update an existing subscription's metadata
+    // One optional argument per field keeps partial updates a single call
+    #[allow(clippy::too_many_arguments)]
     pub fn update_subscription(
         env: Env,
         subscription_id: BytesN<32>,
@@ -157,11 +264,7 @@ impl SubscriptionRegistry {
         encrypted_blob: Option<Bytes>,
     ) {
         user.require_auth();
-        let mut metadata: SubscriptionMetadata = env
-            .storage()
-            .instance()
-            .get(&DataKey::Subscription(subscription_id.clone()))
-            .unwrap_or_else(|| panic!("subscription not found"));
+        let mut metadata = Self::load_owned(&env, &subscription_id, &user);
 
         if !metadata.is_active {
             panic!("subscription is not active");
@@ -192,9 +295,7 @@ impl SubscriptionRegistry {
             metadata.encrypted_blob = eb;
         }
 
-        env.storage()
-            .instance()
-            .set(&DataKey::Subscription(subscription_id.clone()), &metadata);
+        Self::save(&env, &subscription_id, &metadata);
 
         SubscriptionUpdatedEvent {
             subscription_id: subscription_id.clone(),
@@ -211,20 +312,14 @@ impl SubscriptionRegistry {
     /// Cancel a subscription by marking it as inactive
     pub fn cancel_subscription(env: Env, subscription_id: BytesN<32>, user: Address) {
         user.require_auth();
-        let mut metadata: SubscriptionMetadata = env
-            .storage()
-            .instance()
-            .get(&DataKey::Subscription(subscription_id.clone()))
-            .unwrap_or_else(|| panic!("subscription not found"));
+        let mut metadata = Self::load_owned(&env, &subscription_id, &user);
 
         if !metadata.is_active {
             panic!("subscription is already cancelled");
         }
 
         metadata.is_active = false;
-        env.storage()
-            .instance()
-            .set(&DataKey::Subscription(subscription_id.clone()), &metadata);
+        Self::save(&env, &subscription_id, &metadata);
 
         SubscriptionCancelledEvent {
             subscription_id: subscription_id.clone(),
@@ -242,30 +337,443 @@ impl SubscriptionRegistry {
         encrypted_data: String,
     ) {
         user.require_auth();
-        let mut metadata: SubscriptionMetadata = env
-            .storage()
-            .instance()
-            .get(&DataKey::Subscription(subscription_id.clone()))
-            .unwrap_or_else(|| panic!("subscription not found"));
-        
+        let mut metadata = Self::load_owned(&env, &subscription_id, &user);
+
         metadata.encrypted_data = Some(encrypted_data);
-        env.storage()
-            .instance()
-            .set(&DataKey::Subscription(subscription_id), &metadata);
+        Self::save(&env, &subscription_id, &metadata);
+    }
+
+    /// Link a subscription to the renewal contract subscription that bills it.
+    /// Both the owner and the renewal contract must authorize; the renewal
+    /// contract calls this from its own `link_registry`.
+    pub fn link_renewal(
+        env: Env,
+        subscription_id: BytesN<32>,
+        user: Address,
+        renewal_contract: Address,
+        renewal_sub_id: u64,
+    ) {
+        user.require_auth();
+        renewal_contract.require_auth();
+        let mut metadata = Self::load_owned(&env, &subscription_id, &user);
+        if !metadata.is_active {
+            panic!("subscription is not active");
+        }
+        if metadata.renewal_contract.is_some() {
+            panic!("subscription already linked");
+        }
+
+        metadata.renewal_contract = Some(renewal_contract.clone());
+        metadata.renewal_sub_id = renewal_sub_id;
+        Self::save(&env, &subscription_id, &metadata);
+
+        SubscriptionLinkedEvent {
+            subscription_id,
+            user,
+            renewal_contract,
+            renewal_sub_id,
+        }
+        .publish(&env);
     }
 
-    /// Get subscription metadata by ID
+    /// Hook called by the linked renewal contract after a successful renewal:
+    /// advances `next_renewal` by `billing_interval`. Inactive entries are left as is.
+    pub fn record_renewal(env: Env, subscription_id: BytesN<32>) {
+        let Some(mut metadata) = Self::load_linked(&env, &subscription_id) else {
+            return;
+        };
+        if !metadata.is_active {
+            return;
+        }
+        metadata.next_renewal = metadata
+            .next_renewal
+            .saturating_add(metadata.billing_interval);
+        Self::save(&env, &subscription_id, &metadata);
+
+        SubscriptionUpdatedEvent {
+            subscription_id,
+            user: metadata.owner.clone(),
+            service_id: metadata.service_id.clone(),
+            billing_interval: metadata.billing_interval,
+            expected_amount: metadata.expected_amount,
+            next_renewal: metadata.next_renewal,
+            encrypted_blob: metadata.encrypted_blob.clone(),
+        }
+        .publish(&env);
+    }
+
+    /// Hook called by the linked renewal contract once the subscription is
+    /// cancelled or has failed for good: marks it inactive
+    pub fn record_deactivation(env: Env, subscription_id: BytesN<32>) {
+        let Some(mut metadata) = Self::load_linked(&env, &subscription_id) else {
+            return;
+        };
+        if !metadata.is_active {
+            return;
+        }
+        metadata.is_active = false;
+        Self::save(&env, &subscription_id, &metadata);
+
+        SubscriptionCancelledEvent {
+            subscription_id,
+            user: metadata.owner.clone(),
+            service_id: metadata.service_id.clone(),
+        }
+        .publish(&env);
+    }
+
+    /// Store subscription details as an envelope readable by several recipients:
+    /// `ciphertext` is encrypted under a fresh content key, wrapped to each of
+    /// `recipients`. Each call rotates the key: `key_version` must be higher than
+    /// the current one, and recipients left out lose access to the new content.
+    /// The owner must be among the recipients with the `Owner` role.
+    pub fn store_envelope(
+        env: Env,
+        subscription_id: BytesN<32>,
+        user: Address,
+        ciphertext: Bytes,
+        key_version: u32,
+        recipients: Vec<RecipientKey>,
+    ) {
+        user.require_auth();
+        Self::load_owned(&env, &subscription_id, &user);
+        let current = Self::get_envelope(env.clone(), subscription_id.clone());
+        if current
+            .as_ref()
+            .is_some_and(|envelope| key_version <= envelope.key_version)
+        {
+            panic!("key_version must increase");
+        }
+        if recipients.len() > MAX_RECIPIENTS {
+            panic!("too many recipients");
+        }
+
+        let mut addresses: Vec<Address> = vec![&env];
+        let mut has_owner = false;
+        for key in recipients.iter() {
+            if key.key_version != key_version {
+                panic!("wrapped key version mismatch");
+            }
+            if addresses.contains(&key.recipient) {
+                panic!("duplicate recipient");
+            }
+            if key.role == RecipientRole::Owner {
+                if key.recipient != user {
+                    panic!("owner role reserved for subscription owner");
+                }
+                has_owner = true;
+            }
+            addresses.push_back(key.recipient.clone());
+        }
+        if !has_owner {
+            panic!("owner must be a recipient");
+        }
+
+        // Drop every wrapped key of the previous version
+        let storage = env.storage().persistent();
+        if let Some(previous) = current {
+            for recipient in Self::get_recipients(env.clone(), subscription_id.clone()).iter() {
+                storage.remove(&DataKey::WrappedKey(
+                    subscription_id.clone(),
+                    recipient,
+                    previous.key_version,
+                ));
+            }
+        }
+        for key in recipients.iter() {
+            let key_key =
+                DataKey::WrappedKey(subscription_id.clone(), key.recipient.clone(), key_version);
+            storage.set(&key_key, &key);
+            Self::bump(&env, &key_key);
+        }
+        let recipients_key = DataKey::Recipients(subscription_id.clone());
+        storage.set(&recipients_key, &addresses);
+        Self::bump(&env, &recipients_key);
+
+        let envelope = EncryptedEnvelope {
+            ciphertext,
+            key_version,
+            updated_at: env.ledger().timestamp(),
+        };
+        let envelope_key = DataKey::Envelope(subscription_id.clone());
+        storage.set(&envelope_key, &envelope);
+        Self::bump(&env, &envelope_key);
+
+        EnvelopeKeyRotatedEvent {
+            subscription_id,
+            key_version,
+            recipients: addresses,
+        }
+        .publish(&env);
+    }
+
+    /// Share the envelope with one more recipient under the current key version.
+    /// Granting to an existing recipient replaces its wrapped key.
+    pub fn grant_recipient(
+        env: Env,
+        subscription_id: BytesN<32>,
+        user: Address,
+        recipient_key: RecipientKey,
+    ) {
+        user.require_auth();
+        Self::load_owned(&env, &subscription_id, &user);
+        let envelope = Self::get_envelope(env.clone(), subscription_id.clone())
+            .unwrap_or_else(|| panic!("envelope not found"));
+        if recipient_key.key_version != envelope.key_version {
+            panic!("wrapped key version mismatch");
+        }
+        let recipient = recipient_key.recipient.clone();
+        if recipient_key.role == RecipientRole::Owner || recipient == user {
+            panic!("owner role reserved for subscription owner");
+        }
+
+        let storage = env.storage().persistent();
+        let mut recipients = Self::get_recipients(env.clone(), subscription_id.clone());
+        if !recipients.contains(&recipient) {
+            if recipients.len() >= MAX_RECIPIENTS {
+                panic!("too many recipients");
+            }
+            recipients.push_back(recipient.clone());
+            storage.set(&DataKey::Recipients(subscription_id.clone()), &recipients);
+        }
+        let key_key = DataKey::WrappedKey(
+            subscription_id.clone(),
+            recipient.clone(),
+            envelope.key_version,
+        );
+        storage.set(&key_key, &recipient_key);
+        Self::bump(&env, &key_key);
+
+        RecipientGrantedEvent {
+            subscription_id,
+            recipient,
+            role: recipient_key.role,
+            key_version: envelope.key_version,
+        }
+        .publish(&env);
+    }
+
+    /// Remove a recipient's wrapped key. A revoked recipient may still hold the
+    /// current content key, so the owner should follow up with `store_envelope`
+    /// under a new key version before sharing new details.
+    pub fn revoke_recipient(
+        env: Env,
+        subscription_id: BytesN<32>,
+        user: Address,
+        recipient: Address,
+    ) {
+        user.require_auth();
+        Self::load_owned(&env, &subscription_id, &user);
+        if recipient == user {
+            panic!("cannot revoke owner");
+        }
+        let envelope = Self::get_envelope(env.clone(), subscription_id.clone())
+            .unwrap_or_else(|| panic!("envelope not found"));
+        let mut recipients = Self::get_recipients(env.clone(), subscription_id.clone());
+        let index = recipients
+            .first_index_of(&recipient)
+            .unwrap_or_else(|| panic!("recipient not found"));
+
+        recipients.remove(index);
+        let storage = env.storage().persistent();
+        storage.set(&DataKey::Recipients(subscription_id.clone()), &recipients);
+        storage.remove(&DataKey::WrappedKey(
+            subscription_id.clone(),
+            recipient.clone(),
+            envelope.key_version,
+        ));
+
+        RecipientRevokedEvent {
+            subscription_id,
+            recipient,
+            key_version: envelope.key_version,
+        }
+        .publish(&env);
+    }
+
+    /// Current encrypted envelope of a subscription
+    pub fn get_envelope(env: Env, subscription_id: BytesN<32>) -> Option<EncryptedEnvelope> {
+        let key = DataKey::Envelope(subscription_id);
+        let envelope = env.storage().persistent().get(&key);
+        if envelope.is_some() {
+            Self::bump(&env, &key);
+        }
+        envelope
+    }
+
+    /// Recipients that can decrypt the current envelope
+    pub fn get_recipients(env: Env, subscription_id: BytesN<32>) -> Vec<Address> {
+        let key = DataKey::Recipients(subscription_id);
+        let recipients = env.storage().persistent().get(&key);
+        if recipients.is_some() {
+            Self::bump(&env, &key);
+        }
+        recipients.unwrap_or_else(|| vec![&env])
+    }
+
+    /// A recipient's wrapped key for the current key version
+    pub fn get_wrapped_key(
+        env: Env,
+        subscription_id: BytesN<32>,
+        recipient: Address,
+    ) -> Option<RecipientKey> {
+        let envelope = Self::get_envelope(env.clone(), subscription_id.clone())?;
+        let key = DataKey::WrappedKey(subscription_id, recipient, envelope.key_version);
+        let wrapped = env.storage().persistent().get(&key);
+        if wrapped.is_some() {
+            Self::bump(&env, &key);
+        }
+        wrapped
+    }
+
+    /// Get subscription metadata by ID; `None` if it does not exist or is archived
     pub fn get_subscription(env: Env, subscription_id: BytesN<32>) -> Option<SubscriptionMetadata> {
-        env.storage()
-            .instance()
-            .get(&DataKey::Subscription(subscription_id))
+        Self::load(&env, &subscription_id)
     }
 
-    /// Get all subscription IDs for a user
+    /// Get all subscription IDs for a user, including archived ones
     pub fn get_user_subscriptions(env: Env, user: Address) -> Vec<BytesN<32>> {
+        let key = DataKey::UserSubscriptions(user);
+        let subs = env.storage().persistent().get(&key);
+        if subs.is_some() {
+            Self::bump(&env, &key);
+        }
+        subs.unwrap_or_else(|| vec![&env])
+    }
+
+    /// Keep a subscription and the owner's subscription list live for `extend_to` ledgers
+    /// (capped at the network's max TTL), e.g. ahead of a long idle period
+    pub fn extend_subscription_ttl(
+        env: Env,
+        subscription_id: BytesN<32>,
+        user: Address,
+        extend_to: u32,
+    ) {
+        user.require_auth();
+        if extend_to == 0 {
+            panic!("extend_to must be greater than 0");
+        }
+        Self::load_owned(&env, &subscription_id, &user);
+
+        let extend_to = extend_to.min(env.storage().max_ttl());
+        let storage = env.storage().persistent();
+        storage.extend_ttl(
+            &DataKey::Subscription(subscription_id),
+            extend_to,
+            extend_to,
+        );
+        storage.extend_ttl(&DataKey::UserSubscriptions(user), extend_to, extend_to);
+    }
+
+    /// Archive a cancelled subscription: the metadata entry is removed and only its
+    /// hash is kept, so it can later be restored from an off-chain copy
+    pub fn archive_subscription(env: Env, subscription_id: BytesN<32>, user: Address) {
+        user.require_auth();
+        let metadata = Self::load_owned(&env, &subscription_id, &user);
+        if metadata.is_active {
+            panic!("only cancelled subscriptions can be archived");
+        }
+
+        let metadata_hash: BytesN<32> = env.crypto().sha256(&metadata.to_xdr(&env)).into();
         env.storage()
-            .instance()
-            .get(&DataKey::UserSubscriptions(user))
-            .unwrap_or_else(|| vec![&env])
+            .persistent()
+            .remove(&DataKey::Subscription(subscription_id.clone()));
+        let key = DataKey::Archived(subscription_id.clone());
+        env.storage().persistent().set(&key, &metadata_hash);
+        let max_ttl = env.storage().max_ttl();
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, max_ttl, max_ttl);
+
+        SubscriptionArchivedEvent {
+            subscription_id,
+            user,
+            metadata_hash,
+        }
+        .publish(&env);
+    }
+
+    /// Restore an archived subscription from its metadata, which must match the
+    /// hash recorded at archival
+    pub fn restore_subscription(
+        env: Env,
+        subscription_id: BytesN<32>,
+        user: Address,
+        metadata: SubscriptionMetadata,
+    ) {
+        user.require_auth();
+        let key = DataKey::Archived(subscription_id.clone());
+        let metadata_hash: BytesN<32> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| panic!("subscription not archived"));
+        if metadata.owner != user {
+            panic!("not subscription owner");
+        }
+        let restored_hash: BytesN<32> = env.crypto().sha256(&metadata.clone().to_xdr(&env)).into();
+        if restored_hash != metadata_hash {
+            panic!("metadata does not match archive");
+        }
+
+        env.storage().persistent().remove(&key);
+        Self::save(&env, &subscription_id, &metadata);
+
+        SubscriptionRestoredEvent {
+            subscription_id,
+            user,
+        }
+        .publish(&env);
+    }
+
+    /// Hash of an archived subscription's metadata
+    pub fn get_archived(env: Env, subscription_id: BytesN<32>) -> Option<BytesN<32>> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Archived(subscription_id))
+    }
+
+    fn load(env: &Env, subscription_id: &BytesN<32>) -> Option<SubscriptionMetadata> {
+        let key = DataKey::Subscription(subscription_id.clone());
+        let metadata = env.storage().persistent().get(&key);
+        if metadata.is_some() {
+            Self::bump(env, &key);
+        }
+        metadata
+    }
+
+    /// Load a subscription that `user` must own.
+    fn load_owned(env: &Env, subscription_id: &BytesN<32>, user: &Address) -> SubscriptionMetadata {
+        let metadata =
+            Self::load(env, subscription_id).unwrap_or_else(|| panic!("subscription not found"));
+        if metadata.owner != *user {
+            panic!("not subscription owner");
+        }
+        metadata
+    }
+
+    /// Load a subscription for a renewal hook, requiring the linked renewal
+    /// contract's authorization. Archived subscriptions are skipped.
+    fn load_linked(env: &Env, subscription_id: &BytesN<32>) -> Option<SubscriptionMetadata> {
+        let metadata = Self::load(env, subscription_id)?;
+        metadata
+            .renewal_contract
+            .as_ref()
+            .unwrap_or_else(|| panic!("subscription not linked"))
+            .require_auth();
+        Some(metadata)
+    }
+
+    fn save(env: &Env, subscription_id: &BytesN<32>, metadata: &SubscriptionMetadata) {
+        let key = DataKey::Subscription(subscription_id.clone());
+        env.storage().persistent().set(&key, metadata);
+        Self::bump(env, &key);
+    }
+
+    /// Extend `key` to `TTL_EXTEND_TO` once its TTL falls below `TTL_THRESHOLD`
+    fn bump(env: &Env, key: &DataKey) {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
     }
 }
@@ -21,6 +21,7 @@ pub enum EventTypeByte {
     SubscriptionCancel = 0x13,
     SubscriptionPause = 0x14,
     SubscriptionUnpause = 0x15,
+    UsageReport = 0x16,
 }
 
 /// Compute the on-chain commitment hash:
@@ -5,7 +5,16 @@ use soroban_sdk::{
     Vec,
 };
 
-mod commitment;
+pub mod commitment;
+
+/// Depth of the on-chain commitment accumulator (capacity 2^20 commitments)
+pub const ACCUMULATOR_DEPTH: u32 = 20;
+
+/// Accumulator state whose TTL drops below this many ledgers (~7 days) is extended.
+pub const TTL_THRESHOLD: u32 = 120_960;
+
+/// Target TTL in ledgers (~30 days) for the frontier and historical roots.
+pub const TTL_EXTEND_TO: u32 = 518_400;
 
 // ============================================================================
 // LEGACY TYPES (Preserved for backward compatibility)
@@ -62,6 +71,16 @@ pub struct MerkleRoot {
     pub timestamp: u64,
 }
 
+/// Merkle inclusion path from a commitment up to a contract-computed accumulator root
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MerkleProof {
+    /// Accumulator size the path was built against (see `get_root_at_size`)
+    pub tree_size: u64,
+    /// `ACCUMULATOR_DEPTH` sibling hashes from leaf to root
+    pub path: Vec<BytesN<32>>,
+}
+
 #[contracttype]
 #[derive(Clone)]
 enum DataKey {
@@ -72,8 +91,17 @@ enum DataKey {
     // New commitment keys (no sub_id to prevent linkage)
     CommitmentCount,        // Global counter: u64
     Commitment(u64),        // commitment_index -> AuditCommitment
+    CommitmentWriter(u64),  // commitment_index -> Address that recorded it
     MerkleRootCount,        // Number of Merkle roots anchored
     MerkleRootByIndex(u64), // root_index -> MerkleRoot
+
+    // Incremental Merkle accumulator keys
+    Frontier,             // Vec<BytesN<32>>: rightmost filled node per level
+    AccumulatorRoot(u64), // tree_size -> root after that many commitments
+
+    // Writer allowlist keys
+    Writer(Address),            // present while the address may write
+    WriterCommitments(Address), // commitments recorded by a writer: u64
 }
 
 // ============================================================================
@@ -90,6 +118,23 @@ pub struct LogAppended {
 pub struct CommitmentRecorded {
     pub commitment_index: u64,
     pub commitment_hash: BytesN<32>,
+    pub writer: Address,
+}
+
+#[contractevent]
+pub struct WriterAdded {
+    pub writer: Address,
+}
+
+#[contractevent]
+pub struct WriterRemoved {
+    pub writer: Address,
+}
+
+#[contractevent]
+pub struct AccumulatorRootUpdated {
+    pub tree_size: u64,
+    pub root: BytesN<32>,
 }
 
 #[contractevent]
@@ -130,6 +175,68 @@ impl SubscriptionLoggingContract {
         admin.require_auth();
     }
 
+    /// Set the `ContractUpgradeGovernance` contract allowed to call `upgrade`
+    /// (admin for the first call, the current governance contract afterwards).
+    pub fn set_governance(env: Env, governance: Address) {
+        governed_upgrade::set_governance(&env, &governance, || Self::require_admin(&env));
+    }
+
+    /// Query the configured governance contract, if any.
+    pub fn get_governance(env: Env) -> Option<Address> {
+        governed_upgrade::get_governance(&env)
+    }
+
+    // ========================================================================
+    // WRITER ALLOWLIST
+    // ========================================================================
+
+    /// Allow `writer` (e.g. the renewal, refund or escrow contract) to record
+    /// commitments and anchor Merkle roots. Admin only.
+    pub fn add_writer(env: Env, writer: Address) {
+        Self::require_admin(&env);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Writer(writer.clone()), &true);
+        WriterAdded { writer }.publish(&env);
+    }
+
+    /// Revoke a writer. Its commitments and counter are kept. Admin only.
+    pub fn remove_writer(env: Env, writer: Address) {
+        Self::require_admin(&env);
+        let key = DataKey::Writer(writer.clone());
+        if !env.storage().persistent().has(&key) {
+            panic!("Writer not found");
+        }
+        env.storage().persistent().remove(&key);
+        WriterRemoved { writer }.publish(&env);
+    }
+
+    /// Whether `writer` is on the allowlist
+    pub fn is_writer(env: Env, writer: Address) -> bool {
+        env.storage().persistent().has(&DataKey::Writer(writer))
+    }
+
+    /// Number of commitments recorded by `writer`
+    pub fn get_writer_commitment_count(env: Env, writer: Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::WriterCommitments(writer))
+            .unwrap_or(0)
+    }
+
+    /// Require `writer` to be allowlisted and to have authorized the call
+    fn require_writer(env: &Env, writer: &Address) {
+        if !Self::is_writer(env.clone(), writer.clone()) {
+            panic!("Writer not authorized");
+        }
+        writer.require_auth();
+    }
+
+    /// Replace this contract's WASM. Only callable by the governance contract.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        governed_upgrade::upgrade(&env, new_wasm_hash);
+    }
+
     // ========================================================================
     // LEGACY PLAINTEXT LOGGING (Deprecated, kept for backward compatibility)
     // ========================================================================
@@ -168,6 +275,7 @@ impl SubscriptionLoggingContract {
     /// Record a cryptographic commitment to an audit event
     ///
     /// # Arguments
+    /// * `writer` - Allowlisted writer recording the commitment; must authorize
     /// * `commitment_hash` - SHA-256(event_data || blinding_factor || domain_separator)
     ///
     /// # Returns
@@ -176,8 +284,8 @@ impl SubscriptionLoggingContract {
     /// # Privacy
     /// No subscription metadata is stored on-chain. The commitment reveals
     /// nothing about the underlying event without the blinding factor.
-    pub fn record_commitment(env: Env, commitment_hash: BytesN<32>) -> u64 {
-        Self::require_admin(&env);
+    pub fn record_commitment(env: Env, writer: Address, commitment_hash: BytesN<32>) -> u64 {
+        Self::require_writer(&env, &writer);
 
         // Get and increment commitment counter
         let commitment_index: u64 = env
@@ -200,15 +308,28 @@ impl SubscriptionLoggingContract {
             commitment_index,
         };
 
-        // Store commitment
+        // Store commitment, and its writer under a separate key so the
+        // `AuditCommitment` layout stays readable for earlier records
         env.storage()
             .persistent()
             .set(&DataKey::Commitment(commitment_index), &commitment);
+        env.storage()
+            .persistent()
+            .set(&DataKey::CommitmentWriter(commitment_index), &writer);
+
+        // Count it against the writer
+        let writer_key = DataKey::WriterCommitments(writer.clone());
+        let written: u64 = env.storage().persistent().get(&writer_key).unwrap_or(0);
+        env.storage().persistent().set(&writer_key, &(written + 1));
+
+        // Append to the on-chain accumulator
+        Self::append_leaf(&env, commitment_index, &commitment_hash);
 
         // Emit event
         CommitmentRecorded {
             commitment_index,
             commitment_hash,
+            writer,
         }
         .publish(&env);
 
@@ -228,6 +349,14 @@ impl SubscriptionLoggingContract {
             .get(&DataKey::Commitment(commitment_index))
     }
 
+    /// Writer that recorded a commitment. `None` for commitments recorded
+    /// before writers were tracked.
+    pub fn get_commitment_writer(env: Env, commitment_index: u64) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::CommitmentWriter(commitment_index))
+    }
+
     /// Get the total number of commitments recorded
     pub fn get_commitment_count(env: Env) -> u64 {
         env.storage()
@@ -274,6 +403,7 @@ impl SubscriptionLoggingContract {
     /// Anchor a Merkle root representing a batch of commitments
     ///
     /// # Arguments
+    /// * `writer` - Allowlisted writer anchoring the root; must authorize
     /// * `root_hash` - Root hash of Merkle tree
     /// * `start_index` - First commitment index in batch
     /// * `end_index` - Last commitment index in batch (inclusive)
@@ -281,8 +411,19 @@ impl SubscriptionLoggingContract {
     /// # Privacy
     /// Batching commitments into Merkle trees hides individual commitment
     /// timing and reduces on-chain storage costs.
-    pub fn anchor_merkle_root(env: Env, root_hash: BytesN<32>, start_index: u64, end_index: u64) {
-        Self::require_admin(&env);
+    ///
+    /// # Trust
+    /// The root is writer-supplied and not checked against the stored commitments.
+    /// Prefer `current_root`/`verify_accumulator_membership`, whose roots are
+    /// computed by the contract on every `record_commitment`.
+    pub fn anchor_merkle_root(
+        env: Env,
+        writer: Address,
+        root_hash: BytesN<32>,
+        start_index: u64,
+        end_index: u64,
+    ) {
+        Self::require_writer(&env, &writer);
 
         // Validate indices
         if end_index < start_index {
@@ -368,6 +509,76 @@ impl SubscriptionLoggingContract {
             None => return false,
         };
 
+        Self::is_anchored(
+            &env,
+            &commitment,
+            root_index,
+            &proof_path,
+            &proof_directions,
+        )
+    }
+
+    /// Verify a selectively disclosed event against its on-chain commitment
+    ///
+    /// # Arguments
+    /// * `commitment_index` - Index of the commitment being opened
+    /// * `event_type` - Event type discriminator (see `commitment::EventTypeByte`)
+    /// * `event_data_hash` - SHA-256 of the serialized event data
+    /// * `blinding_factor` - Blinding factor used when the commitment was made
+    /// * `merkle_proof` - Optional path proving the commitment is in the accumulator
+    ///
+    /// # Returns
+    /// * `bool` - True if the opening matches the stored commitment and, when a
+    ///   proof is given, the commitment is included in the accumulator root the
+    ///   contract computed for `proof.tree_size`. Writer-anchored roots are not
+    ///   trusted here.
+    ///
+    /// # Privacy
+    /// Only the disclosed event is revealed; the rest of the batch stays hidden
+    /// behind the sibling hashes in the path.
+    pub fn verify_disclosure(
+        env: Env,
+        commitment_index: u64,
+        event_type: u32,
+        event_data_hash: BytesN<32>,
+        blinding_factor: BytesN<32>,
+        merkle_proof: Option<MerkleProof>,
+    ) -> bool {
+        let commitment = match Self::get_commitment(env.clone(), commitment_index) {
+            Some(c) => c,
+            None => return false,
+        };
+
+        // Check the opening against the stored commitment
+        if !commitment::verify_commitment(
+            &env,
+            &commitment.commitment_hash,
+            event_type,
+            &event_data_hash,
+            &blinding_factor,
+        ) {
+            return false;
+        }
+
+        match merkle_proof {
+            Some(proof) => Self::verify_accumulator_membership(
+                env,
+                commitment_index,
+                proof.tree_size,
+                proof.path,
+            ),
+            None => true,
+        }
+    }
+
+    /// Check that `commitment` hashes up `proof_path` to the anchored root at `root_index`
+    fn is_anchored(
+        env: &Env,
+        commitment: &AuditCommitment,
+        root_index: u64,
+        proof_path: &Vec<BytesN<32>>,
+        proof_directions: &Vec<bool>,
+    ) -> bool {
         // Get Merkle root
         let merkle_root = match Self::get_merkle_root(env.clone(), root_index) {
             Some(r) => r,
@@ -375,6 +586,7 @@ impl SubscriptionLoggingContract {
         };
 
         // Verify commitment is in range
+        let commitment_index = commitment.commitment_index;
         if commitment_index < merkle_root.start_index || commitment_index > merkle_root.end_index {
             return false;
         }
@@ -385,34 +597,180 @@ impl SubscriptionLoggingContract {
         }
 
         // Compute root from proof
-        let mut current_hash = commitment.commitment_hash;
+        let mut current_hash = commitment.commitment_hash.clone();
 
         for i in 0..proof_path.len() {
             let sibling = proof_path.get_unchecked(i);
             let is_right = proof_directions.get_unchecked(i);
 
-            // Concatenate hashes based on direction
-            let combined = if is_right {
-                // Current is left, sibling is right
-                let mut bytes = Bytes::new(&env);
-                bytes.extend_from_slice(&current_hash.to_array());
-                bytes.extend_from_slice(&sibling.to_array());
-                bytes
+            // Current is left when the sibling is on the right
+            current_hash = if is_right {
+                Self::hash_pair(env, &current_hash, &sibling)
             } else {
-                // Sibling is left, current is right
-                let mut bytes = Bytes::new(&env);
-                bytes.extend_from_slice(&sibling.to_array());
-                bytes.extend_from_slice(&current_hash.to_array());
-                bytes
+                Self::hash_pair(env, &sibling, &current_hash)
             };
-
-            // Hash combined value and convert to BytesN<32>
-            current_hash = env.crypto().sha256(&combined).into();
         }
 
         // Compare computed root with stored root
         current_hash == merkle_root.root_hash
     }
+
+    // ========================================================================
+    // INCREMENTAL MERKLE ACCUMULATOR
+    // ========================================================================
+
+    /// Root of the accumulator over every commitment recorded so far
+    ///
+    /// # Returns
+    /// * `BytesN<32>` - Root of the depth-`ACCUMULATOR_DEPTH` tree, with unfilled
+    ///   leaves set to zero
+    pub fn current_root(env: Env) -> BytesN<32> {
+        let tree_size = Self::get_commitment_count(env.clone());
+        match Self::get_root_at_size(env.clone(), tree_size) {
+            Some(root) => root,
+            None => Self::empty_root(&env),
+        }
+    }
+
+    /// Root of the accumulator as it was after `tree_size` commitments
+    ///
+    /// # Returns
+    /// * `Option<BytesN<32>>` - The historical root, if that size was ever reached
+    pub fn get_root_at_size(env: Env, tree_size: u64) -> Option<BytesN<32>> {
+        if tree_size == 0 {
+            return Some(Self::empty_root(&env));
+        }
+        let key = DataKey::AccumulatorRoot(tree_size);
+        let root = env.storage().persistent().get(&key);
+        if root.is_some() {
+            env.storage()
+                .persistent()
+                .extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+        }
+        root
+    }
+
+    /// Verify a commitment is included in the accumulator at a past tree size
+    ///
+    /// # Arguments
+    /// * `commitment_index` - Index of commitment to verify (its leaf position)
+    /// * `tree_size` - Number of commitments in the tree the proof was built against
+    /// * `proof_path` - `ACCUMULATOR_DEPTH` sibling hashes from leaf to root
+    ///
+    /// # Returns
+    /// * `bool` - True if the path authenticates the commitment under the
+    ///   contract-computed root for `tree_size`
+    pub fn verify_accumulator_membership(
+        env: Env,
+        commitment_index: u64,
+        tree_size: u64,
+        proof_path: Vec<BytesN<32>>,
+    ) -> bool {
+        if commitment_index >= tree_size || proof_path.len() != ACCUMULATOR_DEPTH {
+            return false;
+        }
+
+        let commitment = match Self::get_commitment(env.clone(), commitment_index) {
+            Some(c) => c,
+            None => return false,
+        };
+        let root = match Self::get_root_at_size(env.clone(), tree_size) {
+            Some(r) => r,
+            None => return false,
+        };
+
+        // Directions come from the leaf position: bit 0 at a level means current is left
+        let mut current_hash = commitment.commitment_hash;
+        let mut position = commitment_index;
+        for sibling in proof_path.iter() {
+            current_hash = if position & 1 == 0 {
+                Self::hash_pair(&env, &current_hash, &sibling)
+            } else {
+                Self::hash_pair(&env, &sibling, &current_hash)
+            };
+            position >>= 1;
+        }
+
+        current_hash == root
+    }
+
+    /// Insert `leaf` at position `leaf_index` and store the resulting root
+    fn append_leaf(env: &Env, leaf_index: u64, leaf: &BytesN<32>) {
+        if leaf_index >= 1u64 << ACCUMULATOR_DEPTH {
+            panic!("Accumulator is full");
+        }
+
+        let zero = BytesN::from_array(env, &[0u8; 32]);
+        let mut frontier: Vec<BytesN<32>> = env
+            .storage()
+            .instance()
+            .get(&DataKey::Frontier)
+            .unwrap_or_else(|| {
+                let mut empty = Vec::new(env);
+                for _ in 0..ACCUMULATOR_DEPTH {
+                    empty.push_back(zero.clone());
+                }
+                empty
+            });
+
+        // Fold the new leaf into the frontier: merge with filled left siblings
+        // until reaching a level where it becomes the left child
+        let mut node = leaf.clone();
+        let mut position = leaf_index;
+        for level in 0..ACCUMULATOR_DEPTH {
+            if position & 1 == 0 {
+                frontier.set(level, node);
+                break;
+            }
+            node = Self::hash_pair(env, &frontier.get_unchecked(level), &node);
+            position >>= 1;
+        }
+        env.storage().instance().set(&DataKey::Frontier, &frontier);
+        env.storage()
+            .instance()
+            .extend_ttl(TTL_THRESHOLD, TTL_EXTEND_TO);
+
+        // Recompute the root, padding unfilled subtrees with zero hashes
+        let tree_size = leaf_index + 1;
+        let mut root = zero.clone();
+        let mut zero_subtree = zero;
+        let mut size = tree_size;
+        for level in 0..ACCUMULATOR_DEPTH {
+            root = if size & 1 == 1 {
+                Self::hash_pair(env, &frontier.get_unchecked(level), &root)
+            } else {
+                Self::hash_pair(env, &root, &zero_subtree)
+            };
+            zero_subtree = Self::hash_pair(env, &zero_subtree, &zero_subtree);
+            size >>= 1;
+        }
+
+        // Historical roots back `get_root_at_size` proofs, so keep them alive
+        let root_key = DataKey::AccumulatorRoot(tree_size);
+        env.storage().persistent().set(&root_key, &root);
+        env.storage()
+            .persistent()
+            .extend_ttl(&root_key, TTL_THRESHOLD, TTL_EXTEND_TO);
+
+        AccumulatorRootUpdated { tree_size, root }.publish(env);
+    }
+
+    /// Root of an accumulator with no commitments
+    fn empty_root(env: &Env) -> BytesN<32> {
+        let mut node = BytesN::from_array(env, &[0u8; 32]);
+        for _ in 0..ACCUMULATOR_DEPTH {
+            node = Self::hash_pair(env, &node, &node);
+        }
+        node
+    }
+
+    /// SHA-256(left || right)
+    fn hash_pair(env: &Env, left: &BytesN<32>, right: &BytesN<32>) -> BytesN<32> {
+        let mut bytes = Bytes::new(env);
+        bytes.extend_from_slice(&left.to_array());
+        bytes.extend_from_slice(&right.to_array());
+        env.crypto().sha256(&bytes).into()
+    }
 }
 
 #[cfg(test)]
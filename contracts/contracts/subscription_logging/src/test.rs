@@ -1,7 +1,11 @@
 #![cfg(test)]
+extern crate std;
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Bytes, BytesN, Env};
+use soroban_sdk::{
+    testutils::{Address as _, MockAuth, MockAuthInvoke},
+    Address, Bytes, BytesN, Env, IntoVal,
+};
 
 fn create_test_env() -> (Env, Address, SubscriptionLoggingContractClient<'static>) {
     let env = Env::default();
@@ -13,7 +17,11 @@ fn create_test_env() -> (Env, Address, SubscriptionLoggingContractClient<'static
     let admin = Address::generate(&env);
     client.init(&admin);
 
-    (env, admin, client)
+    // Allowlisted writer used to record commitments
+    let writer = Address::generate(&env);
+    client.add_writer(&writer);
+
+    (env, writer, client)
 }
 
 // ============================================================================
@@ -22,7 +30,7 @@ fn create_test_env() -> (Env, Address, SubscriptionLoggingContractClient<'static
 
 #[test]
 fn test_record_commitment() {
-    let (_env, _admin, client) = create_test_env();
+    let (_env, writer, client) = create_test_env();
 
     // Generate a mock commitment hash (in production, this would be SHA-256 of event data)
     let commitment_hash = BytesN::from_array(
@@ -30,7 +38,7 @@ fn test_record_commitment() {
         &[0u8; 32], // Mock commitment
     );
 
-    let commitment_index = client.record_commitment(&commitment_hash);
+    let commitment_index = client.record_commitment(&writer, &commitment_hash);
 
     // Verify index returned
     assert_eq!(commitment_index, 0);
@@ -41,7 +49,7 @@ fn test_record_commitment() {
 
 #[test]
 fn test_record_multiple_commitments() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Record 5 commitments
     for i in 0..5u8 {
@@ -49,7 +57,7 @@ fn test_record_multiple_commitments() {
         hash_bytes[0] = i;
         let commitment_hash = BytesN::from_array(&env, &hash_bytes);
 
-        let index = client.record_commitment(&commitment_hash);
+        let index = client.record_commitment(&writer, &commitment_hash);
         assert_eq!(index, i as u64);
     }
 
@@ -58,10 +66,10 @@ fn test_record_multiple_commitments() {
 
 #[test]
 fn test_get_commitment() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     let commitment_hash = BytesN::from_array(&env, &[1u8; 32]);
-    let index = client.record_commitment(&commitment_hash);
+    let index = client.record_commitment(&writer, &commitment_hash);
 
     let retrieved = client.get_commitment(&index).unwrap();
 
@@ -81,14 +89,14 @@ fn test_get_nonexistent_commitment() {
 
 #[test]
 fn test_get_commitments_range() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Record 10 commitments
     for i in 0..10u8 {
         let mut hash_bytes = [0u8; 32];
         hash_bytes[0] = i;
         let commitment_hash = BytesN::from_array(&env, &hash_bytes);
-        client.record_commitment(&commitment_hash);
+        client.record_commitment(&writer, &commitment_hash);
     }
 
     // Get range 2-5
@@ -115,11 +123,11 @@ fn test_get_commitments_range_too_large() {
 
 #[test]
 fn test_commitment_reveals_no_subscription_data() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Record a commitment (representing sensitive subscription data)
     let commitment_hash = BytesN::from_array(&env, &[42u8; 32]);
-    let index = client.record_commitment(&commitment_hash);
+    let index = client.record_commitment(&writer, &commitment_hash);
 
     let commitment = client.get_commitment(&index).unwrap();
 
@@ -133,14 +141,14 @@ fn test_commitment_reveals_no_subscription_data() {
 
 #[test]
 fn test_commitments_not_linkable_by_subscription() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Record commitments for "same subscription" (different hashes)
     let hash1 = BytesN::from_array(&env, &[1u8; 32]);
     let hash2 = BytesN::from_array(&env, &[2u8; 32]);
 
-    let index1 = client.record_commitment(&hash1);
-    let index2 = client.record_commitment(&hash2);
+    let index1 = client.record_commitment(&writer, &hash1);
+    let index2 = client.record_commitment(&writer, &hash2);
 
     let c1 = client.get_commitment(&index1).unwrap();
     let c2 = client.get_commitment(&index2).unwrap();
@@ -156,18 +164,18 @@ fn test_commitments_not_linkable_by_subscription() {
 
 #[test]
 fn test_anchor_merkle_root() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Record 4 commitments
     for i in 0..4u8 {
         let mut hash_bytes = [0u8; 32];
         hash_bytes[0] = i;
-        client.record_commitment(&BytesN::from_array(&env, &hash_bytes));
+        client.record_commitment(&writer, &BytesN::from_array(&env, &hash_bytes));
     }
 
     // Anchor Merkle root for commitments 0-3
     let root_hash = BytesN::from_array(&env, &[99u8; 32]);
-    client.anchor_merkle_root(&root_hash, &0, &3);
+    client.anchor_merkle_root(&writer, &root_hash, &0, &3);
 
     // Verify root was stored
     assert_eq!(client.get_merkle_root_count(), 1);
@@ -181,27 +189,27 @@ fn test_anchor_merkle_root() {
 #[test]
 #[should_panic(expected = "Invalid range")]
 fn test_anchor_merkle_root_invalid_range() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     let root_hash = BytesN::from_array(&env, &[99u8; 32]);
-    client.anchor_merkle_root(&root_hash, &5, &2); // end < start
+    client.anchor_merkle_root(&writer, &root_hash, &5, &2); // end < start
 }
 
 #[test]
 #[should_panic(expected = "exceeds commitment count")]
 fn test_anchor_merkle_root_exceeds_commitments() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Only record 2 commitments
     for i in 0..2u8 {
         let mut hash_bytes = [0u8; 32];
         hash_bytes[0] = i;
-        client.record_commitment(&BytesN::from_array(&env, &hash_bytes));
+        client.record_commitment(&writer, &BytesN::from_array(&env, &hash_bytes));
     }
 
     // Try to anchor root for indices 0-5 (but only 0-1 exist)
     let root_hash = BytesN::from_array(&env, &[99u8; 32]);
-    client.anchor_merkle_root(&root_hash, &0, &5);
+    client.anchor_merkle_root(&writer, &root_hash, &0, &5);
 }
 
 // ============================================================================
@@ -210,14 +218,14 @@ fn test_anchor_merkle_root_exceeds_commitments() {
 
 #[test]
 fn test_verify_merkle_membership_simple() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Record 2 commitments
     let hash1 = BytesN::from_array(&env, &[1u8; 32]);
     let hash2 = BytesN::from_array(&env, &[2u8; 32]);
 
-    client.record_commitment(&hash1);
-    client.record_commitment(&hash2);
+    client.record_commitment(&writer, &hash1);
+    client.record_commitment(&writer, &hash2);
 
     // Compute Merkle root manually
     let mut combined = Bytes::new(&env);
@@ -226,7 +234,7 @@ fn test_verify_merkle_membership_simple() {
     let root: BytesN<32> = env.crypto().sha256(&combined).into();
 
     // Anchor the root
-    client.anchor_merkle_root(&root, &0, &1);
+    client.anchor_merkle_root(&writer, &root, &0, &1);
 
     // Verify membership of commitment 0
     let proof_path = soroban_sdk::vec![&env, hash2]; // Sibling is hash2
@@ -238,14 +246,14 @@ fn test_verify_merkle_membership_simple() {
 
 #[test]
 fn test_verify_merkle_membership_invalid_proof() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Record 2 commitments
     let hash1 = BytesN::from_array(&env, &[1u8; 32]);
     let hash2 = BytesN::from_array(&env, &[2u8; 32]);
 
-    client.record_commitment(&hash1);
-    client.record_commitment(&hash2);
+    client.record_commitment(&writer, &hash1);
+    client.record_commitment(&writer, &hash2);
 
     // Compute correct Merkle root
     let mut combined = Bytes::new(&env);
@@ -253,7 +261,7 @@ fn test_verify_merkle_membership_invalid_proof() {
     combined.extend_from_slice(&hash2.to_array());
     let root: BytesN<32> = env.crypto().sha256(&combined).into();
 
-    client.anchor_merkle_root(&root, &0, &1);
+    client.anchor_merkle_root(&writer, &root, &0, &1);
 
     // Provide wrong sibling (should fail)
     let wrong_sibling = BytesN::from_array(&env, &[99u8; 32]);
@@ -266,18 +274,18 @@ fn test_verify_merkle_membership_invalid_proof() {
 
 #[test]
 fn test_verify_merkle_membership_commitment_not_in_range() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Record 4 commitments
     for i in 0..4u8 {
         let mut hash_bytes = [0u8; 32];
         hash_bytes[0] = i;
-        client.record_commitment(&BytesN::from_array(&env, &hash_bytes));
+        client.record_commitment(&writer, &BytesN::from_array(&env, &hash_bytes));
     }
 
     // Anchor root for only commitments 0-1
     let root_hash = BytesN::from_array(&env, &[99u8; 32]);
-    client.anchor_merkle_root(&root_hash, &0, &1);
+    client.anchor_merkle_root(&writer, &root_hash, &0, &1);
 
     // Try to verify commitment 3 (out of range)
     let proof_path = soroban_sdk::vec![&env];
@@ -287,6 +295,266 @@ fn test_verify_merkle_membership_commitment_not_in_range() {
     assert!(!is_valid);
 }
 
+// ============================================================================
+// SELECTIVE DISCLOSURE TESTS
+// ============================================================================
+
+/// Records two commitments, the first of which can be opened.
+/// Returns (event_data_hash, blinding_factor, second commitment hash).
+fn record_disclosable_pair(
+    env: &Env,
+    client: &SubscriptionLoggingContractClient,
+    writer: &Address,
+) -> (BytesN<32>, BytesN<32>, BytesN<32>) {
+    let event_type = commitment::EventTypeByte::Renewal as u32;
+    let event_data_hash =
+        commitment::hash_event_data(env, &Bytes::from_slice(env, b"renewal:sub=7"));
+    let blinding_factor = BytesN::from_array(env, &[7u8; 32]);
+    let hash1 =
+        commitment::compute_commitment_hash(env, event_type, &event_data_hash, &blinding_factor);
+    let hash2 = BytesN::from_array(env, &[2u8; 32]);
+
+    client.record_commitment(writer, &hash1);
+    client.record_commitment(writer, &hash2);
+
+    (event_data_hash, blinding_factor, hash2)
+}
+
+#[test]
+fn test_verify_disclosure_opening_only() {
+    let (env, writer, client) = create_test_env();
+    let (event_data_hash, blinding_factor, _) = record_disclosable_pair(&env, &client, &writer);
+    let event_type = commitment::EventTypeByte::Renewal as u32;
+
+    assert!(client.verify_disclosure(&0, &event_type, &event_data_hash, &blinding_factor, &None));
+}
+
+#[test]
+fn test_verify_disclosure_with_merkle_proof() {
+    let (env, writer, client) = create_test_env();
+    let (event_data_hash, blinding_factor, second) =
+        record_disclosable_pair(&env, &client, &writer);
+    let event_type = commitment::EventTypeByte::Renewal as u32;
+
+    let first = client.get_commitment(&0).unwrap().commitment_hash;
+    let (_, path) = reference_tree(&env, &[first, second], 0);
+    let proof = MerkleProof { tree_size: 2, path };
+    assert!(client.verify_disclosure(
+        &0,
+        &event_type,
+        &event_data_hash,
+        &blinding_factor,
+        &Some(proof)
+    ));
+}
+
+#[test]
+fn test_verify_disclosure_wrong_opening() {
+    let (env, writer, client) = create_test_env();
+    let (event_data_hash, _, _) = record_disclosable_pair(&env, &client, &writer);
+    let wrong_bf = BytesN::from_array(&env, &[8u8; 32]);
+
+    // Wrong blinding factor
+    let event_type = commitment::EventTypeByte::Renewal as u32;
+    assert!(!client.verify_disclosure(&0, &event_type, &event_data_hash, &wrong_bf, &None));
+
+    // Right blinding factor, wrong event type
+    let bf = BytesN::from_array(&env, &[7u8; 32]);
+    let wrong_type = commitment::EventTypeByte::Cancellation as u32;
+    assert!(!client.verify_disclosure(&0, &wrong_type, &event_data_hash, &bf, &None));
+
+    // Nonexistent commitment
+    assert!(!client.verify_disclosure(&5, &event_type, &event_data_hash, &bf, &None));
+}
+
+#[test]
+fn test_verify_disclosure_invalid_merkle_proof() {
+    let (env, writer, client) = create_test_env();
+    let (event_data_hash, blinding_factor, _) = record_disclosable_pair(&env, &client, &writer);
+    let event_type = commitment::EventTypeByte::Renewal as u32;
+
+    // Valid opening but the path does not lead to the accumulator root
+    let first = client.get_commitment(&0).unwrap().commitment_hash;
+    let forged = BytesN::from_array(&env, &[99u8; 32]);
+    let (_, path) = reference_tree(&env, &[first, forged], 0);
+    let proof = MerkleProof { tree_size: 2, path };
+    assert!(!client.verify_disclosure(
+        &0,
+        &event_type,
+        &event_data_hash,
+        &blinding_factor,
+        &Some(proof)
+    ));
+}
+
+#[test]
+fn test_verify_disclosure_ignores_anchored_roots() {
+    let (env, writer, client) = create_test_env();
+    let (event_data_hash, blinding_factor, _) = record_disclosable_pair(&env, &client, &writer);
+    let event_type = commitment::EventTypeByte::Renewal as u32;
+
+    // A writer anchors a root over a forged sibling
+    let first = client.get_commitment(&0).unwrap().commitment_hash;
+    let forged = BytesN::from_array(&env, &[99u8; 32]);
+    let (forged_root, path) = reference_tree(&env, &[first, forged], 0);
+    client.anchor_merkle_root(&writer, &forged_root, &0, &1);
+
+    // The disclosure is checked against the contract's own root, not the anchored one
+    let proof = MerkleProof { tree_size: 2, path };
+    assert!(!client.verify_disclosure(
+        &0,
+        &event_type,
+        &event_data_hash,
+        &blinding_factor,
+        &Some(proof)
+    ));
+}
+
+// ============================================================================
+// INCREMENTAL MERKLE ACCUMULATOR TESTS
+// ============================================================================
+
+fn leaf(env: &Env, i: u8) -> BytesN<32> {
+    let mut bytes = [0u8; 32];
+    bytes[0] = i;
+    bytes[31] = 0xAA;
+    BytesN::from_array(env, &bytes)
+}
+
+fn pair(env: &Env, left: &BytesN<32>, right: &BytesN<32>) -> BytesN<32> {
+    let mut combined = Bytes::new(env);
+    combined.extend_from_slice(&left.to_array());
+    combined.extend_from_slice(&right.to_array());
+    env.crypto().sha256(&combined).into()
+}
+
+/// Naive full-tree reference: returns (root, sibling path for `index`)
+fn reference_tree(
+    env: &Env,
+    leaves: &[BytesN<32>],
+    index: usize,
+) -> (BytesN<32>, soroban_sdk::Vec<BytesN<32>>) {
+    let mut level: std::vec::Vec<BytesN<32>> = leaves.to_vec();
+    let mut zero = BytesN::from_array(env, &[0u8; 32]);
+    let mut position = index;
+    let mut path = soroban_sdk::vec![env];
+    for _ in 0..ACCUMULATOR_DEPTH {
+        if level.len() % 2 == 1 {
+            level.push(zero.clone());
+        }
+        if level.is_empty() {
+            level.push(zero.clone());
+            level.push(zero.clone());
+        }
+        path.push_back(level[position ^ 1].clone());
+        level = level.chunks(2).map(|c| pair(env, &c[0], &c[1])).collect();
+        zero = pair(env, &zero, &zero);
+        position >>= 1;
+    }
+    (level[0].clone(), path)
+}
+
+#[test]
+fn test_accumulator_empty_root() {
+    let (env, _admin, client) = create_test_env();
+
+    let (expected, _) = reference_tree(&env, &[], 0);
+    assert_eq!(client.current_root(), expected);
+    assert_eq!(client.get_root_at_size(&0), Some(expected));
+}
+
+#[test]
+fn test_accumulator_matches_reference_tree() {
+    let (env, writer, client) = create_test_env();
+
+    let mut leaves = std::vec::Vec::new();
+    for i in 0..5u8 {
+        let hash = leaf(&env, i);
+        client.record_commitment(&writer, &hash);
+        leaves.push(hash);
+
+        let (expected, _) = reference_tree(&env, &leaves, 0);
+        assert_eq!(client.current_root(), expected);
+    }
+}
+
+#[test]
+fn test_accumulator_retains_historical_roots() {
+    let (env, writer, client) = create_test_env();
+
+    let mut leaves = std::vec::Vec::new();
+    for i in 0..3u8 {
+        let hash = leaf(&env, i);
+        client.record_commitment(&writer, &hash);
+        leaves.push(hash);
+    }
+    let root_at_3 = client.current_root();
+
+    client.record_commitment(&writer, &leaf(&env, 3));
+    assert_ne!(client.current_root(), root_at_3);
+    assert_eq!(client.get_root_at_size(&3), Some(root_at_3));
+    assert_eq!(client.get_root_at_size(&9), None);
+
+    // A proof built against the 3-leaf tree still verifies after growth
+    let (_, path) = reference_tree(&env, &leaves, 2);
+    assert!(client.verify_accumulator_membership(&2, &3, &path));
+}
+
+#[test]
+fn test_accumulator_membership_proofs() {
+    let (env, writer, client) = create_test_env();
+
+    let mut leaves = std::vec::Vec::new();
+    for i in 0..6u8 {
+        let hash = leaf(&env, i);
+        client.record_commitment(&writer, &hash);
+        leaves.push(hash);
+    }
+
+    for index in 0..6u64 {
+        let (_, path) = reference_tree(&env, &leaves, index as usize);
+        assert!(client.verify_accumulator_membership(&index, &6, &path));
+    }
+
+    // Path for one leaf does not authenticate another
+    let (_, path) = reference_tree(&env, &leaves, 1);
+    assert!(!client.verify_accumulator_membership(&2, &6, &path));
+
+    // Index beyond the claimed tree size
+    assert!(!client.verify_accumulator_membership(&5, &4, &path));
+
+    // Truncated path
+    let mut short = path.clone();
+    short.pop_back();
+    assert!(!client.verify_accumulator_membership(&1, &6, &short));
+}
+
+#[test]
+fn test_accumulator_roots_have_extended_ttl() {
+    use soroban_sdk::testutils::{storage::Persistent as _, Ledger as _};
+
+    let (env, writer, client) = create_test_env();
+    client.record_commitment(&writer, &leaf(&env, 0));
+
+    let ttl = env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .get_ttl(&DataKey::AccumulatorRoot(1))
+    });
+    assert_eq!(ttl, TTL_EXTEND_TO);
+
+    // Reading an older root past the threshold extends it again
+    env.ledger()
+        .with_mut(|li| li.sequence_number += TTL_EXTEND_TO - TTL_THRESHOLD + 1);
+    assert!(client.get_root_at_size(&1).is_some());
+    let ttl = env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .get_ttl(&DataKey::AccumulatorRoot(1))
+    });
+    assert_eq!(ttl, TTL_EXTEND_TO);
+}
+
 // ============================================================================
 // BACKWARD COMPATIBILITY TESTS (Legacy Plaintext Logging)
 // ============================================================================
@@ -336,21 +604,158 @@ fn test_legacy_multiple_logs_same_subscription() {
     assert_eq!(logs.len(), 3);
 }
 
+// ============================================================================
+// WRITER ALLOWLIST TESTS
+// ============================================================================
+
+#[test]
+fn test_commitment_records_writer() {
+    let (env, writer, client) = create_test_env();
+
+    let index = client.record_commitment(&writer, &BytesN::from_array(&env, &[1u8; 32]));
+
+    assert_eq!(client.get_commitment_writer(&index), Some(writer.clone()));
+    assert_eq!(client.get_writer_commitment_count(&writer), 1);
+}
+
+#[test]
+fn test_writers_have_independent_counters() {
+    let (env, writer, client) = create_test_env();
+    let other = Address::generate(&env);
+    client.add_writer(&other);
+
+    client.record_commitment(&writer, &BytesN::from_array(&env, &[1u8; 32]));
+    client.record_commitment(&other, &BytesN::from_array(&env, &[2u8; 32]));
+    client.record_commitment(&other, &BytesN::from_array(&env, &[3u8; 32]));
+
+    // Indices stay global while counters are per writer
+    assert_eq!(client.get_commitment_count(), 3);
+    assert_eq!(client.get_writer_commitment_count(&writer), 1);
+    assert_eq!(client.get_writer_commitment_count(&other), 2);
+    assert_eq!(client.get_commitment_writer(&2), Some(other));
+}
+
+#[test]
+fn test_commitment_recorded_before_writers_still_reads() {
+    let (env, _writer, client) = create_test_env();
+
+    // A record stored before writers were tracked has no writer key
+    let legacy = AuditCommitment {
+        commitment_hash: BytesN::from_array(&env, &[9u8; 32]),
+        timestamp: 0,
+        commitment_index: 0,
+    };
+    env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Commitment(0), &legacy);
+    });
+
+    assert_eq!(client.get_commitment(&0), Some(legacy));
+    assert_eq!(client.get_commitment_writer(&0), None);
+}
+
+#[test]
+#[should_panic(expected = "Writer not authorized")]
+fn test_unlisted_writer_rejected() {
+    let (env, _writer, client) = create_test_env();
+    let stranger = Address::generate(&env);
+
+    client.record_commitment(&stranger, &BytesN::from_array(&env, &[1u8; 32]));
+}
+
+#[test]
+#[should_panic(expected = "Writer not authorized")]
+fn test_removed_writer_rejected() {
+    let (env, writer, client) = create_test_env();
+
+    client.remove_writer(&writer);
+    assert!(!client.is_writer(&writer));
+
+    client.record_commitment(&writer, &BytesN::from_array(&env, &[1u8; 32]));
+}
+
+#[test]
+#[should_panic(expected = "Writer not authorized")]
+fn test_unlisted_writer_cannot_anchor() {
+    let (env, writer, client) = create_test_env();
+    client.record_commitment(&writer, &BytesN::from_array(&env, &[1u8; 32]));
+
+    let stranger = Address::generate(&env);
+    client.anchor_merkle_root(&stranger, &BytesN::from_array(&env, &[9u8; 32]), &0, &0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_record_commitment_requires_writer_auth() {
+    let (env, writer, client) = create_test_env();
+    env.set_auths(&[]);
+
+    client.record_commitment(&writer, &BytesN::from_array(&env, &[1u8; 32]));
+}
+
+#[test]
+#[should_panic(expected = "Writer not found")]
+fn test_remove_unknown_writer() {
+    let (env, _writer, client) = create_test_env();
+
+    client.remove_writer(&Address::generate(&env));
+}
+
+// ============================================================================
+// GOVERNANCE TESTS
+// ============================================================================
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_admin_cannot_replace_governance() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionLoggingContract, ());
+    let client = SubscriptionLoggingContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.init(&admin);
+    client.set_governance(&Address::generate(&env));
+
+    // Only the current governance contract may hand over; the admin may not
+    env.mock_auths(&[MockAuth {
+        address: &admin,
+        invoke: &MockAuthInvoke {
+            contract: &contract_id,
+            fn_name: "set_governance",
+            args: (admin.clone(),).into_val(&env),
+            sub_invokes: &[],
+        },
+    }]);
+    client.set_governance(&admin);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_upgrade_rejects_non_governance_caller() {
+    let (env, _writer, client) = create_test_env();
+    client.set_governance(&Address::generate(&env));
+    assert!(client.get_governance().is_some());
+
+    env.set_auths(&[]);
+    client.upgrade(&BytesN::from_array(&env, &[1u8; 32]));
+}
+
 // ============================================================================
 // REPLAY ATTACK PREVENTION TESTS
 // ============================================================================
 
 #[test]
 fn test_commitment_indices_monotonic() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     let hash1 = BytesN::from_array(&env, &[1u8; 32]);
     let hash2 = BytesN::from_array(&env, &[2u8; 32]);
     let hash3 = BytesN::from_array(&env, &[3u8; 32]);
 
-    let idx1 = client.record_commitment(&hash1);
-    let idx2 = client.record_commitment(&hash2);
-    let idx3 = client.record_commitment(&hash3);
+    let idx1 = client.record_commitment(&writer, &hash1);
+    let idx2 = client.record_commitment(&writer, &hash2);
+    let idx3 = client.record_commitment(&writer, &hash3);
 
     // Indices must be strictly increasing
     assert_eq!(idx1, 0);
@@ -360,13 +765,13 @@ fn test_commitment_indices_monotonic() {
 
 #[test]
 fn test_commitment_index_uniqueness() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Record same hash twice (simulating replay attempt)
     let hash = BytesN::from_array(&env, &[42u8; 32]);
 
-    let idx1 = client.record_commitment(&hash);
-    let idx2 = client.record_commitment(&hash);
+    let idx1 = client.record_commitment(&writer, &hash);
+    let idx2 = client.record_commitment(&writer, &hash);
 
     // Different indices even for same hash
     assert_ne!(idx1, idx2);
@@ -385,7 +790,7 @@ fn test_commitment_index_uniqueness() {
 
 #[test]
 fn test_commitment_storage_size() {
-    let (env, _admin, client) = create_test_env();
+    let (env, writer, client) = create_test_env();
 
     // Commitment structure:
     // - commitment_hash: 32 bytes
@@ -403,7 +808,7 @@ fn test_commitment_storage_size() {
     // Commitment is ~75% smaller!
 
     let commitment_hash = BytesN::from_array(&env, &[0u8; 32]);
-    let index = client.record_commitment(&commitment_hash);
+    let index = client.record_commitment(&writer, &commitment_hash);
 
     let commitment = client.get_commitment(&index).unwrap();
 
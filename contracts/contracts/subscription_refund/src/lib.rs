@@ -147,9 +147,12 @@ impl SubscriptionRefundContract {
     }
 
     /// Record a completed subscription charge on-chain.
-    /// Can be called by merchant or admin.
+    /// `caller` must be the merchant or the admin.
+    // Mirrors the `ChargeRecord` fields plus the authorizing caller
+    #[allow(clippy::too_many_arguments)]
     pub fn record_charge(
         env: Env,
+        caller: Address,
         payment_ref: u64,
         sub_id: u64,
         payer: Address,
@@ -164,11 +167,9 @@ impl SubscriptionRefundContract {
         }
 
         // Auth requirement: merchant or admin
-        if merchant.has_auth() {
-            merchant.require_auth();
-        } else {
-            let admin = Self::get_admin(&env);
-            admin.require_auth();
+        caller.require_auth();
+        if caller != merchant && caller != Self::get_admin(&env) {
+            panic_with_error!(&env, RefundError::Unauthorized);
         }
 
         if env
@@ -194,22 +195,20 @@ impl SubscriptionRefundContract {
             .persistent()
             .set(&DataKey::Charge(payment_ref), &charge);
 
-        env.events().publish(
-            (payment_ref, sub_id),
-            ChargeRecorded {
-                payment_ref,
-                sub_id,
-                payer,
-                merchant,
-                amount,
-                token,
-            },
-        );
+        ChargeRecorded {
+            payment_ref,
+            sub_id,
+            payer,
+            merchant,
+            amount,
+            token,
+        }
+        .publish(&env);
     }
 
     /// Open a dispute against a completed charge.
-    /// Can be called by payer or admin.
-    pub fn open_dispute(env: Env, payment_ref: u64, reason: String) {
+    /// `caller` must be the payer or the admin.
+    pub fn open_dispute(env: Env, caller: Address, payment_ref: u64, reason: String) {
         Self::require_not_paused(&env);
 
         let charge: ChargeRecord = env
@@ -223,14 +222,11 @@ impl SubscriptionRefundContract {
         }
 
         // Auth requirement: payer or admin
-        let raised_by = if charge.payer.has_auth() {
-            charge.payer.require_auth();
-            charge.payer.clone()
-        } else {
-            let admin = Self::get_admin(&env);
-            admin.require_auth();
-            admin
-        };
+        caller.require_auth();
+        if caller != charge.payer && caller != Self::get_admin(&env) {
+            panic_with_error!(&env, RefundError::Unauthorized);
+        }
+        let raised_by = caller;
 
         if env
             .storage()
@@ -253,21 +249,19 @@ impl SubscriptionRefundContract {
             .persistent()
             .set(&DataKey::Dispute(payment_ref), &dispute);
 
-        env.events().publish(
-            (payment_ref, raised_by.clone()),
-            DisputeOpened {
-                payment_ref,
-                raised_by,
-                reason,
-            },
-        );
+        DisputeOpened {
+            payment_ref,
+            raised_by,
+            reason,
+        }
+        .publish(&env);
     }
 
     /// Dispute admin or admin authorizes or rejects a dispute.
-    pub fn authorize_dispute(env: Env, payment_ref: u64, approve: bool) {
+    pub fn authorize_dispute(env: Env, caller: Address, payment_ref: u64, approve: bool) {
         Self::require_not_paused(&env);
 
-        let resolver = Self::require_dispute_admin_or_admin(&env);
+        let resolver = Self::require_dispute_admin_or_admin(&env, caller);
 
         let mut dispute: DisputeRecord = env
             .storage()
@@ -289,24 +283,23 @@ impl SubscriptionRefundContract {
             .persistent()
             .set(&DataKey::Dispute(payment_ref), &dispute);
 
-        env.events().publish(
-            (payment_ref, resolver.clone()),
-            DisputeAuthorized {
-                payment_ref,
-                approved: approve,
-                resolver,
-            },
-        );
+        DisputeAuthorized {
+            payment_ref,
+            approved: approve,
+            resolver,
+        }
+        .publish(&env);
     }
 
     /// Process a refund for a payment reference.
     /// Reverses the completed charge by transferring tokens back to the subscriber.
     /// Supported authorization paths:
-    /// 1. Direct voluntary merchant refund (merchant auth)
-    /// 2. Authorized dispute refund (dispute admin / admin auth with approved dispute)
+    /// 1. Direct voluntary merchant refund (`caller` is the merchant)
+    /// 2. Authorized dispute refund (`caller` is the dispute admin / admin, with an
+    ///    approved dispute)
     ///
     /// DOUBLE-REFUND PREVENTION: Rejects any attempt to refund a charge that is already refunded.
-    pub fn process_refund(env: Env, payment_ref: u64) {
+    pub fn process_refund(env: Env, caller: Address, payment_ref: u64) {
         Self::require_not_paused(&env);
 
         let mut charge: ChargeRecord = env
@@ -320,16 +313,18 @@ impl SubscriptionRefundContract {
             panic_with_error!(&env, RefundError::AlreadyRefunded);
         }
 
-        let mut dispute_opt: Option<DisputeRecord> =
-            env.storage().persistent().get(&DataKey::Dispute(payment_ref));
+        let dispute_opt: Option<DisputeRecord> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Dispute(payment_ref));
 
         let refunded_by: Address;
 
-        if charge.merchant.has_auth() {
-            charge.merchant.require_auth();
-            refunded_by = charge.merchant.clone();
+        if caller == charge.merchant {
+            caller.require_auth();
+            refunded_by = caller;
         } else {
-            refunded_by = Self::require_dispute_admin_or_admin(&env);
+            refunded_by = Self::require_dispute_admin_or_admin(&env, caller);
             if let Some(ref dispute) = dispute_opt {
                 if dispute.status != DisputeStatus::Approved {
                     panic_with_error!(&env, RefundError::DisputeNotApproved);
@@ -359,18 +354,16 @@ impl SubscriptionRefundContract {
         token_client.transfer(&charge.merchant, &charge.payer, &charge.amount);
 
         // Emit refund event
-        env.events().publish(
-            (payment_ref, charge.sub_id),
-            RefundProcessed {
-                payment_ref,
-                sub_id: charge.sub_id,
-                payer: charge.payer,
-                merchant: charge.merchant,
-                amount: charge.amount,
-                token: charge.token,
-                refunded_by,
-            },
-        );
+        RefundProcessed {
+            payment_ref,
+            sub_id: charge.sub_id,
+            payer: charge.payer,
+            merchant: charge.merchant,
+            amount: charge.amount,
+            token: charge.token,
+            refunded_by,
+        }
+        .publish(&env);
     }
 
     /// Check if a charge has been refunded
@@ -404,13 +397,11 @@ impl SubscriptionRefundContract {
         let admin = Self::get_admin(&env);
         admin.require_auth();
         env.storage().instance().set(&ContractKey::Admin, &new_admin);
-        env.events().publish(
-            (&admin, &new_admin),
-            AdminUpdated {
-                old_admin: admin,
-                new_admin,
-            },
-        );
+        AdminUpdated {
+            old_admin: admin,
+            new_admin,
+        }
+        .publish(&env);
     }
 
     /// Update dispute admin
@@ -421,13 +412,11 @@ impl SubscriptionRefundContract {
         env.storage()
             .instance()
             .set(&ContractKey::DisputeAdmin, &new_dispute_admin);
-        env.events().publish(
-            (&old_dispute_admin, &new_dispute_admin),
-            DisputeAdminUpdated {
-                old_dispute_admin,
-                new_dispute_admin,
-            },
-        );
+        DisputeAdminUpdated {
+            old_dispute_admin,
+            new_dispute_admin,
+        }
+        .publish(&env);
     }
 
     /// Set contract paused state
@@ -464,17 +453,10 @@ impl SubscriptionRefundContract {
         }
     }
 
-    fn require_dispute_admin_or_admin(env: &Env) -> Address {
-        let dispute_admin = Self::get_dispute_admin(env);
-        if dispute_admin.has_auth() {
-            dispute_admin.require_auth();
-            return dispute_admin;
-        }
-
-        let admin = Self::get_admin(env);
-        if admin.has_auth() {
-            admin.require_auth();
-            return admin;
+    fn require_dispute_admin_or_admin(env: &Env, caller: Address) -> Address {
+        caller.require_auth();
+        if caller == Self::get_dispute_admin(env) || caller == Self::get_admin(env) {
+            return caller;
         }
 
         panic_with_error!(env, RefundError::Unauthorized)
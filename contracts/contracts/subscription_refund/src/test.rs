@@ -58,6 +58,7 @@ fn test_init_and_record_charge() {
     let amount = 500i128;
 
     client.record_charge(
+        &merchant,
         &payment_ref,
         &sub_id,
         &payer,
@@ -93,6 +94,7 @@ fn test_direct_merchant_refund() {
     let amount = 300i128;
 
     client.record_charge(
+        &merchant,
         &payment_ref,
         &sub_id,
         &payer,
@@ -101,7 +103,7 @@ fn test_direct_merchant_refund() {
         &amount,
     );
 
-    client.process_refund(&payment_ref);
+    client.process_refund(&merchant, &payment_ref);
 
     assert!(client.is_refunded(&payment_ref));
     assert_eq!(token_client.balance(&merchant), 700);
@@ -110,7 +112,7 @@ fn test_direct_merchant_refund() {
 
 #[test]
 fn test_dispute_authorization_and_refund_flow() {
-    let (env, _id, client, _admin, _dispute_admin, payer, merchant, token_id) = setup_env();
+    let (env, _id, client, _admin, dispute_admin, payer, merchant, token_id) = setup_env();
 
     let token_sac = token::StellarAssetClient::new(&env, &token_id);
     let token_client = token::Client::new(&env, &token_id);
@@ -122,6 +124,7 @@ fn test_dispute_authorization_and_refund_flow() {
     let amount = 1000i128;
 
     client.record_charge(
+        &merchant,
         &payment_ref,
         &sub_id,
         &payer,
@@ -131,18 +134,19 @@ fn test_dispute_authorization_and_refund_flow() {
     );
 
     let reason = String::from_str(&env, "Service not delivered");
-    client.open_dispute(&payment_ref, &reason);
+    client.open_dispute(&payer, &payment_ref, &reason);
 
     let dispute: DisputeRecord = client.get_dispute(&payment_ref);
     assert_eq!(dispute.status, DisputeStatus::Pending);
 
     // Authorize dispute
-    client.authorize_dispute(&payment_ref, &true);
+    client.authorize_dispute(&dispute_admin, &payment_ref, &true);
     let dispute_after_auth = client.get_dispute(&payment_ref);
     assert_eq!(dispute_after_auth.status, DisputeStatus::Approved);
 
-    // Execute refund
-    client.process_refund(&payment_ref);
+    // Execute refund; the merchant signs the transfer the dispute admin triggers
+    env.mock_all_auths_allowing_non_root_auth();
+    client.process_refund(&dispute_admin, &payment_ref);
 
     assert!(client.is_refunded(&payment_ref));
     assert_eq!(token_client.balance(&payer), 1000);
@@ -153,7 +157,7 @@ fn test_dispute_authorization_and_refund_flow() {
 }
 
 #[test]
-#[should_panic(expected = "AlreadyRefunded")]
+#[should_panic(expected = "Error(Contract, #5)")]
 fn test_double_refund_prevention() {
     let (env, _id, client, _admin, _dispute_admin, payer, merchant, token_id) = setup_env();
 
@@ -165,6 +169,7 @@ fn test_double_refund_prevention() {
     let amount = 500i128;
 
     client.record_charge(
+        &merchant,
         &payment_ref,
         &sub_id,
         &payer,
@@ -174,15 +179,15 @@ fn test_double_refund_prevention() {
     );
 
     // First refund succeeds
-    client.process_refund(&payment_ref);
+    client.process_refund(&merchant, &payment_ref);
     assert!(client.is_refunded(&payment_ref));
 
     // Second refund attempt MUST panic with AlreadyRefunded
-    client.process_refund(&payment_ref);
+    client.process_refund(&merchant, &payment_ref);
 }
 
 #[test]
-#[should_panic(expected = "AlreadyRefunded")]
+#[should_panic(expected = "Error(Contract, #5)")]
 fn test_dispute_cannot_be_opened_on_refunded_charge() {
     let (env, _id, client, _admin, _dispute_admin, payer, merchant, token_id) = setup_env();
 
@@ -194,6 +199,7 @@ fn test_dispute_cannot_be_opened_on_refunded_charge() {
     let amount = 200i128;
 
     client.record_charge(
+        &merchant,
         &payment_ref,
         &sub_id,
         &payer,
@@ -202,35 +208,63 @@ fn test_dispute_cannot_be_opened_on_refunded_charge() {
         &amount,
     );
 
-    client.process_refund(&payment_ref);
+    client.process_refund(&merchant, &payment_ref);
 
     let reason = String::from_str(&env, "Duplicate claim");
-    client.open_dispute(&payment_ref, &reason);
+    client.open_dispute(&payer, &payment_ref, &reason);
 }
 
 #[test]
-#[should_panic(expected = "ContractPaused")]
+#[should_panic(expected = "Error(Contract, #10)")]
 fn test_paused_contract_blocks_recording() {
     let (_env, _id, client, _admin, _dispute_admin, payer, merchant, token_id) = setup_env();
 
     client.set_paused(&true);
 
-    client.record_charge(&1006, &55, &payer, &merchant, &token_id, &100);
+    client.record_charge(&merchant, &1006, &55, &payer, &merchant, &token_id, &100);
 }
 
 #[test]
 fn test_dispute_rejection_prevents_authorized_refund() {
-    let (env, _id, client, _admin, _dispute_admin, payer, merchant, token_id) = setup_env();
+    let (env, _id, client, _admin, dispute_admin, payer, merchant, token_id) = setup_env();
 
     let payment_ref = 1007;
-    client.record_charge(&payment_ref, &56, &payer, &merchant, &token_id, &100);
+    client.record_charge(
+        &merchant,
+        &payment_ref,
+        &56,
+        &payer,
+        &merchant,
+        &token_id,
+        &100,
+    );
 
     let reason = String::from_str(&env, "Invalid reason");
-    client.open_dispute(&payment_ref, &reason);
+    client.open_dispute(&payer, &payment_ref, &reason);
 
     // Dispute Admin rejects dispute
-    client.authorize_dispute(&payment_ref, &false);
+    client.authorize_dispute(&dispute_admin, &payment_ref, &false);
 
     let dispute = client.get_dispute(&payment_ref);
     assert_eq!(dispute.status, DisputeStatus::Rejected);
 }
+
+#[test]
+#[should_panic(expected = "Error(Contract, #3)")]
+fn test_stranger_cannot_refund_without_dispute() {
+    let (env, _id, client, _admin, _dispute_admin, payer, merchant, token_id) = setup_env();
+
+    let payment_ref = 1008;
+    client.record_charge(
+        &merchant,
+        &payment_ref,
+        &57,
+        &payer,
+        &merchant,
+        &token_id,
+        &100,
+    );
+
+    let stranger = Address::generate(&env);
+    client.process_refund(&stranger, &payment_ref);
+}
@@ -1,15 +1,18 @@
 #![cfg(test)]
 extern crate std;
 
+use agent_registry::{AgentRegistry, AgentRegistryClient, Scope};
 use proptest::prelude::*;
 use soroban_sdk::{
     testutils::{Address as _, EnvTestConfig},
-    Address, Env,
+    token::{StellarAssetClient, TokenClient},
+    Address, BytesN, Env,
 };
 use std::panic::{catch_unwind, AssertUnwindSafe};
 
 use super::{
-    SubscriptionRenewalContract, SubscriptionRenewalContractClient, SubscriptionState,
+    hash_blinding_salt, SubscriptionRenewalContract, SubscriptionRenewalContractClient,
+    SubscriptionState,
 };
 
 fn fuzz_env() -> Env {
@@ -19,14 +22,36 @@ fn fuzz_env() -> Env {
     })
 }
 
-fn fuzz_setup() -> (Env, Address, Address) {
+fn fuzz_setup() -> (Env, Address, Address, Address, Address) {
     let env = fuzz_env();
     env.mock_all_auths();
     let id = env.register_contract(None, SubscriptionRenewalContract);
     let admin = Address::generate(&env);
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     client.init(&admin);
-    (env, id, admin)
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
+
+    // Agent allowed to hold renewal locks
+    let registry_id = env.register(AgentRegistry, ());
+    let registry = AgentRegistryClient::new(&env, &registry_id);
+    registry.init(&admin);
+    let agent = Address::generate(&env);
+    registry.register(&agent);
+    registry.update_scopes(&agent, &(Scope::Renewals as u32));
+    client.set_agent_registry(&registry_id);
+    (env, id, admin, token, agent)
+}
+
+/// Hash of a fixed owner salt, as registered at `init_sub`.
+fn fuzz_salt(env: &Env) -> BytesN<32> {
+    hash_blinding_salt(env, &BytesN::from_array(env, &[7u8; 32]))
+}
+
+fn fuzz_fund(env: &Env, token: &Address, renewal: &Address, owner: &Address, amount: i128) {
+    StellarAssetClient::new(env, token).mint(owner, &amount);
+    TokenClient::new(env, token).approve(owner, renewal, &amount, &1_000_000u32);
 }
 
 proptest! {
@@ -38,14 +63,13 @@ proptest! {
         amount in 1i128..=1_000_000_000i128,
         frequency in 1u64..=31_536_000u64,
         spending_cap in 0i128..=10_000_000_000i128,
-        sub_id in 1u64..=10_000u64,
     ) {
-        let (env, id, _admin) = fuzz_setup();
+        let (env, id, _admin, token, _agent) = fuzz_setup();
         let client = SubscriptionRenewalContractClient::new(&env, &id);
         let user = Address::generate(&env);
         let merchant = Address::generate(&env);
 
-        client.init_sub(&user, &merchant, &amount, &frequency, &spending_cap, &sub_id);
+        let sub_id = client.init_sub(&user, &merchant, &token, &amount, &frequency, &spending_cap, &fuzz_salt(&env));
 
         let data = client.get_sub(&sub_id);
         prop_assert_eq!(data.amount, amount);
@@ -61,25 +85,25 @@ proptest! {
         spending_cap in 1i128..=1_000_000i128,
         renew_amount in 1i128..=2_000_000i128,
     ) {
-        let (env, id, _admin) = fuzz_setup();
+        let (env, id, _admin, token, agent) = fuzz_setup();
         let client = SubscriptionRenewalContractClient::new(&env, &id);
         let user = Address::generate(&env);
         let merchant = Address::generate(&env);
-        let sub_id = 42u64;
 
-        client.init_sub(&user, &merchant, &amount, &86400u64, &spending_cap, &sub_id);
+        let sub_id = client.init_sub(&user, &merchant, &token, &amount, &86400u64, &spending_cap, &fuzz_salt(&env));
+        fuzz_fund(&env, &token, &id, &user, renew_amount);
         client.approve_renewal(&sub_id, &1u64, &renew_amount, &10_000u32);
-        client.acquire_renewal_lock(&sub_id, &200u32);
+        client.acquire_renewal_lock(&agent, &sub_id, &200u32);
 
         let exceeds_cap = spending_cap > 0 && renew_amount > spending_cap;
 
         if exceeds_cap {
             let result = catch_unwind(AssertUnwindSafe(|| {
-                client.renew(&sub_id, &1u64, &renew_amount, &3u32, &10u32, &20260101u64, &true);
+                client.renew(&agent, &sub_id, &1u64, &renew_amount, &20260101u64);
             }));
             prop_assert!(result.is_err(), "renewal exceeding cap must panic");
         } else {
-            let ok = client.renew(&sub_id, &1u64, &renew_amount, &3u32, &10u32, &20260101u64, &true);
+            let ok = client.renew(&agent, &sub_id, &1u64, &renew_amount, &20260101u64);
             prop_assert!(ok);
             let spent = client.get_user_spent(&user);
             prop_assert_eq!(spent, renew_amount);
@@ -93,33 +117,32 @@ proptest! {
         first_amount in 1i128..=5_000i128,
         second_amount in 1i128..=10_000i128,
     ) {
-        let (env, id, _admin) = fuzz_setup();
+        let (env, id, _admin, token, agent) = fuzz_setup();
         let client = SubscriptionRenewalContractClient::new(&env, &id);
         let user = Address::generate(&env);
         let merchant = Address::generate(&env);
 
-        client.set_user_cap(&user, &cap);
+        client.set_user_cap(&user, &cap, &0u64);
 
-        let sub_a = 1u64;
-        let sub_b = 2u64;
-        client.init_sub(&user, &merchant, &100i128, &86400u64, &0i128, &sub_a);
-        client.init_sub(&user, &merchant, &100i128, &86400u64, &0i128, &sub_b);
+        let sub_a = client.init_sub(&user, &merchant, &token, &100i128, &86400u64, &0i128, &fuzz_salt(&env));
+        let sub_b = client.init_sub(&user, &merchant, &token, &100i128, &86400u64, &0i128, &fuzz_salt(&env));
+        fuzz_fund(&env, &token, &id, &user, first_amount + second_amount);
 
         client.approve_renewal(&sub_a, &1u64, &first_amount, &10_000u32);
-        client.acquire_renewal_lock(&sub_a, &200u32);
+        client.acquire_renewal_lock(&agent, &sub_a, &200u32);
         if first_amount <= cap {
-            let _ = client.renew(&sub_a, &1u64, &first_amount, &3u32, &10u32, &20260101u64, &true);
+            let _ = client.renew(&agent, &sub_a, &1u64, &first_amount, &20260101u64);
         }
 
         let spent = client.get_user_spent(&user);
         let remaining = cap.saturating_sub(spent);
 
         client.approve_renewal(&sub_b, &1u64, &second_amount, &10_000u32);
-        client.acquire_renewal_lock(&sub_b, &200u32);
+        client.acquire_renewal_lock(&agent, &sub_b, &200u32);
 
         if second_amount > remaining {
             let result = catch_unwind(AssertUnwindSafe(|| {
-                client.renew(&sub_b, &1u64, &second_amount, &3u32, &10u32, &20260201u64, &true);
+                client.renew(&agent, &sub_b, &1u64, &second_amount, &20260201u64);
             }));
             prop_assert!(result.is_err(), "global cap overflow must panic");
         }
@@ -146,22 +169,22 @@ proptest! {
         amount in 1i128..=100_000i128,
         approval_max in 1i128..=1_000_000i128,
     ) {
-        let (env, id, _admin) = fuzz_setup();
+        let (env, id, _admin, token, agent) = fuzz_setup();
         let client = SubscriptionRenewalContractClient::new(&env, &id);
         let user = Address::generate(&env);
         let merchant = Address::generate(&env);
-        let sub_id = 7u64;
         let renew_amount = amount.min(approval_max);
 
-        client.init_sub(&user, &merchant, &amount, &86400u64, &0i128, &sub_id);
+        let sub_id = client.init_sub(&user, &merchant, &token, &amount, &86400u64, &0i128, &fuzz_salt(&env));
+        fuzz_fund(&env, &token, &id, &user, renew_amount * 2);
         client.approve_renewal(&sub_id, &1u64, &approval_max, &10_000u32);
 
-        client.acquire_renewal_lock(&sub_id, &200u32);
-        let _ = client.renew(&sub_id, &1u64, &renew_amount, &3u32, &10u32, &20260101u64, &true);
+        client.acquire_renewal_lock(&agent, &sub_id, &200u32);
+        let _ = client.renew(&agent, &sub_id, &1u64, &renew_amount, &20260101u64);
 
-        client.acquire_renewal_lock(&sub_id, &200u32);
+        client.acquire_renewal_lock(&agent, &sub_id, &200u32);
         let result = catch_unwind(AssertUnwindSafe(|| {
-            client.renew(&sub_id, &1u64, &renew_amount, &3u32, &10u32, &20260201u64, &true);
+            client.renew(&agent, &sub_id, &1u64, &renew_amount, &20260201u64);
         }));
         prop_assert!(result.is_err(), "reused approval must panic");
     }
@@ -1,8 +1,15 @@
 #![no_std]
+use agent_registry::{AgentRegistryClient, Scope};
+use fx_oracle::FxOracleContractClient;
+use payment_adapter::PaymentAdapterContractClient;
 use soroban_sdk::{
-    contract, contractevent, contractimpl, contracttype, xdr::ToXdr, Address, Bytes, Env, IntoVal,
+    contract, contractevent, contractimpl, contracttype, token, xdr::ToXdr, Address, Bytes, BytesN,
+    Env, IntoVal, String, Vec,
 };
+use subscription_logging::commitment::{self, EventTypeByte};
 use subscription_logging::SubscriptionLoggingContractClient;
+use subscription_refund::SubscriptionRefundContractClient;
+use subscription_registry::SubscriptionRegistryClient;
 
 /// Storage keys for contract-level state (admin, pause flag).
 #[contracttype]
@@ -11,6 +18,14 @@ enum ContractKey {
     Admin,
     Paused,
     LoggingContract,
+    PaymentAdapter,
+    FxOracle,
+    KeeperBountyBps,
+    AgentRegistry,
+    MaxPauseDuration,
+    DefaultDunningPolicy,
+    RefundContract,
+    NextSubId,
 }
 
 /// Tagged persistent storage keys.
@@ -24,16 +39,37 @@ enum PersistentKey {
     Lifecycle(u64),
     Window(u64),
     UserCap(Address),
+    UserCapPeriod(Address),
     UserSpent(Address),
+    MerchantCap(Address, Address),
+    MerchantSpent(Address, Address),
+    DunningPolicy(Address),
     MultiSig(u64, u64),
     TeamThreshold(u64),
     SigningWindow(u64),
+    TeamSigners(u64),
+    FxPricing(u64),
+    BlindingSaltHash(u64),
+    LogNonce(u64),
+    PendingPlan(u64),
+    TrialUsed(Address, Address),
+    LastCharge(u64),
+    PendingRefund(u64),
+    OwnerSubCount(Address),
+    OwnerSub(Address, u32),
+    MerchantSubCount(Address),
+    MerchantSub(Address, u32),
+    MerchantStats(Address),
+    UsageReport(u64, u64),
+    RegistryLink(u64),
 }
 
 /// Data stored for an active renewal lock
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct RenewalLockData {
+    /// Agent that acquired the lock; only it may renew or release
+    pub holder: Address,
     pub locked_at: u32,
     pub lock_timeout: u32,
 }
@@ -56,6 +92,10 @@ pub enum SubscriptionState {
     Retrying,
     Failed,
     Cancelled,
+    /// Paused by the owner; resumes automatically at `LifecycleTimestamps::resume_at`
+    Paused,
+    /// A charge failed but the service stays entitled until `LifecycleTimestamps::grace_ends_at`
+    GracePeriod,
 }
 
 /// Core subscription data stored on-chain
@@ -64,6 +104,8 @@ pub enum SubscriptionState {
 pub struct SubscriptionData {
     pub owner: Address,
     pub merchant: Address,
+    /// SEP-41 token the renewal charge is pulled in
+    pub token: Address,
     pub amount: i128,
     pub frequency: u64,
     pub spending_cap: i128,
@@ -71,6 +113,142 @@ pub struct SubscriptionData {
     pub state: SubscriptionState,
     pub failure_count: u32,
     pub last_attempt_ledger: u32,
+    /// Team whose multi-sig policy gates renewals above its threshold
+    pub team_id: Option<u64>,
+    /// Trial and introductory pricing, covered by `integrity_hash` (all zero if none)
+    pub intro: IntroOffer,
+    /// Renewals charged so far
+    pub paid_cycles: u32,
+    /// Graduated unit prices for metered usage, covered by `integrity_hash` (empty if unmetered)
+    pub usage_tiers: Vec<UsageTier>,
+}
+
+/// One band of a graduated usage price table. Units above the previous tier's
+/// `up_to` and up to this one's cost `unit_price` each; the last tier is unbounded.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UsageTier {
+    pub up_to: u64,
+    pub unit_price: i128,
+}
+
+/// Usage a merchant reported for one billing cycle, priced when it was reported.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UsageReport {
+    pub units: u64,
+    /// Metered charge added to the base amount of the cycle
+    pub amount: i128,
+    pub reported_at: u64,
+}
+
+/// Trial and introductory pricing offered when a subscription is created.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IntroOffer {
+    /// Seconds after creation during which nothing is charged (0 for no trial)
+    pub trial_secs: u64,
+    /// Price of each of the first `intro_cycles` renewals
+    pub intro_amount: i128,
+    pub intro_cycles: u32,
+}
+
+/// Fiat pricing terms for a subscription whose `amount` is quoted in a fiat currency.
+/// The token amount is computed from the FX oracle at charge time.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FxPricing {
+    /// Currency the subscription is priced in (e.g. "USD")
+    pub quote_currency: String,
+    /// Oracle symbol of the settlement token (e.g. "XLM")
+    pub token_currency: String,
+    /// Oracle rate (8 decimals) snapshotted when the owner set the bounds
+    pub reference_rate: i128,
+    /// Maximum deviation from `reference_rate` the owner accepts, in basis points
+    pub max_slippage_bps: u32,
+}
+
+/// Plan change scheduled by `change_plan`, applied by the first renewal at or
+/// after `effective_at` (the end of the current period).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingPlan {
+    pub amount: i128,
+    pub frequency: u64,
+    pub effective_at: u64,
+}
+
+/// Delays between retries of a failed charge, in ledgers.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RetryBackoff {
+    /// `base * 2^(n-1)` ledgers before the retry after the n-th failure
+    Exponential(u32),
+    /// Delay before the retry after each failure; the last entry repeats
+    Fixed(soroban_sdk::Vec<u32>),
+}
+
+/// What happens to a subscription once its dunning attempts are exhausted.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FinalAction {
+    /// Move to `Failed`; the subscription is kept but never renewed again
+    Suspend,
+    /// Move to `Cancelled`
+    Cancel,
+}
+
+/// How `cancel_sub` ends a subscription.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum CancelMode {
+    /// Cancel now and refund the unused part of the paid period
+    Immediate = 1,
+    /// Stay active until the paid period ends, then cancel without charging again
+    AtPeriodEnd = 2,
+}
+
+/// `SubscriptionRegistry` entry a subscription reports its renewals and
+/// deactivation to.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegistryLink {
+    pub registry: Address,
+    pub subscription_id: BytesN<32>,
+}
+
+/// Retry policy for failed charges, set per merchant or as the contract default.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DunningPolicy {
+    /// Failed charges (in a row) before `final_action` is applied
+    pub max_attempts: u32,
+    pub backoff: RetryBackoff,
+    /// Seconds the service stays entitled after the first failed charge (0 for none)
+    pub grace_secs: u64,
+    pub final_action: FinalAction,
+}
+
+/// Aggregates over a merchant's subscriptions, updated on every state change.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MerchantStats {
+    /// Subscriptions still billing: `Active`, `Retrying` or `GracePeriod`
+    pub active_count: u32,
+    /// Monthly recurring revenue of those subscriptions, with 30-day months
+    pub mrr: i128,
+    /// Subscriptions suspended after exhausting their dunning attempts
+    pub failed_count: u32,
+}
+
+/// Amount a user has spent in the current window of their spending cap.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpendWindow {
+    /// Start timestamp of the window the amount was spent in
+    pub period_start: u64,
+    pub spent: i128,
 }
 
 /// Immutable audit timestamps for subscription lifecycle events.
@@ -82,6 +260,16 @@ pub struct LifecycleTimestamps {
     pub activated_at: u64,
     pub last_renewed_at: u64,
     pub canceled_at: u64,
+    pub paused_at: u64,
+    /// Scheduled automatic resumption while paused, 0 otherwise
+    pub resume_at: u64,
+    pub resumed_at: u64,
+    /// End of the grace period after a failed charge, 0 outside one
+    pub grace_ends_at: u64,
+    /// `CancelMode` the owner cancelled with, 0 if never cancelled by the owner
+    pub cancel_mode: u32,
+    /// Scheduled end of an `AtPeriodEnd` cancellation, 0 if none is pending
+    pub cancel_at: u64,
 }
 
 /// Events for subscription renewal tracking
@@ -96,6 +284,7 @@ pub struct RenewalFailed {
     pub sub_id: u64,
     pub failure_count: u32,
     pub ledger: u32,
+    pub reason: u32, // 1=transfer_failed, 2=fx_paused, 3=fx_stale, 4=fx_slippage, 100+code=PaymentAdapterError
 }
 
 #[contractevent]
@@ -138,6 +327,7 @@ pub struct IntegrityViolation {
 #[contractevent]
 pub struct RenewalLockAcquired {
     pub sub_id: u64,
+    pub holder: Address,
     pub locked_at: u32,
     pub lock_timeout: u32,
 }
@@ -158,7 +348,8 @@ pub struct RenewalLockExpired {
 #[contractevent]
 pub struct LifecycleTimestampUpdated {
     pub sub_id: u64,
-    pub event_kind: u32, // 1=created, 2=activated, 3=renewed, 4=canceled
+    // 1=created, 2=activated, 3=renewed, 4=canceled, 5=paused, 6=resumed, 7=grace started
+    pub event_kind: u32,
     pub timestamp: u64,
 }
 
@@ -183,10 +374,93 @@ pub struct GlobalCapViolated {
     pub cap: i128,
 }
 
+#[contractevent]
+pub struct MerchantCapViolated {
+    pub owner: Address,
+    pub merchant: Address,
+    pub amount: i128,
+    pub cap: i128,
+}
+
 #[contractevent]
 pub struct UserCapUpdated {
     pub user: Address,
     pub cap: i128,
+    pub period_secs: u64,
+}
+
+#[contractevent]
+pub struct MerchantCapUpdated {
+    pub user: Address,
+    pub merchant: Address,
+    pub cap: i128,
+}
+
+#[contractevent]
+pub struct FxPricingUpdated {
+    pub sub_id: u64,
+    pub quote_currency: String,
+    pub reference_rate: i128,
+    pub max_slippage_bps: u32,
+}
+
+#[contractevent]
+pub struct FxCharged {
+    pub sub_id: u64,
+    pub quoted_amount: i128,
+    pub token_amount: i128,
+    pub rate: i128,
+}
+
+#[contractevent]
+pub struct PlanChanged {
+    pub sub_id: u64,
+    pub amount: i128,
+    pub frequency: u64,
+    /// Prorated difference charged for the rest of the period (0 if none)
+    pub prorated_charge: i128,
+}
+
+#[contractevent]
+pub struct PlanChangeScheduled {
+    pub sub_id: u64,
+    pub amount: i128,
+    pub frequency: u64,
+    pub effective_at: u64,
+}
+
+#[contractevent]
+pub struct UsageReported {
+    pub sub_id: u64,
+    pub cycle_id: u64,
+    pub units: u64,
+    pub amount: i128,
+}
+
+#[contractevent]
+pub struct RegistryLinked {
+    pub sub_id: u64,
+    pub registry: Address,
+    pub subscription_id: BytesN<32>,
+}
+
+#[contractevent]
+pub struct CancellationScheduled {
+    pub sub_id: u64,
+    pub cancel_at: u64,
+}
+
+#[contractevent]
+pub struct CancellationRefundQueued {
+    pub sub_id: u64,
+    pub amount: i128,
+}
+
+#[contractevent]
+pub struct CancellationRefunded {
+    pub sub_id: u64,
+    pub payment_ref: u64,
+    pub amount: i128,
 }
 
 /// Billing window for a subscription renewal
@@ -205,6 +479,8 @@ pub enum MultiSigStatus {
     Approved = 1,
     Cancelled = 2,
     Expired = 3,
+    /// Used by `renew` to authorize a renewal; cannot be used again
+    Consumed = 4,
 }
 
 /// On-chain multi-sig approval request
@@ -217,12 +493,23 @@ pub struct MultiSigRequest {
     pub amount: i128,
     pub requester: Address,
     pub required_signers: soroban_sdk::Vec<Address>,
+    /// Signatures needed for approval, copied from the team policy
+    pub quorum: u32,
     pub collected_signers: soroban_sdk::Vec<Address>,
     pub created_at: u64,
     pub expires_at: u64,
     pub status: MultiSigStatus,
 }
 
+/// Admin-configured signer set for a team's multi-sig requests
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TeamSignerPolicy {
+    pub signers: soroban_sdk::Vec<Address>,
+    /// Number of distinct signers needed to approve a request
+    pub quorum: u32,
+}
+
 // ── Multi-sig events ──────────────────────────────────────────────
 
 #[contractevent]
@@ -268,23 +555,96 @@ pub struct MultiSigExpired {
 pub struct MultiSigAuditLog {
     pub sub_id: u64,
     pub request_id: u64,
-    /// 1=requested, 2=signed, 3=approved, 4=cancelled, 5=expired
+    /// 1=requested, 2=signed, 3=approved, 4=cancelled, 5=expired, 6=consumed
     pub decision: u32,
     pub actor: Address,
     pub timestamp: u64,
 }
 
+#[contractevent]
+pub struct KeeperBountyPaid {
+    pub sub_id: u64,
+    pub keeper: Address,
+    pub amount: i128,
+}
+
+#[contractevent]
+pub struct TeamAssigned {
+    pub sub_id: u64,
+    pub team_id: u64,
+}
+
 #[contractevent]
 pub struct TeamThresholdUpdated {
     pub team_id: u64,
     pub threshold: i128,
 }
 
+#[contractevent]
+pub struct TeamSignersUpdated {
+    pub team_id: u64,
+    pub signer_count: u32,
+    pub quorum: u32,
+}
+
 /// Default threshold: 10_000_000 stroops ($100 USD equivalent)
 const DEFAULT_MULTISIG_THRESHOLD: i128 = 10_000_000;
 /// Default signing window: 24 hours in seconds
 const DEFAULT_SIGNING_WINDOW_SECS: u64 = 86_400;
 
+/// RenewalFailed reason: the token transfer (or adapter call) itself failed
+const FAILURE_TRANSFER: u32 = 1;
+/// RenewalFailed reason: the FX oracle is paused
+const FAILURE_FX_PAUSED: u32 = 2;
+/// RenewalFailed reason: the FX rate is missing, stale or signed by a removed signer
+const FAILURE_FX_STALE: u32 = 3;
+/// RenewalFailed reason: the FX rate moved beyond the owner's slippage bound
+const FAILURE_FX_SLIPPAGE: u32 = 4;
+/// RenewalFailed reason offset for `PaymentAdapterError` codes
+const FAILURE_ADAPTER_BASE: u32 = 100;
+/// Basis-point denominator for slippage bounds and keeper bounties
+const BPS_DENOMINATOR: i128 = 10_000;
+/// Default keeper bounty: 0.1% of each charge settled through `process_due`
+const DEFAULT_KEEPER_BOUNTY_BPS: u32 = 10;
+/// Upper bound for the keeper bounty: 5% of the charge
+const MAX_KEEPER_BOUNTY_BPS: u32 = 500;
+/// Default maximum pause: 90 days in seconds
+const DEFAULT_MAX_PAUSE_SECS: u64 = 7_776_000;
+
+/// Default dunning: failed charges before a subscription is suspended
+const DEFAULT_DUNNING_MAX_ATTEMPTS: u32 = 4;
+/// Default dunning: first retry delay (~1 hour), doubling after each failure
+const DEFAULT_DUNNING_BASE_DELAY_LEDGERS: u32 = 720;
+/// Longest renewal lock: ~1 day of ledgers at 5 seconds each
+const MAX_LOCK_TIMEOUT_LEDGERS: u32 = 17_280;
+/// Largest page returned by `list_by_owner` / `list_by_merchant`
+const MAX_PAGE_SIZE: u32 = 100;
+/// Month length used to normalise amounts into MRR (30 days)
+const SECS_PER_MONTH: i128 = 2_592_000;
+
+/// Hash of an owner's blinding salt: SHA-256(salt). `init_sub` takes and stores
+/// only this hash; the salt itself stays with the owner.
+pub fn hash_blinding_salt(env: &Env, salt: &BytesN<32>) -> BytesN<32> {
+    env.crypto()
+        .sha256(&Bytes::from_slice(env, &salt.to_array()))
+        .into()
+}
+
+/// Blinding factor for the `nonce`-th audit commitment of a subscription:
+/// SHA-256(salt_hash || sub_id || nonce), integers big-endian, where
+/// `salt_hash = hash_blinding_salt(salt)`.
+pub fn derive_blinding_factor(
+    env: &Env,
+    salt_hash: &BytesN<32>,
+    sub_id: u64,
+    nonce: u64,
+) -> BytesN<32> {
+    let mut payload = Bytes::from_slice(env, &salt_hash.to_array());
+    payload.extend_from_slice(&sub_id.to_be_bytes());
+    payload.extend_from_slice(&nonce.to_be_bytes());
+    env.crypto().sha256(&payload).into()
+}
+
 #[contract]
 pub struct SubscriptionRenewalContract;
 
@@ -311,6 +671,22 @@ impl SubscriptionRenewalContract {
         admin.require_auth();
     }
 
+    /// Set the `ContractUpgradeGovernance` contract allowed to call `upgrade`
+    /// (admin for the first call, the current governance contract afterwards).
+    pub fn set_governance(env: Env, governance: Address) {
+        governed_upgrade::set_governance(&env, &governance, || Self::require_admin(&env));
+    }
+
+    /// Query the configured governance contract, if any.
+    pub fn get_governance(env: Env) -> Option<Address> {
+        governed_upgrade::get_governance(&env)
+    }
+
+    /// Replace this contract's WASM. Only callable by the governance contract.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        governed_upgrade::upgrade(&env, new_wasm_hash);
+    }
+
     /// Pause or unpause all renewal execution. Admin only.
     pub fn set_paused(env: Env, paused: bool) {
         Self::require_admin(&env);
@@ -334,14 +710,152 @@ impl SubscriptionRenewalContract {
             .set(&ContractKey::LoggingContract, &address);
     }
 
+    /// Set the payment adapter address. Admin only.
+    /// Once set, renewals settle through `PaymentAdapterContract::settle_renewal`, so
+    /// subscription amounts are denominated in the adapter's display units and owners
+    /// grant their token allowance to the adapter instead of this contract. The adapter
+    /// admin must register this contract with `set_caller`.
+    pub fn set_payment_adapter(env: Env, address: Address) {
+        Self::require_admin(&env);
+        env.storage()
+            .instance()
+            .set(&ContractKey::PaymentAdapter, &address);
+    }
+
+    /// Query the configured payment adapter, if any.
+    pub fn get_payment_adapter(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ContractKey::PaymentAdapter)
+    }
+
+    /// Set the FX oracle used to price fiat-quoted subscriptions. Admin only.
+    pub fn set_fx_oracle(env: Env, address: Address) {
+        Self::require_admin(&env);
+        env.storage()
+            .instance()
+            .set(&ContractKey::FxOracle, &address);
+    }
+
+    /// Query the configured FX oracle, if any.
+    pub fn get_fx_oracle(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ContractKey::FxOracle)
+    }
+
+    /// Set the `AgentRegistry` whose `Renewals` scope gates renewal locks. Admin only.
+    pub fn set_agent_registry(env: Env, address: Address) {
+        Self::require_admin(&env);
+        env.storage()
+            .instance()
+            .set(&ContractKey::AgentRegistry, &address);
+    }
+
+    /// Query the configured agent registry, if any.
+    pub fn get_agent_registry(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ContractKey::AgentRegistry)
+    }
+
+    /// Set the `SubscriptionRefundContract` that pays out prorated refunds
+    /// on immediate cancellation. Admin only.
+    pub fn set_refund_contract(env: Env, address: Address) {
+        Self::require_admin(&env);
+        env.storage()
+            .instance()
+            .set(&ContractKey::RefundContract, &address);
+    }
+
+    /// Query the configured refund contract, if any.
+    pub fn get_refund_contract(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ContractKey::RefundContract)
+    }
+
+    // ── Registry linkage ──────────────────────────────────────────
+
+    /// Link a subscription to its entry in a `SubscriptionRegistry`. Owner only.
+    /// The registry records this contract and `sub_id`; from then on successful
+    /// renewals advance its `next_renewal`, and cancellation or a final failure
+    /// marks it inactive.
+    pub fn link_registry(env: Env, sub_id: u64, registry: Address, subscription_id: BytesN<32>) {
+        let data = Self::get_sub(env.clone(), sub_id);
+        data.owner.require_auth();
+        let key = PersistentKey::RegistryLink(sub_id);
+        if env.storage().persistent().has(&key) {
+            panic!("Registry already linked");
+        }
+
+        SubscriptionRegistryClient::new(&env, &registry).link_renewal(
+            &subscription_id,
+            &data.owner,
+            &env.current_contract_address(),
+            &sub_id,
+        );
+        let link = RegistryLink {
+            registry: registry.clone(),
+            subscription_id: subscription_id.clone(),
+        };
+        env.storage().persistent().set(&key, &link);
+
+        RegistryLinked {
+            sub_id,
+            registry,
+            subscription_id,
+        }
+        .publish(&env);
+    }
+
+    /// Registry entry a subscription is linked to, if any.
+    pub fn get_registry_link(env: Env, sub_id: u64) -> Option<RegistryLink> {
+        env.storage()
+            .persistent()
+            .get(&PersistentKey::RegistryLink(sub_id))
+    }
+
+    /// Report a successful renewal (`renewed`) or a deactivation to the linked registry.
+    /// The registry is chosen by the owner, so its failures are ignored rather than
+    /// allowed to revert the charge or cancellation (or a whole `process_due` batch).
+    fn notify_registry(env: &Env, sub_id: u64, renewed: bool) {
+        if let Some(link) = Self::get_registry_link(env.clone(), sub_id) {
+            let registry = SubscriptionRegistryClient::new(env, &link.registry);
+            if renewed {
+                let _ = registry.try_record_renewal(&link.subscription_id);
+            } else {
+                let _ = registry.try_record_deactivation(&link.subscription_id);
+            }
+        }
+    }
+
     // ── Renewal lock management ────────────────────────────────────
 
+    /// Whether `agent` has the `Renewals` scope in the configured agent registry.
+    fn has_renewals_scope(env: &Env, agent: &Address) -> bool {
+        env.storage()
+            .instance()
+            .get::<_, Address>(&ContractKey::AgentRegistry)
+            .is_some_and(|registry| {
+                AgentRegistryClient::new(env, &registry).has_scope(agent, &Scope::Renewals)
+            })
+    }
+
     /// Acquire a processing lock for a subscription renewal.
     /// Prevents concurrent renewal execution by multiple workers.
-    pub fn acquire_renewal_lock(env: Env, sub_id: u64, lock_timeout: u32) {
+    /// `holder` must be an agent with the `Renewals` scope in the agent registry;
+    /// only it can then `renew` or release the lock. `lock_timeout` is in ledgers,
+    /// between 1 and `MAX_LOCK_TIMEOUT_LEDGERS`.
+    pub fn acquire_renewal_lock(env: Env, holder: Address, sub_id: u64, lock_timeout: u32) {
         if Self::is_paused(env.clone()) {
             panic!("Protocol is paused");
         }
+        if lock_timeout == 0 || lock_timeout > MAX_LOCK_TIMEOUT_LEDGERS {
+            panic!("Invalid lock timeout");
+        }
+
+        let registry: Address = env
+            .storage()
+            .instance()
+            .get(&ContractKey::AgentRegistry)
+            .expect("Agent registry not set");
+        holder.require_auth();
+        if !AgentRegistryClient::new(&env, &registry).has_scope(&holder, &Scope::Renewals) {
+            panic!("Agent missing Renewals scope");
+        }
 
         let lock_key = PersistentKey::RenewalLock(sub_id);
         let current_ledger = env.ledger().sequence();
@@ -352,7 +866,7 @@ impl SubscriptionRenewalContract {
             .get::<PersistentKey, RenewalLockData>(&lock_key)
         {
             // Check if existing lock has expired
-            if current_ledger < existing.locked_at + existing.lock_timeout {
+            if current_ledger < existing.locked_at.saturating_add(existing.lock_timeout) {
                 panic!("Renewal lock active");
             }
             // Lock expired — emit expiry event and allow re-acquisition
@@ -365,6 +879,7 @@ impl SubscriptionRenewalContract {
         }
 
         let lock_data = RenewalLockData {
+            holder: holder.clone(),
             locked_at: current_ledger,
             lock_timeout,
         };
@@ -372,18 +887,22 @@ impl SubscriptionRenewalContract {
 
         RenewalLockAcquired {
             sub_id,
+            holder,
             locked_at: current_ledger,
             lock_timeout,
         }
         .publish(&env);
     }
 
-    /// Release a processing lock for a subscription renewal.
+    /// Release a processing lock for a subscription renewal. Lock holder only.
     pub fn release_renewal_lock(env: Env, sub_id: u64) {
         let lock_key = PersistentKey::RenewalLock(sub_id);
-        if !env.storage().persistent().has(&lock_key) {
-            panic!("No renewal lock to release");
-        }
+        let lock_data: RenewalLockData = env
+            .storage()
+            .persistent()
+            .get(&lock_key)
+            .expect("No renewal lock to release");
+        lock_data.holder.require_auth();
 
         let current_ledger = env.ledger().sequence();
         env.storage().persistent().remove(&lock_key);
@@ -403,40 +922,161 @@ impl SubscriptionRenewalContract {
 
     // ── Subscription logic ────────────────────────────────────────
 
-    /// Initialize a subscription
+    /// Initialize a subscription and return its contract-assigned id.
+    /// Renewal charges are pulled from `owner` to `merchant` in `token`, so the
+    /// owner must grant this contract a token allowance before renewals can settle.
+    /// `amount` and `frequency` must both be positive.
+    /// `blinding_salt_hash` is `hash_blinding_salt(salt)` for a salt the owner keeps
+    /// off-chain. It seeds the blinding factors of this subscription's audit
+    /// commitments (see `derive_blinding_factor`); the owner later opens them by
+    /// presenting the salt to `verify_log_disclosure`.
+    // Entrypoint arguments are the flat XDR ABI used by the SDK bindings
+    #[allow(clippy::too_many_arguments)]
     pub fn init_sub(
         env: Env,
         owner: Address,
         merchant: Address,
+        token: Address,
         amount: i128,
         frequency: u64,
         spending_cap: i128,
-        sub_id: u64,
-    ) {
+        blinding_salt_hash: BytesN<32>,
+    ) -> u64 {
+        Self::create_sub(
+            &env,
+            owner,
+            merchant,
+            token,
+            amount,
+            frequency,
+            spending_cap,
+            blinding_salt_hash,
+            IntroOffer {
+                trial_secs: 0,
+                intro_amount: 0,
+                intro_cycles: 0,
+            },
+        )
+    }
+
+    /// Initialize a subscription that starts with a trial and/or introductory price.
+    /// Nothing is charged for `trial_secs` after creation, and converting out of the
+    /// trial takes an owner approval (`renew`); `process_due` only picks the
+    /// subscription up after that first charge. The first `intro_cycles` renewals
+    /// cost at most `intro_amount`. Each owner gets one trial per merchant.
+    /// Returns the contract-assigned subscription id.
+    // Same flat argument list as `init_sub`, plus the offer
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_sub_with_intro(
+        env: Env,
+        owner: Address,
+        merchant: Address,
+        token: Address,
+        amount: i128,
+        frequency: u64,
+        spending_cap: i128,
+        blinding_salt_hash: BytesN<32>,
+        intro: IntroOffer,
+    ) -> u64 {
+        if intro.trial_secs == 0 && intro.intro_cycles == 0 {
+            panic!("Empty introductory offer");
+        }
+        if intro.intro_cycles > 0 && (intro.intro_amount <= 0 || intro.intro_amount > amount) {
+            panic!("Invalid introductory price");
+        }
+        if intro.trial_secs > 0 {
+            let trial_key = PersistentKey::TrialUsed(owner.clone(), merchant.clone());
+            if env.storage().persistent().has(&trial_key) {
+                panic!("Trial already used with this merchant");
+            }
+            env.storage().persistent().set(&trial_key, &true);
+        }
+
+        Self::create_sub(
+            &env,
+            owner,
+            merchant,
+            token,
+            amount,
+            frequency,
+            spending_cap,
+            blinding_salt_hash,
+            intro,
+        )
+    }
+
+    /// Whether `owner` has already started a trial with `merchant`.
+    pub fn has_used_trial(env: Env, owner: Address, merchant: Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&PersistentKey::TrialUsed(owner, merchant))
+    }
+
+    // Takes the entrypoints' arguments as they are
+    #[allow(clippy::too_many_arguments)]
+    fn create_sub(
+        env: &Env,
+        owner: Address,
+        merchant: Address,
+        token: Address,
+        amount: i128,
+        frequency: u64,
+        spending_cap: i128,
+        blinding_salt_hash: BytesN<32>,
+        intro: IntroOffer,
+    ) -> u64 {
         owner.require_auth();
 
-        let mut integrity_data = soroban_sdk::Vec::<soroban_sdk::Val>::new(&env);
-        integrity_data.push_back(merchant.into_val(&env));
-        integrity_data.push_back(amount.into_val(&env));
-        integrity_data.push_back(frequency.into_val(&env));
-        integrity_data.push_back(spending_cap.into_val(&env));
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+        if frequency == 0 {
+            panic!("Frequency must be positive");
+        }
 
-        // Use a simple hash of the vector of values
-        let integrity_hash = env.crypto().sha256(&integrity_data.to_xdr(&env));
+        let sub_id: u64 = env
+            .storage()
+            .instance()
+            .get(&ContractKey::NextSubId)
+            .unwrap_or(1);
+        env.storage()
+            .instance()
+            .set(&ContractKey::NextSubId, &(sub_id + 1));
 
-        let key = PersistentKey::Subscription(sub_id);
-        let data = SubscriptionData {
+        // Index the subscription under its owner and merchant
+        let position = Self::next_index(env, PersistentKey::OwnerSubCount(owner.clone()));
+        env.storage()
+            .persistent()
+            .set(&PersistentKey::OwnerSub(owner.clone(), position), &sub_id);
+        let position = Self::next_index(env, PersistentKey::MerchantSubCount(merchant.clone()));
+        env.storage().persistent().set(
+            &PersistentKey::MerchantSub(merchant.clone(), position),
+            &sub_id,
+        );
+
+        env.storage().persistent().set(
+            &PersistentKey::BlindingSaltHash(sub_id),
+            &blinding_salt_hash,
+        );
+
+        let mut data = SubscriptionData {
             owner,
             merchant,
+            token,
             amount,
             frequency,
             spending_cap,
-            integrity_hash: integrity_hash.into(),
+            integrity_hash: BytesN::from_array(env, &[0; 32]),
             state: SubscriptionState::Active,
             failure_count: 0,
             last_attempt_ledger: 0,
+            team_id: None,
+            intro,
+            paid_cycles: 0,
+            usage_tiers: Vec::new(env),
         };
-        env.storage().persistent().set(&key, &data);
+        data.integrity_hash = Self::compute_integrity_hash(env, &data);
+        Self::save_sub(env, sub_id, &data);
 
         // Initialize lifecycle timestamps
         let now = env.ledger().timestamp();
@@ -445,6 +1085,12 @@ impl SubscriptionRenewalContract {
             activated_at: now,
             last_renewed_at: 0,
             canceled_at: 0,
+            paused_at: 0,
+            resume_at: 0,
+            resumed_at: 0,
+            grace_ends_at: 0,
+            cancel_mode: 0,
+            cancel_at: 0,
         };
         let lc_key = PersistentKey::Lifecycle(sub_id);
         env.storage().persistent().set(&lc_key, &lifecycle);
@@ -454,58 +1100,390 @@ impl SubscriptionRenewalContract {
             event_kind: 1,
             timestamp: now,
         }
-        .publish(&env);
+        .publish(env);
         LifecycleTimestampUpdated {
             sub_id,
             event_kind: 2,
             timestamp: now,
         }
-        .publish(&env);
+        .publish(env);
 
         // Record initialization log
         Self::record_log(
-            &env,
+            env,
             sub_id,
-            2,
-            soroban_sdk::String::from_str(&env, "Subscription initialized"),
+            EventTypeByte::SubscriptionCreate,
+            soroban_sdk::String::from_str(env, "Subscription initialized"),
         );
+
+        sub_id
     }
 
-    fn record_log(env: &Env, sub_id: u64, event_type: u32, data_str: soroban_sdk::String) {
-        if let Some(log_addr) = env
-            .storage()
-            .instance()
+    /// Bump the length of an index and return the position of the new entry.
+    fn next_index(env: &Env, count_key: PersistentKey) -> u32 {
+        let count: u32 = env.storage().persistent().get(&count_key).unwrap_or(0);
+        env.storage().persistent().set(&count_key, &(count + 1));
+        count
+    }
+
+    /// Store a subscription and move its contribution to the merchant's stats
+    /// from its previous state and plan to the new ones.
+    fn save_sub(env: &Env, sub_id: u64, data: &SubscriptionData) {
+        let key = PersistentKey::Subscription(sub_id);
+        let previous: Option<SubscriptionData> = env.storage().persistent().get(&key);
+        env.storage().persistent().set(&key, data);
+
+        let stats_key = PersistentKey::MerchantStats(data.merchant.clone());
+        let mut stats: MerchantStats = env
+            .storage()
+            .persistent()
+            .get(&stats_key)
+            .unwrap_or_default();
+        if let Some(previous) = previous {
+            let (active, mrr, failed) = Self::stats_contribution(&previous);
+            stats.active_count -= active;
+            stats.mrr -= mrr;
+            stats.failed_count -= failed;
+        }
+        let (active, mrr, failed) = Self::stats_contribution(data);
+        stats.active_count += active;
+        stats.mrr += mrr;
+        stats.failed_count += failed;
+        env.storage().persistent().set(&stats_key, &stats);
+    }
+
+    /// What a subscription adds to its merchant's stats: (active, MRR, failed).
+    fn stats_contribution(data: &SubscriptionData) -> (u32, i128, u32) {
+        match data.state {
+            SubscriptionState::Active
+            | SubscriptionState::Retrying
+            | SubscriptionState::GracePeriod => {
+                (1, data.amount * SECS_PER_MONTH / data.frequency as i128, 0)
+            }
+            SubscriptionState::Failed => (0, 0, 1),
+            SubscriptionState::Cancelled | SubscriptionState::Paused => (0, 0, 0),
+        }
+    }
+
+    /// Hash of the merchant-facing subscription terms, checked on every renewal.
+    /// Subscriptions without an introductory offer or usage tiers hash the same
+    /// terms as before those existed.
+    fn compute_integrity_hash(env: &Env, data: &SubscriptionData) -> soroban_sdk::BytesN<32> {
+        let mut integrity_data = soroban_sdk::Vec::<soroban_sdk::Val>::new(env);
+        integrity_data.push_back(data.merchant.into_val(env));
+        integrity_data.push_back(data.token.into_val(env));
+        integrity_data.push_back(data.amount.into_val(env));
+        integrity_data.push_back(data.frequency.into_val(env));
+        integrity_data.push_back(data.spending_cap.into_val(env));
+        let intro = &data.intro;
+        if intro.trial_secs > 0 || intro.intro_cycles > 0 {
+            integrity_data.push_back(intro.trial_secs.into_val(env));
+            integrity_data.push_back(intro.intro_amount.into_val(env));
+            integrity_data.push_back(intro.intro_cycles.into_val(env));
+        }
+        if !data.usage_tiers.is_empty() {
+            integrity_data.push_back(data.usage_tiers.into_val(env));
+        }
+
+        // Use a simple hash of the vector of values
+        env.crypto().sha256(&integrity_data.to_xdr(env)).into()
+    }
+
+    /// Commit to a subscription event in the logging contract, if one is configured.
+    /// The commitment is `compute_commitment_hash(event_type, SHA-256(XDR(sub_id, data_str)), r)`
+    /// with `r = derive_blinding_factor(salt_hash, sub_id, nonce)`, where `nonce` counts this
+    /// subscription's logged events. Only the salt hash is stored here, but it is enough to
+    /// recompute `r`, so commitments stay linkable for anyone reading this contract's
+    /// storage; the salt itself is what proves ownership in `verify_log_disclosure`.
+    fn record_log(env: &Env, sub_id: u64, event_type: EventTypeByte, data_str: String) {
+        Self::record_commitment(env, sub_id, event_type, (sub_id, data_str).to_xdr(env));
+    }
+
+    /// Commit to arbitrary event data (XDR) for a subscription, blinded like `record_log`.
+    fn record_commitment(env: &Env, sub_id: u64, event_type: EventTypeByte, event_data: Bytes) {
+        if let Some(log_addr) = env
+            .storage()
+            .instance()
             .get::<_, Address>(&ContractKey::LoggingContract)
         {
-            let logging_client = SubscriptionLoggingContractClient::new(env, &log_addr);
-            let payload: Bytes = (sub_id, event_type, data_str).to_xdr(env);
-            let commitment_hash = soroban_sdk::BytesN::from_array(
+            let salt_hash: BytesN<32> = env
+                .storage()
+                .persistent()
+                .get(&PersistentKey::BlindingSaltHash(sub_id))
+                .expect("Blinding salt not found");
+            let nonce_key = PersistentKey::LogNonce(sub_id);
+            let nonce: u64 = env.storage().persistent().get(&nonce_key).unwrap_or(0);
+            env.storage().persistent().set(&nonce_key, &(nonce + 1));
+
+            let blinding_factor = derive_blinding_factor(env, &salt_hash, sub_id, nonce);
+            let event_data_hash = commitment::hash_event_data(env, &event_data);
+            let commitment_hash = commitment::compute_commitment_hash(
                 env,
-                &env.crypto().sha256(&payload).to_array(),
+                event_type as u32,
+                &event_data_hash,
+                &blinding_factor,
             );
-            logging_client.record_commitment(&commitment_hash);
+
+            let logging_client = SubscriptionLoggingContractClient::new(env, &log_addr);
+            logging_client.record_commitment(&env.current_contract_address(), &commitment_hash);
         }
     }
 
-    /// Explicitly cancel a subscription
-    pub fn cancel_sub(env: Env, sub_id: u64) {
-        let key = PersistentKey::Subscription(sub_id);
-        let mut data: SubscriptionData = env
+    /// Open the `nonce`-th audit commitment of a subscription, logged at
+    /// `commitment_index`, with the owner's blinding salt. Returns false when the
+    /// salt does not match the hash registered at `init_sub` or the commitment does
+    /// not match `event_type` and `event_data_hash`.
+    pub fn verify_log_disclosure(
+        env: Env,
+        sub_id: u64,
+        nonce: u64,
+        commitment_index: u64,
+        event_type: u32,
+        event_data_hash: BytesN<32>,
+        salt: BytesN<32>,
+    ) -> bool {
+        let log_addr: Address = env
+            .storage()
+            .instance()
+            .get(&ContractKey::LoggingContract)
+            .expect("Logging contract not set");
+        let salt_hash = hash_blinding_salt(&env, &salt);
+        let stored: Option<BytesN<32>> = env
             .storage()
             .persistent()
-            .get(&key)
-            .expect("Subscription not found");
+            .get(&PersistentKey::BlindingSaltHash(sub_id));
+        if stored != Some(salt_hash.clone()) {
+            return false;
+        }
 
+        let blinding_factor = derive_blinding_factor(&env, &salt_hash, sub_id, nonce);
+        SubscriptionLoggingContractClient::new(&env, &log_addr).verify_disclosure(
+            &commitment_index,
+            &event_type,
+            &event_data_hash,
+            &blinding_factor,
+            &None,
+        )
+    }
+
+    /// Number of audit commitments logged for a subscription so far.
+    /// The next commitment uses this value as its blinding nonce.
+    pub fn get_log_nonce(env: Env, sub_id: u64) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&PersistentKey::LogNonce(sub_id))
+            .unwrap_or(0)
+    }
+
+    /// Cancel a subscription. Owner only.
+    /// `Immediate` cancels now and refunds the unused part of the last paid
+    /// period through the refund contract, which also needs the merchant's
+    /// authorization. `AtPeriodEnd` keeps the subscription active until
+    /// `next_due_at`, after which it is cancelled without being charged again;
+    /// if that period is already over (or was never paid), it cancels now.
+    pub fn cancel_sub(env: Env, sub_id: u64, mode: CancelMode) {
+        let mut data = Self::get_sub(env.clone(), sub_id);
         data.owner.require_auth();
 
         if data.state == SubscriptionState::Cancelled {
             panic!("Subscription already cancelled");
         }
 
+        let now = env.ledger().timestamp();
+        match mode {
+            CancelMode::Immediate => {
+                Self::queue_refund(&env, sub_id, &data, now);
+                Self::finish_cancel(&env, sub_id, &mut data, mode, now);
+            }
+            CancelMode::AtPeriodEnd => {
+                if data.state != SubscriptionState::Active {
+                    panic!("Only active subscriptions can cancel at period end");
+                }
+                let lc_key = PersistentKey::Lifecycle(sub_id);
+                let mut lifecycle = Self::get_lifecycle(env.clone(), sub_id);
+                if lifecycle.cancel_at > 0 {
+                    panic!("Cancellation already scheduled");
+                }
+                let cancel_at = Self::next_due_at(env.clone(), sub_id);
+                // No paid period left to run out: cancel now
+                if cancel_at <= now {
+                    Self::finish_cancel(&env, sub_id, &mut data, mode, now);
+                    return;
+                }
+                lifecycle.cancel_mode = mode as u32;
+                lifecycle.cancel_at = cancel_at;
+                env.storage().persistent().set(&lc_key, &lifecycle);
+
+                CancellationScheduled { sub_id, cancel_at }.publish(&env);
+
+                Self::record_log(
+                    &env,
+                    sub_id,
+                    EventTypeByte::SubscriptionUpdate,
+                    soroban_sdk::String::from_str(&env, "Cancellation scheduled"),
+                );
+            }
+        }
+    }
+
+    /// Queue a refund of the unused part of the last paid period on immediate
+    /// cancellation: the last charge prorated over what is left of its period.
+    /// The merchant pays it out later with `settle_refund`, so cancelling never
+    /// needs the merchant's authorization or a configured refund contract.
+    fn queue_refund(env: &Env, sub_id: u64, data: &SubscriptionData, now: u64) {
+        if data.paid_cycles == 0 {
+            return;
+        }
+        let lifecycle = Self::get_lifecycle(env.clone(), sub_id);
+        let last_charge: i128 = env
+            .storage()
+            .persistent()
+            .get(&PersistentKey::LastCharge(sub_id))
+            .unwrap_or(0);
+        let period_end = lifecycle.last_renewed_at.saturating_add(data.frequency);
+        let unused = period_end.saturating_sub(now).min(data.frequency);
+        let refund = last_charge * unused as i128 / data.frequency as i128;
+        if refund <= 0 {
+            return;
+        }
+
+        env.storage()
+            .persistent()
+            .set(&PersistentKey::PendingRefund(sub_id), &refund);
+
+        CancellationRefundQueued {
+            sub_id,
+            amount: refund,
+        }
+        .publish(env);
+    }
+
+    /// Pay out the refund queued by an immediate cancellation. Merchant only.
+    /// The refund is recorded as a charge in the refund contract and refunded
+    /// from the merchant to the owner.
+    pub fn settle_refund(env: Env, sub_id: u64) {
+        let data = Self::get_sub(env.clone(), sub_id);
+        data.merchant.require_auth();
+
+        let pending_key = PersistentKey::PendingRefund(sub_id);
+        let refund: i128 = env
+            .storage()
+            .persistent()
+            .get(&pending_key)
+            .expect("No refund pending");
+        let refund_addr: Address = env
+            .storage()
+            .instance()
+            .get(&ContractKey::RefundContract)
+            .expect("Refund contract not configured");
+        env.storage().persistent().remove(&pending_key);
+
+        // Derive a payment reference no other charge in the refund contract uses
+        let mut preimage = Bytes::new(&env);
+        preimage.append(&env.current_contract_address().to_xdr(&env));
+        preimage.append(&Bytes::from_array(&env, &sub_id.to_be_bytes()));
+        preimage.append(&Bytes::from_array(&env, &data.paid_cycles.to_be_bytes()));
+        let digest = env.crypto().sha256(&preimage).to_array();
+        let mut ref_bytes = [0u8; 8];
+        ref_bytes.copy_from_slice(&digest[..8]);
+        let payment_ref = u64::from_be_bytes(ref_bytes);
+
+        let refunds = SubscriptionRefundContractClient::new(&env, &refund_addr);
+        refunds.record_charge(
+            &data.merchant,
+            &payment_ref,
+            &sub_id,
+            &data.owner,
+            &data.merchant,
+            &data.token,
+            &refund,
+        );
+        refunds.process_refund(&data.merchant, &payment_ref);
+
+        CancellationRefunded {
+            sub_id,
+            payment_ref,
+            amount: refund,
+        }
+        .publish(&env);
+    }
+
+    /// Refund queued for a cancelled subscription and not yet settled (0 if none).
+    pub fn get_pending_refund(env: Env, sub_id: u64) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&PersistentKey::PendingRefund(sub_id))
+            .unwrap_or(0)
+    }
+
+    /// Move a subscription to `Cancelled`, recording how and when it was cancelled.
+    fn finish_cancel(
+        env: &Env,
+        sub_id: u64,
+        data: &mut SubscriptionData,
+        mode: CancelMode,
+        canceled_at: u64,
+    ) {
         data.state = SubscriptionState::Cancelled;
-        env.storage().persistent().set(&key, &data);
+        Self::save_sub(env, sub_id, data);
 
         // Update lifecycle timestamps
+        let lc_key = PersistentKey::Lifecycle(sub_id);
+        let mut lifecycle = Self::get_lifecycle(env.clone(), sub_id);
+        lifecycle.canceled_at = canceled_at;
+        lifecycle.cancel_mode = mode as u32;
+        lifecycle.cancel_at = 0;
+        env.storage().persistent().set(&lc_key, &lifecycle);
+
+        LifecycleTimestampUpdated {
+            sub_id,
+            event_kind: 4,
+            timestamp: canceled_at,
+        }
+        .publish(env);
+
+        // Record cancellation log
+        Self::record_log(
+            env,
+            sub_id,
+            EventTypeByte::SubscriptionCancel,
+            soroban_sdk::String::from_str(env, "Subscription cancelled"),
+        );
+
+        // Emit state transition event
+        StateTransition {
+            sub_id,
+            new_state: SubscriptionState::Cancelled,
+        }
+        .publish(env);
+
+        Self::notify_registry(env, sub_id, false);
+    }
+
+    /// Pause a subscription for `duration` seconds. Owner only.
+    /// No renewals run while paused; the subscription resumes automatically once
+    /// `duration` has passed, or earlier through `resume_sub`. The duration may
+    /// not exceed `get_max_pause_duration`.
+    pub fn pause_sub(env: Env, sub_id: u64, duration: u64) {
+        let key = PersistentKey::Subscription(sub_id);
+        let mut data: SubscriptionData = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("Subscription not found");
+
+        data.owner.require_auth();
+
+        if data.state != SubscriptionState::Active {
+            panic!("Only active subscriptions can be paused");
+        }
+        if duration == 0 || duration > Self::get_max_pause_duration(env.clone()) {
+            panic!("Invalid pause duration");
+        }
+
+        data.state = SubscriptionState::Paused;
+        Self::save_sub(&env, sub_id, &data);
+
         let lc_key = PersistentKey::Lifecycle(sub_id);
         let mut lifecycle: LifecycleTimestamps = env
             .storage()
@@ -513,32 +1491,411 @@ impl SubscriptionRenewalContract {
             .get(&lc_key)
             .expect("Lifecycle data not found");
         let now = env.ledger().timestamp();
-        lifecycle.canceled_at = now;
+        lifecycle.paused_at = now;
+        lifecycle.resume_at = now + duration;
         env.storage().persistent().set(&lc_key, &lifecycle);
 
         LifecycleTimestampUpdated {
             sub_id,
-            event_kind: 4,
+            event_kind: 5,
             timestamp: now,
         }
         .publish(&env);
 
-        // Record cancellation log
         Self::record_log(
             &env,
             sub_id,
-            5,
-            soroban_sdk::String::from_str(&env, "Subscription cancelled"),
+            EventTypeByte::SubscriptionPause,
+            soroban_sdk::String::from_str(&env, "Subscription paused"),
         );
 
-        // Emit state transition event
         StateTransition {
             sub_id,
-            new_state: SubscriptionState::Cancelled,
+            new_state: SubscriptionState::Paused,
         }
         .publish(&env);
     }
 
+    /// Resume a paused subscription before its pause runs out. Owner only.
+    pub fn resume_sub(env: Env, sub_id: u64) {
+        let mut data = Self::get_sub(env.clone(), sub_id);
+        data.owner.require_auth();
+
+        if data.state != SubscriptionState::Paused {
+            panic!("Subscription is not paused");
+        }
+        Self::resume(&env, sub_id, &mut data);
+    }
+
+    /// Whether the owner is currently entitled to the service: the subscription
+    /// is active, inside its grace period, or paused past its resumption time,
+    /// and any cancellation scheduled for the end of the period has not come due.
+    pub fn is_entitled(env: Env, sub_id: u64) -> bool {
+        let data = Self::get_sub(env.clone(), sub_id);
+        let lifecycle = Self::get_lifecycle(env.clone(), sub_id);
+        let now = env.ledger().timestamp();
+        if lifecycle.cancel_at > 0 && now >= lifecycle.cancel_at {
+            return false;
+        }
+        match data.state {
+            SubscriptionState::Active => true,
+            SubscriptionState::GracePeriod => now < lifecycle.grace_ends_at,
+            SubscriptionState::Paused => now >= lifecycle.resume_at,
+            _ => false,
+        }
+    }
+
+    /// Apply time-based transitions before a renewal reads the state: complete a
+    /// cancellation scheduled for the end of the period, resume a pause that has
+    /// run out, and move a subscription whose grace period has ended to `Retrying`.
+    fn refresh_state(env: &Env, sub_id: u64, data: &mut SubscriptionData) {
+        let lifecycle = Self::get_lifecycle(env.clone(), sub_id);
+        let now = env.ledger().timestamp();
+        if lifecycle.cancel_at > 0
+            && now >= lifecycle.cancel_at
+            && data.state != SubscriptionState::Cancelled
+        {
+            let at = lifecycle.cancel_at;
+            Self::finish_cancel(env, sub_id, data, CancelMode::AtPeriodEnd, at);
+            return;
+        }
+        match data.state {
+            SubscriptionState::Paused if now >= lifecycle.resume_at => {
+                Self::resume(env, sub_id, data);
+            }
+            SubscriptionState::GracePeriod if now >= lifecycle.grace_ends_at => {
+                data.state = SubscriptionState::Retrying;
+                Self::save_sub(env, sub_id, data);
+                StateTransition {
+                    sub_id,
+                    new_state: SubscriptionState::Retrying,
+                }
+                .publish(env);
+            }
+            _ => {}
+        }
+    }
+
+    /// Move a paused subscription back to `Active`.
+    fn resume(env: &Env, sub_id: u64, data: &mut SubscriptionData) {
+        data.state = SubscriptionState::Active;
+        Self::save_sub(env, sub_id, data);
+
+        let lc_key = PersistentKey::Lifecycle(sub_id);
+        let mut lifecycle: LifecycleTimestamps = env
+            .storage()
+            .persistent()
+            .get(&lc_key)
+            .expect("Lifecycle data not found");
+        let now = env.ledger().timestamp();
+        lifecycle.resumed_at = now;
+        lifecycle.resume_at = 0;
+        env.storage().persistent().set(&lc_key, &lifecycle);
+
+        LifecycleTimestampUpdated {
+            sub_id,
+            event_kind: 6,
+            timestamp: now,
+        }
+        .publish(env);
+
+        Self::record_log(
+            env,
+            sub_id,
+            EventTypeByte::SubscriptionUnpause,
+            soroban_sdk::String::from_str(env, "Subscription resumed"),
+        );
+
+        StateTransition {
+            sub_id,
+            new_state: SubscriptionState::Active,
+        }
+        .publish(env);
+    }
+
+    /// Change the plan (`amount`, `frequency`) of a subscription. Owner only.
+    ///
+    /// An upgrade at the same frequency takes effect immediately: the owner is
+    /// charged the price difference prorated over the rest of the current period.
+    /// That charge passes the same guards as a renewal: the subscription must be
+    /// `Active`, the protocol unpaused, the integrity hash intact and the owner's
+    /// caps respected. A team upgrade whose charge needs multi-sig approval is
+    /// scheduled instead, so the next renewal's request covers the new amount.
+    /// Any other change (downgrade or new frequency) is scheduled as a pending
+    /// plan and applied by the next renewal once the period has ended.
+    /// No change is accepted once a cancellation is scheduled.
+    /// The integrity hash is recomputed whenever the terms change.
+    pub fn change_plan(env: Env, sub_id: u64, new_amount: i128, new_frequency: u64) {
+        let key = PersistentKey::Subscription(sub_id);
+        let mut data: SubscriptionData = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("Subscription not found");
+
+        data.owner.require_auth();
+
+        if data.state == SubscriptionState::Cancelled || data.state == SubscriptionState::Failed {
+            panic!("Subscription is not active");
+        }
+        if new_amount <= 0 || new_frequency == 0 {
+            panic!("Invalid plan");
+        }
+        if data.spending_cap > 0 && new_amount > data.spending_cap {
+            panic!("Plan exceeds spending cap");
+        }
+        if Self::get_lifecycle(env.clone(), sub_id).cancel_at > 0 {
+            panic!("Subscription cancels at period end");
+        }
+
+        let pending_key = PersistentKey::PendingPlan(sub_id);
+        let period_end = Self::next_due_at(env.clone(), sub_id);
+        let now = env.ledger().timestamp();
+
+        // Upgrade now: the difference for the rest of the current period
+        // (nothing during a trial, which has not been paid for)
+        let mut upgrade_now = new_frequency == data.frequency && new_amount > data.amount;
+        let mut prorated = 0;
+        if upgrade_now {
+            let remaining = if now < Self::trial_ends_at(&env, sub_id, &data) {
+                0
+            } else {
+                period_end.saturating_sub(now).min(data.frequency)
+            };
+            prorated = (new_amount - data.amount) * remaining as i128 / data.frequency as i128;
+            // A charge the team must approve waits for the next renewal's request
+            if let Some(team_id) = data.team_id {
+                upgrade_now = !Self::requires_multisig(env.clone(), team_id, prorated);
+            }
+        }
+
+        if !upgrade_now {
+            let plan = PendingPlan {
+                amount: new_amount,
+                frequency: new_frequency,
+                effective_at: period_end,
+            };
+            env.storage().persistent().set(&pending_key, &plan);
+
+            PlanChangeScheduled {
+                sub_id,
+                amount: new_amount,
+                frequency: new_frequency,
+                effective_at: period_end,
+            }
+            .publish(&env);
+
+            Self::record_log(
+                &env,
+                sub_id,
+                EventTypeByte::SubscriptionUpdate,
+                soroban_sdk::String::from_str(&env, "Plan change scheduled"),
+            );
+            return;
+        }
+
+        if Self::is_paused(env.clone()) {
+            panic!("Protocol is paused");
+        }
+        if data.state != SubscriptionState::Active {
+            panic!("Only active subscriptions can be upgraded");
+        }
+        if let Err(reason) = Self::check_integrity(&env, sub_id, &data) {
+            panic!("{}", reason);
+        }
+        if prorated > 0 {
+            if let Err(reason) = Self::check_owner_caps(&env, &data, prorated) {
+                panic!("{}", reason);
+            }
+            let settled = Self::quote_charge(&env, sub_id, prorated)
+                .and_then(|charge| Self::settle_charge(&env, &data, &data.merchant, charge));
+            if settled.is_err() {
+                panic!("Prorated charge failed");
+            }
+
+            Self::record_spend(&env, &data.owner, &data.merchant, prorated);
+        }
+
+        data.amount = new_amount;
+        data.integrity_hash = Self::compute_integrity_hash(&env, &data);
+        Self::save_sub(&env, sub_id, &data);
+        // The upgrade supersedes any change scheduled earlier
+        env.storage().persistent().remove(&pending_key);
+
+        PlanChanged {
+            sub_id,
+            amount: new_amount,
+            frequency: new_frequency,
+            prorated_charge: prorated,
+        }
+        .publish(&env);
+
+        Self::record_log(
+            &env,
+            sub_id,
+            EventTypeByte::SubscriptionUpdate,
+            soroban_sdk::String::from_str(&env, "Plan upgraded"),
+        );
+    }
+
+    /// Plan change waiting for the end of the current period, if any.
+    pub fn get_pending_plan(env: Env, sub_id: u64) -> Option<PendingPlan> {
+        env.storage()
+            .persistent()
+            .get(&PersistentKey::PendingPlan(sub_id))
+    }
+
+    // ── Metered usage ─────────────────────────────────────────────
+
+    /// Set the graduated price table for usage billed on top of `amount`. The owner
+    /// and the merchant must both authorize it, since either could otherwise reprice
+    /// usage the other has not agreed to. Tiers must have strictly increasing `up_to`
+    /// and non-negative prices; an empty table turns metering off. The table is part
+    /// of the integrity hash.
+    pub fn set_usage_tiers(env: Env, sub_id: u64, tiers: Vec<UsageTier>) {
+        let mut data = Self::get_sub(env.clone(), sub_id);
+        data.owner.require_auth();
+        data.merchant.require_auth();
+
+        let mut previous_up_to = 0u64;
+        for (i, tier) in tiers.iter().enumerate() {
+            if tier.unit_price < 0 || (i > 0 && tier.up_to <= previous_up_to) {
+                panic!("Invalid usage tiers");
+            }
+            previous_up_to = tier.up_to;
+        }
+
+        data.usage_tiers = tiers;
+        data.integrity_hash = Self::compute_integrity_hash(&env, &data);
+        Self::save_sub(&env, sub_id, &data);
+
+        Self::record_log(
+            &env,
+            sub_id,
+            EventTypeByte::SubscriptionUpdate,
+            soroban_sdk::String::from_str(&env, "Usage tiers updated"),
+        );
+    }
+
+    /// Report `units` of usage for billing cycle `cycle_id`. Merchant only.
+    /// The units are priced with the current tier table and added to the charge
+    /// of that cycle. One report per cycle, before the cycle is charged. Each
+    /// report is logged as a `UsageReport` commitment over
+    /// `XDR((sub_id, cycle_id, units, amount))` so it can be disputed later.
+    pub fn report_usage(env: Env, sub_id: u64, cycle_id: u64, units: u64) -> i128 {
+        let data = Self::get_sub(env.clone(), sub_id);
+        data.merchant.require_auth();
+
+        if data.usage_tiers.is_empty() {
+            panic!("Subscription is not metered");
+        }
+        let last_cycle: Option<u64> = env
+            .storage()
+            .persistent()
+            .get(&PersistentKey::Cycle(sub_id));
+        if last_cycle.is_some_and(|last| cycle_id <= last) {
+            panic!("Cycle already charged");
+        }
+        let key = PersistentKey::UsageReport(sub_id, cycle_id);
+        if env.storage().persistent().has(&key) {
+            panic!("Usage already reported for this cycle");
+        }
+
+        let amount = Self::price_usage(&data.usage_tiers, units);
+        let report = UsageReport {
+            units,
+            amount,
+            reported_at: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&key, &report);
+
+        UsageReported {
+            sub_id,
+            cycle_id,
+            units,
+            amount,
+        }
+        .publish(&env);
+
+        Self::record_commitment(
+            &env,
+            sub_id,
+            EventTypeByte::UsageReport,
+            (sub_id, cycle_id, units, amount).to_xdr(&env),
+        );
+        amount
+    }
+
+    /// Usage reported for a billing cycle, if any.
+    pub fn get_usage_report(env: Env, sub_id: u64, cycle_id: u64) -> Option<UsageReport> {
+        env.storage()
+            .persistent()
+            .get(&PersistentKey::UsageReport(sub_id, cycle_id))
+    }
+
+    /// Metered charge reported for a cycle (0 if none was reported).
+    fn metered_charge(env: &Env, sub_id: u64, cycle_id: u64) -> i128 {
+        env.storage()
+            .persistent()
+            .get::<_, UsageReport>(&PersistentKey::UsageReport(sub_id, cycle_id))
+            .map_or(0, |report| report.amount)
+    }
+
+    /// Price `units` against a graduated tier table.
+    fn price_usage(tiers: &Vec<UsageTier>, units: u64) -> i128 {
+        let mut total = 0i128;
+        let mut floor = 0u64;
+        let last = tiers.len() - 1;
+        for (i, tier) in tiers.iter().enumerate() {
+            let ceiling = if i as u32 == last {
+                u64::MAX
+            } else {
+                tier.up_to
+            };
+            let in_tier = units.min(ceiling).saturating_sub(floor);
+            total += in_tier as i128 * tier.unit_price;
+            if units <= ceiling {
+                break;
+            }
+            floor = tier.up_to;
+        }
+        total
+    }
+
+    /// Switch `data` to its pending plan once the period it was scheduled for has
+    /// ended. Called by renewals before the charge is validated.
+    fn apply_pending_plan(env: &Env, sub_id: u64, data: &mut SubscriptionData) {
+        let pending_key = PersistentKey::PendingPlan(sub_id);
+        let plan: PendingPlan = match env.storage().persistent().get(&pending_key) {
+            Some(plan) => plan,
+            None => return,
+        };
+        if env.ledger().timestamp() < plan.effective_at {
+            return;
+        }
+
+        data.amount = plan.amount;
+        data.frequency = plan.frequency;
+        data.integrity_hash = Self::compute_integrity_hash(env, data);
+        Self::save_sub(env, sub_id, data);
+        env.storage().persistent().remove(&pending_key);
+
+        PlanChanged {
+            sub_id,
+            amount: plan.amount,
+            frequency: plan.frequency,
+            prorated_charge: 0,
+        }
+        .publish(env);
+
+        Self::record_log(
+            env,
+            sub_id,
+            EventTypeByte::SubscriptionUpdate,
+            soroban_sdk::String::from_str(env, "Scheduled plan change applied"),
+        );
+    }
+
     // ── Approval management ───────────────────────────────────────
 
     /// Create a renewal approval for a subscription
@@ -633,19 +1990,19 @@ impl SubscriptionRenewalContract {
 
     // ── Renewal logic ─────────────────────────────────────────────
 
-    /// Attempt to renew the subscription.
-    /// Returns true if renewal is successful (simulated), false if it failed and retry logic was triggered.
-    /// limits: max retries allowed.
-    /// cooldown: min ledgers between retries.
+    /// Attempt to renew the subscription by pulling `amount` of the subscription
+    /// token from the owner to the merchant via `transfer_from`.
+    /// Returns true if the transfer settled, false if it failed and retry logic was triggered,
+    /// or if a cancellation scheduled for the period end completed instead of a charge.
+    /// caller: the agent holding the renewal lock.
+    /// Retries of a failed charge follow the merchant's `DunningPolicy`.
     pub fn renew(
         env: Env,
+        caller: Address,
         sub_id: u64,
         approval_id: u64,
         amount: i128,
-        max_retries: u32,
-        cooldown_ledgers: u32,
         cycle_id: u64,
-        succeed: bool,
     ) -> bool {
         // 1. Check global pause
         if Self::is_paused(env.clone()) {
@@ -663,18 +2020,37 @@ impl SubscriptionRenewalContract {
             .get(&key)
             .expect("Subscription not found");
 
-        // 3. Check failed state
+        // 3. Check failed state (after resuming an expired pause or ending a grace period)
+        let was_cancelled = data.state == SubscriptionState::Cancelled;
+        Self::refresh_state(&env, sub_id, &mut data);
+        if !was_cancelled && data.state == SubscriptionState::Cancelled {
+            // The period-end cancellation fell due: keep it rather than revert it
+            return false;
+        }
         if data.state == SubscriptionState::Failed {
             panic!("Subscription is in FAILED state");
         }
+        if data.state == SubscriptionState::Paused {
+            panic!("Subscription is paused");
+        }
+        if data.state == SubscriptionState::Cancelled {
+            panic!("Subscription is cancelled");
+        }
+        if Self::get_lifecycle(env.clone(), sub_id).cancel_at > 0 {
+            panic!("Subscription cancels at period end");
+        }
 
-        // 4. Verify renewal lock exists and is not expired
+        // 4. Verify renewal lock exists, is held by the caller and is not expired
         let lock_key = PersistentKey::RenewalLock(sub_id);
         let lock_data: Option<RenewalLockData> = env.storage().persistent().get(&lock_key);
         match lock_data {
             None => panic!("Renewal lock required"),
             Some(ref ld) => {
-                if current_ledger >= ld.locked_at + ld.lock_timeout {
+                if ld.holder != caller {
+                    panic!("Caller is not the lock holder");
+                }
+                caller.require_auth();
+                if current_ledger >= ld.locked_at.saturating_add(ld.lock_timeout) {
                     panic!("Renewal lock expired");
                 }
             }
@@ -690,13 +2066,14 @@ impl SubscriptionRenewalContract {
             }
         }
 
-        // 6. Check cooldown
-        if data.failure_count > 0 && current_ledger < data.last_attempt_ledger + cooldown_ledgers {
+        // 6. Check the dunning backoff
+        if current_ledger < Self::retry_ledger(&env, &data) {
             panic!("Cooldown period active");
         }
 
-        // 7. Validate and consume approval (also checks renewal window if set)
-        if !Self::consume_approval(&env, sub_id, approval_id, amount) {
+        // 7. Validate and consume approval for the base amount plus reported usage
+        let metered = Self::metered_charge(&env, sub_id, cycle_id);
+        if !Self::consume_approval(&env, sub_id, approval_id, amount + metered) {
             panic!("Invalid or expired approval");
         }
 
@@ -713,19 +2090,300 @@ impl SubscriptionRenewalContract {
             }
         }
 
-        // 8. Validate Integrity Hash
-        let mut integrity_data = soroban_sdk::Vec::<soroban_sdk::Val>::new(&env);
-        integrity_data.push_back(data.merchant.into_val(&env));
-        integrity_data.push_back(data.amount.into_val(&env));
-        integrity_data.push_back(data.frequency.into_val(&env));
-        integrity_data.push_back(data.spending_cap.into_val(&env));
+        // A plan change scheduled for the end of the last period starts now
+        Self::apply_pending_plan(&env, sub_id, &mut data);
+
+        // 7c-10. Multi-sig, integrity and spending caps
+        let multisig = match Self::validate_charge(&env, sub_id, &data, amount, metered, cycle_id) {
+            Ok(multisig) => multisig,
+            Err(reason) => panic!("{}", reason),
+        };
+
+        // 11. Price, settle and record the outcome
+        Self::complete_charge(
+            &env,
+            sub_id,
+            data,
+            amount + metered,
+            cycle_id,
+            multisig,
+            None,
+        )
+    }
+
+    /// Renew every subscription in `batch` that is due. Permissionless: any keeper
+    /// may call it and receives the keeper bounty out of each charge it settles.
+    ///
+    /// A subscription is due once `next_due_at` has passed. It is charged its stored
+    /// `amount` for cycle `last cycle + 1`; failures follow the merchant's
+    /// `DunningPolicy`. Subscriptions that are not due, cancelled or failed,
+    /// locked by another worker, backing off, outside their renewal window or
+    /// failing the charge checks in `renew` are skipped. So are cycles with a
+    /// usage report: the metered amount is only bounded by an owner approval,
+    /// so those cycles must be charged through `renew`.
+    /// Returns the number of subscriptions charged successfully.
+    pub fn process_due(env: Env, keeper: Address, batch: soroban_sdk::Vec<u64>) -> u32 {
+        if Self::is_paused(env.clone()) {
+            panic!("Protocol is paused");
+        }
+        keeper.require_auth();
+
+        let current_ledger = env.ledger().sequence();
+        let now = env.ledger().timestamp();
+        let mut renewed = 0u32;
+        for sub_id in batch.iter() {
+            let mut data: SubscriptionData = match env
+                .storage()
+                .persistent()
+                .get(&PersistentKey::Subscription(sub_id))
+            {
+                Some(data) => data,
+                None => continue,
+            };
+            Self::refresh_state(&env, sub_id, &mut data);
+            if !matches!(
+                data.state,
+                SubscriptionState::Active
+                    | SubscriptionState::Retrying
+                    | SubscriptionState::GracePeriod
+            ) {
+                continue;
+            }
+            if now < Self::next_due_at(env.clone(), sub_id) {
+                continue;
+            }
+            // Converting out of a trial needs the owner's approval through `renew`
+            if data.paid_cycles == 0 && Self::trial_ends_at(&env, sub_id, &data) > 0 {
+                continue;
+            }
+            if let Some(lock) = Self::get_renewal_lock(env.clone(), sub_id) {
+                if current_ledger < lock.locked_at.saturating_add(lock.lock_timeout) {
+                    continue;
+                }
+            }
+            if current_ledger < Self::retry_ledger(&env, &data) {
+                continue;
+            }
+            if let Some(window) = Self::get_window(env.clone(), sub_id) {
+                if now < window.billing_start || now > window.billing_end {
+                    continue;
+                }
+            }
 
-        let current_hash = env.crypto().sha256(&integrity_data.to_xdr(&env));
-        let current_hash_bytes: soroban_sdk::BytesN<32> = current_hash.into();
+            let cycle_id = env
+                .storage()
+                .persistent()
+                .get::<_, u64>(&PersistentKey::Cycle(sub_id))
+                .map_or(1, |last| last + 1);
+            // Metered cycles need an owner approval bounding the total through `renew`
+            if Self::get_usage_report(env.clone(), sub_id, cycle_id).is_some() {
+                continue;
+            }
+            Self::apply_pending_plan(&env, sub_id, &mut data);
+            let amount = Self::cycle_price(&data);
+            let multisig = match Self::validate_charge(&env, sub_id, &data, amount, 0, cycle_id) {
+                Ok(multisig) => multisig,
+                Err(_) => continue,
+            };
+
+            if Self::complete_charge(
+                &env,
+                sub_id,
+                data,
+                amount,
+                cycle_id,
+                multisig,
+                Some(keeper.clone()),
+            ) {
+                renewed += 1;
+            }
+        }
+        renewed
+    }
 
-        if current_hash_bytes.as_ref() != data.integrity_hash.as_ref() {
-            IntegrityViolation { sub_id }.publish(&env);
-            panic!("Subscription integrity violation: parameters tampered");
+    /// Timestamp after which `process_due` may renew a subscription: one
+    /// `frequency` after the last successful renewal. Periods are charged in
+    /// advance, so a subscription that was never renewed is due from creation,
+    /// or from the end of its trial.
+    pub fn next_due_at(env: Env, sub_id: u64) -> u64 {
+        let data = Self::get_sub(env.clone(), sub_id);
+        let lifecycle = Self::get_lifecycle(env.clone(), sub_id);
+        if data.paid_cycles > 0 {
+            return lifecycle.last_renewed_at.saturating_add(data.frequency);
+        }
+        match Self::trial_ends_at(&env, sub_id, &data) {
+            0 => lifecycle.created_at,
+            trial_end => trial_end,
+        }
+    }
+
+    /// Set the keeper bounty paid out of `process_due` charges, in basis points. Admin only.
+    pub fn set_keeper_bounty(env: Env, bounty_bps: u32) {
+        Self::require_admin(&env);
+        if bounty_bps > MAX_KEEPER_BOUNTY_BPS {
+            panic!("Keeper bounty too high");
+        }
+        env.storage()
+            .instance()
+            .set(&ContractKey::KeeperBountyBps, &bounty_bps);
+    }
+
+    /// Keeper bounty in basis points of the charge.
+    pub fn get_keeper_bounty(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&ContractKey::KeeperBountyBps)
+            .unwrap_or(DEFAULT_KEEPER_BOUNTY_BPS)
+    }
+
+    /// Set the longest pause an owner may request, in seconds. Admin only.
+    pub fn set_max_pause_duration(env: Env, max_secs: u64) {
+        Self::require_admin(&env);
+        env.storage()
+            .instance()
+            .set(&ContractKey::MaxPauseDuration, &max_secs);
+    }
+
+    /// Longest pause an owner may request, in seconds (defaults to 90 days).
+    pub fn get_max_pause_duration(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&ContractKey::MaxPauseDuration)
+            .unwrap_or(DEFAULT_MAX_PAUSE_SECS)
+    }
+
+    /// Set the dunning policy for subscriptions billed by `merchant`. Merchant only.
+    pub fn set_dunning_policy(env: Env, merchant: Address, policy: DunningPolicy) {
+        merchant.require_auth();
+        Self::validate_dunning_policy(&policy);
+        env.storage()
+            .persistent()
+            .set(&PersistentKey::DunningPolicy(merchant), &policy);
+    }
+
+    /// Set the dunning policy for merchants without their own. Admin only.
+    pub fn set_default_dunning_policy(env: Env, policy: DunningPolicy) {
+        Self::require_admin(&env);
+        Self::validate_dunning_policy(&policy);
+        env.storage()
+            .instance()
+            .set(&ContractKey::DefaultDunningPolicy, &policy);
+    }
+
+    /// Dunning policy applied to `merchant`'s subscriptions: its own, the
+    /// contract default, or 4 attempts with exponential backoff from 720
+    /// ledgers, no grace period, then suspension.
+    pub fn get_dunning_policy(env: Env, merchant: Address) -> DunningPolicy {
+        if let Some(policy) = env
+            .storage()
+            .persistent()
+            .get(&PersistentKey::DunningPolicy(merchant))
+        {
+            return policy;
+        }
+        env.storage()
+            .instance()
+            .get(&ContractKey::DefaultDunningPolicy)
+            .unwrap_or(DunningPolicy {
+                max_attempts: DEFAULT_DUNNING_MAX_ATTEMPTS,
+                backoff: RetryBackoff::Exponential(DEFAULT_DUNNING_BASE_DELAY_LEDGERS),
+                grace_secs: 0,
+                final_action: FinalAction::Suspend,
+            })
+    }
+
+    /// Ledger from which a failing subscription may be retried, or None if it
+    /// has no pending retry.
+    pub fn next_retry_at(env: Env, sub_id: u64) -> Option<u32> {
+        let data = Self::get_sub(env.clone(), sub_id);
+        let retrying = matches!(
+            data.state,
+            SubscriptionState::Retrying | SubscriptionState::GracePeriod
+        );
+        if !retrying || data.failure_count == 0 {
+            return None;
+        }
+        Some(Self::retry_ledger(&env, &data))
+    }
+
+    fn validate_dunning_policy(policy: &DunningPolicy) {
+        if policy.max_attempts == 0 {
+            panic!("Dunning policy needs at least one attempt");
+        }
+        if let RetryBackoff::Fixed(delays) = &policy.backoff {
+            if delays.is_empty() {
+                panic!("Dunning policy needs at least one delay");
+            }
+        }
+    }
+
+    /// First ledger at which a charge may be retried after `data.failure_count`
+    /// failures in a row (0 when nothing has failed).
+    fn retry_ledger(env: &Env, data: &SubscriptionData) -> u32 {
+        if data.failure_count == 0 {
+            return 0;
+        }
+        let policy = Self::get_dunning_policy(env.clone(), data.merchant.clone());
+        let delay = match policy.backoff {
+            RetryBackoff::Exponential(base) => {
+                let doublings = (data.failure_count - 1).min(31);
+                base.saturating_mul(1u32 << doublings)
+            }
+            RetryBackoff::Fixed(delays) => {
+                let index = (data.failure_count - 1).min(delays.len() - 1);
+                delays.get(index).unwrap_or(0)
+            }
+        };
+        data.last_attempt_ledger.saturating_add(delay)
+    }
+
+    /// End of the subscription's trial, or 0 if it has none.
+    fn trial_ends_at(env: &Env, sub_id: u64, data: &SubscriptionData) -> u64 {
+        if data.intro.trial_secs == 0 {
+            return 0;
+        }
+        Self::get_lifecycle(env.clone(), sub_id)
+            .created_at
+            .saturating_add(data.intro.trial_secs)
+    }
+
+    /// Price of the next renewal: the introductory price for the first
+    /// `intro_cycles` renewals, `amount` afterwards.
+    fn cycle_price(data: &SubscriptionData) -> i128 {
+        if data.paid_cycles < data.intro.intro_cycles {
+            data.intro.intro_amount
+        } else {
+            data.amount
+        }
+    }
+
+    /// Checks shared by `renew` and `process_due` once a charge is authorized:
+    /// team multi-sig (7c), integrity hash (8), per-subscription cap (9) and
+    /// global user cap (10). The caps apply to the base amount plus the metered
+    /// usage of the cycle. Returns the multi-sig request to consume, if any,
+    /// or the reason the charge must not go ahead.
+    fn validate_charge(
+        env: &Env,
+        sub_id: u64,
+        data: &SubscriptionData,
+        base: i128,
+        metered: i128,
+        cycle_id: u64,
+    ) -> Result<Option<MultiSigRequest>, &'static str> {
+        let amount = base + metered;
+
+        // 7c. Team subscriptions above the team threshold need an approved multi-sig request
+        let multisig = Self::check_multisig(env, data, sub_id, cycle_id, amount)?;
+
+        // 8. Validate Integrity Hash
+        Self::check_integrity(env, sub_id, data)?;
+
+        // 8b. Nothing is charged during a trial, and intro cycles at most the intro price
+        if env.ledger().timestamp() < Self::trial_ends_at(env, sub_id, data) {
+            return Err("Subscription is in its trial period");
+        }
+        if data.paid_cycles < data.intro.intro_cycles && base > data.intro.intro_amount {
+            return Err("Amount exceeds introductory price");
         }
 
         // 9. Enforce per-subscription spending cap
@@ -735,168 +2393,462 @@ impl SubscriptionRenewalContract {
                 amount,
                 cap: data.spending_cap,
             }
-            .publish(&env);
-            panic!("Per-subscription spending cap exceeded");
+            .publish(env);
+            return Err("Per-subscription spending cap exceeded");
         }
 
-        // 10. Enforce global user spending cap
-        let global_cap: i128 = env
-            .storage()
-            .persistent()
-            .get(&PersistentKey::UserCap(data.owner.clone()))
-            .unwrap_or(0);
-        if global_cap > 0 {
-            let current_spent: i128 = env
-                .storage()
-                .persistent()
-                .get(&PersistentKey::UserSpent(data.owner.clone()))
-                .unwrap_or(0);
-            if current_spent + amount > global_cap {
-                GlobalCapViolated {
+        // 10. Enforce the owner's spending cap and merchant sub-cap for the current window
+        Self::check_owner_caps(env, data, amount)?;
+
+        Ok(multisig)
+    }
+
+    /// Whether the stored terms still match the integrity hash. Emits
+    /// `IntegrityViolation` if not.
+    fn check_integrity(
+        env: &Env,
+        sub_id: u64,
+        data: &SubscriptionData,
+    ) -> Result<(), &'static str> {
+        if Self::compute_integrity_hash(env, data) != data.integrity_hash {
+            IntegrityViolation { sub_id }.publish(env);
+            return Err("Subscription integrity violation: parameters tampered");
+        }
+        Ok(())
+    }
+
+    /// Whether charging `amount` keeps the owner within their spending cap and their
+    /// merchant sub-cap for the current window. Emits the violation event if not.
+    fn check_owner_caps(
+        env: &Env,
+        data: &SubscriptionData,
+        amount: i128,
+    ) -> Result<(), &'static str> {
+        let window = Self::current_window(env, &data.owner);
+        let global_cap = Self::get_user_cap(env.clone(), data.owner.clone());
+        if global_cap > 0 && window.spent + amount > global_cap {
+            GlobalCapViolated {
+                owner: data.owner.clone(),
+                amount: window.spent + amount,
+                cap: global_cap,
+            }
+            .publish(env);
+            return Err("Global user spending cap exceeded");
+        }
+        let merchant_cap =
+            Self::get_merchant_cap(env.clone(), data.owner.clone(), data.merchant.clone());
+        if merchant_cap > 0 {
+            let merchant_spent = Self::merchant_spent(env, &data.owner, &data.merchant, &window);
+            if merchant_spent + amount > merchant_cap {
+                MerchantCapViolated {
                     owner: data.owner.clone(),
-                    amount: current_spent + amount,
-                    cap: global_cap,
+                    merchant: data.merchant.clone(),
+                    amount: merchant_spent + amount,
+                    cap: merchant_cap,
                 }
-                .publish(&env);
-                panic!("Global user spending cap exceeded");
+                .publish(env);
+                return Err("Merchant spending cap exceeded");
             }
         }
 
-        if succeed {
-            // Capture previous state before changing it
-            let previous_state = data.state;
-
-            // Simulated success - renewal successful
-            data.state = SubscriptionState::Active;
-            data.failure_count = 0;
-            data.last_attempt_ledger = current_ledger;
-            env.storage().persistent().set(&key, &data);
-
-            // Update global user spent amount
-            let current_spent: i128 = env
-                .storage()
-                .persistent()
-                .get(&PersistentKey::UserSpent(data.owner.clone()))
-                .unwrap_or(0);
-            env.storage()
-                .persistent()
-                .set(&PersistentKey::UserSpent(data.owner.clone()), &(current_spent + amount));
-
-            // Store cycle_id on success only
-            env.storage().persistent().set(&cycle_key, &cycle_id);
+        Ok(())
+    }
 
-            // Emit renewal success event
-            RenewalSuccess {
-                sub_id,
-                owner: data.owner.clone(),
-            }
-            .publish(&env);
+    /// Price the charge (FX conversion for fiat-quoted subs), settle owner -> merchant
+    /// and apply the success or retry/failure bookkeeping. When `keeper` is set, the
+    /// keeper bounty is split off the charge and paid to the keeper.
+    /// Returns true if the charge settled.
+    fn complete_charge(
+        env: &Env,
+        sub_id: u64,
+        mut data: SubscriptionData,
+        amount: i128,
+        cycle_id: u64,
+        multisig: Option<MultiSigRequest>,
+        keeper: Option<Address>,
+    ) -> bool {
+        let current_ledger = env.ledger().sequence();
 
-            // Update lifecycle timestamps
-            let lc_key = PersistentKey::Lifecycle(sub_id);
-            let mut lifecycle: LifecycleTimestamps = env
-                .storage()
-                .persistent()
-                .get(&lc_key)
-                .expect("Lifecycle data not found");
-            let now = env.ledger().timestamp();
-            lifecycle.last_renewed_at = now;
+        // 11. Price the charge and settle owner -> merchant (and keeper)
+        let settlement = Self::quote_charge(env, sub_id, amount).and_then(|charge| match &keeper {
+            Some(keeper) => Self::settle_with_bounty(env, sub_id, &data, keeper, charge),
+            None => Self::settle_charge(env, &data, &data.merchant, charge),
+        });
+        match settlement {
+            Ok(settled) => {
+                // Capture previous state before changing it
+                let previous_state = data.state;
+
+                // Transfer settled - renewal successful
+                data.state = SubscriptionState::Active;
+                data.failure_count = 0;
+                data.last_attempt_ledger = current_ledger;
+                data.paid_cycles += 1;
+                Self::save_sub(env, sub_id, &data);
+
+                // Count the charge against the owner's spending windows
+                Self::record_spend(env, &data.owner, &data.merchant, amount);
+
+                // Token amount paid for this period in the token's own units,
+                // refunded pro rata on cancellation
+                env.storage()
+                    .persistent()
+                    .set(&PersistentKey::LastCharge(sub_id), &settled);
+
+                // Store cycle_id on success only
+                env.storage()
+                    .persistent()
+                    .set(&PersistentKey::Cycle(sub_id), &cycle_id);
+
+                // Consume the multi-sig approval together with the charge
+                if let Some(mut request) = multisig {
+                    request.status = MultiSigStatus::Consumed;
+                    env.storage()
+                        .persistent()
+                        .set(&PersistentKey::MultiSig(sub_id, cycle_id), &request);
+                    MultiSigAuditLog {
+                        sub_id,
+                        request_id: cycle_id,
+                        decision: 6,
+                        actor: data.owner.clone(),
+                        timestamp: env.ledger().timestamp(),
+                    }
+                    .publish(env);
+                }
 
-            LifecycleTimestampUpdated {
-                sub_id,
-                event_kind: 3,
-                timestamp: now,
-            }
-            .publish(&env);
+                // Emit renewal success event
+                RenewalSuccess {
+                    sub_id,
+                    owner: data.owner.clone(),
+                }
+                .publish(env);
+
+                // Update lifecycle timestamps
+                let lc_key = PersistentKey::Lifecycle(sub_id);
+                let mut lifecycle: LifecycleTimestamps = env
+                    .storage()
+                    .persistent()
+                    .get(&lc_key)
+                    .expect("Lifecycle data not found");
+                let now = env.ledger().timestamp();
+                lifecycle.last_renewed_at = now;
 
-            // If recovering from Retrying, also update activated_at
-            if previous_state == SubscriptionState::Retrying {
-                lifecycle.activated_at = now;
                 LifecycleTimestampUpdated {
                     sub_id,
-                    event_kind: 2,
+                    event_kind: 3,
                     timestamp: now,
                 }
-                .publish(&env);
-            }
-            env.storage().persistent().set(&lc_key, &lifecycle);
+                .publish(env);
+
+                // If recovering from Retrying or a grace period, also update activated_at
+                lifecycle.grace_ends_at = 0;
+                if previous_state == SubscriptionState::Retrying
+                    || previous_state == SubscriptionState::GracePeriod
+                {
+                    lifecycle.activated_at = now;
+                    LifecycleTimestampUpdated {
+                        sub_id,
+                        event_kind: 2,
+                        timestamp: now,
+                    }
+                    .publish(env);
+                }
+                env.storage().persistent().set(&lc_key, &lifecycle);
 
-            // Auto-release lock
-            env.storage().persistent().remove(&lock_key);
-            RenewalLockReleased {
-                sub_id,
-                released_at: current_ledger,
-            }
-            .publish(&env);
+                // Auto-release lock
+                Self::auto_release_lock(env, sub_id, current_ledger);
 
-            // Record renewal success log
-            Self::record_log(
-                &env,
-                sub_id,
-                2,
-                soroban_sdk::String::from_str(&env, "Renewal successful"),
-            );
+                // Record renewal success log
+                Self::record_log(
+                    env,
+                    sub_id,
+                    EventTypeByte::Renewal,
+                    soroban_sdk::String::from_str(env, "Renewal successful"),
+                );
 
-            true
-        } else {
-            // Simulated failure - renewal failed, apply retry logic
-            // Do NOT store cycle_id on failure — retries with same cycle_id remain allowed
-            data.failure_count += 1;
-            data.last_attempt_ledger = current_ledger;
+                Self::notify_registry(env, sub_id, true);
 
-            // Emit renewal failure event
-            RenewalFailed {
-                sub_id,
-                failure_count: data.failure_count,
-                ledger: current_ledger,
+                true
             }
-            .publish(&env);
-
-            // Determine new state based on retry count
-            if data.failure_count > max_retries {
-                data.state = SubscriptionState::Failed;
-                StateTransition {
+            Err(reason) => {
+                // Transfer failed (allowance, balance, frozen account...) - apply retry logic
+                // Do NOT store cycle_id on failure — retries with same cycle_id remain allowed
+                data.failure_count += 1;
+                data.last_attempt_ledger = current_ledger;
+
+                // Emit renewal failure event
+                RenewalFailed {
                     sub_id,
-                    new_state: SubscriptionState::Failed,
+                    failure_count: data.failure_count,
+                    ledger: current_ledger,
+                    reason,
                 }
-                .publish(&env);
-
-                // Record failure log
-                Self::record_log(
-                    &env,
-                    sub_id,
-                    3,
-                    soroban_sdk::String::from_str(&env, "Renewal failed - max retries exceeded"),
-                );
-            } else {
-                data.state = SubscriptionState::Retrying;
-                StateTransition {
-                    sub_id,
-                    new_state: SubscriptionState::Retrying,
+                .publish(env);
+
+                // Determine new state from the merchant's dunning policy
+                let policy = Self::get_dunning_policy(env.clone(), data.merchant.clone());
+                if data.failure_count >= policy.max_attempts {
+                    let final_state = match policy.final_action {
+                        FinalAction::Suspend => SubscriptionState::Failed,
+                        FinalAction::Cancel => SubscriptionState::Cancelled,
+                    };
+                    data.state = final_state;
+                    if final_state == SubscriptionState::Cancelled {
+                        let lc_key = PersistentKey::Lifecycle(sub_id);
+                        let mut lifecycle: LifecycleTimestamps = env
+                            .storage()
+                            .persistent()
+                            .get(&lc_key)
+                            .expect("Lifecycle data not found");
+                        let now = env.ledger().timestamp();
+                        lifecycle.canceled_at = now;
+                        env.storage().persistent().set(&lc_key, &lifecycle);
+                        LifecycleTimestampUpdated {
+                            sub_id,
+                            event_kind: 4,
+                            timestamp: now,
+                        }
+                        .publish(env);
+                    }
+                    StateTransition {
+                        sub_id,
+                        new_state: final_state,
+                    }
+                    .publish(env);
+
+                    // Record failure log
+                    Self::record_log(
+                        env,
+                        sub_id,
+                        EventTypeByte::Failure,
+                        soroban_sdk::String::from_str(
+                            env,
+                            "Renewal failed - max retries exceeded",
+                        ),
+                    );
+
+                    Self::notify_registry(env, sub_id, false);
+                } else {
+                    let grace_secs = policy.grace_secs;
+                    if data.state == SubscriptionState::Active && grace_secs > 0 {
+                        // First failure: the service stays entitled during the grace period
+                        data.state = SubscriptionState::GracePeriod;
+                        let lc_key = PersistentKey::Lifecycle(sub_id);
+                        let mut lifecycle: LifecycleTimestamps = env
+                            .storage()
+                            .persistent()
+                            .get(&lc_key)
+                            .expect("Lifecycle data not found");
+                        let now = env.ledger().timestamp();
+                        lifecycle.grace_ends_at = now + grace_secs;
+                        env.storage().persistent().set(&lc_key, &lifecycle);
+
+                        LifecycleTimestampUpdated {
+                            sub_id,
+                            event_kind: 7,
+                            timestamp: now,
+                        }
+                        .publish(env);
+                        StateTransition {
+                            sub_id,
+                            new_state: SubscriptionState::GracePeriod,
+                        }
+                        .publish(env);
+                    } else if data.state != SubscriptionState::GracePeriod {
+                        // A failure inside a running grace period leaves it running
+                        data.state = SubscriptionState::Retrying;
+                        StateTransition {
+                            sub_id,
+                            new_state: SubscriptionState::Retrying,
+                        }
+                        .publish(env);
+                    }
+
+                    // Record retry log
+                    Self::record_log(
+                        env,
+                        sub_id,
+                        EventTypeByte::Retry,
+                        soroban_sdk::String::from_str(env, "Renewal failed - scheduled for retry"),
+                    );
                 }
-                .publish(&env);
 
-                // Record retry log
-                Self::record_log(
-                    &env,
-                    sub_id,
-                    4,
-                    soroban_sdk::String::from_str(&env, "Renewal failed - scheduled for retry"),
-                );
-            }
+                Self::save_sub(env, sub_id, &data);
 
-            env.storage().persistent().set(&key, &data);
+                // Auto-release lock
+                Self::auto_release_lock(env, sub_id, current_ledger);
+
+                false
+            }
+        }
+    }
 
-            // Auto-release lock
+    /// Drop the renewal lock once an attempt has completed, if one is held.
+    fn auto_release_lock(env: &Env, sub_id: u64, current_ledger: u32) {
+        let lock_key = PersistentKey::RenewalLock(sub_id);
+        if env.storage().persistent().has(&lock_key) {
             env.storage().persistent().remove(&lock_key);
             RenewalLockReleased {
                 sub_id,
                 released_at: current_ledger,
             }
-            .publish(&env);
+            .publish(env);
+        }
+    }
+
+    /// Multi-sig request authorizing this renewal, if the subscription's team requires one.
+    /// The request must be keyed by the renewal's `cycle_id`, belong to the subscription's
+    /// team, cover `amount`, be `Approved` and still be inside its signing window.
+    fn check_multisig(
+        env: &Env,
+        data: &SubscriptionData,
+        sub_id: u64,
+        cycle_id: u64,
+        amount: i128,
+    ) -> Result<Option<MultiSigRequest>, &'static str> {
+        let team_id = match data.team_id {
+            Some(team_id) => team_id,
+            None => return Ok(None),
+        };
+        if !Self::requires_multisig(env.clone(), team_id, amount) {
+            return Ok(None);
+        }
+
+        let request: MultiSigRequest = env
+            .storage()
+            .persistent()
+            .get(&PersistentKey::MultiSig(sub_id, cycle_id))
+            .ok_or("Multi-sig approval required")?;
+        if request.status != MultiSigStatus::Approved {
+            return Err("Multi-sig request is not approved");
+        }
+        if env.ledger().timestamp() >= request.expires_at {
+            return Err("Multi-sig request has expired");
+        }
+        if request.team_id != team_id || amount > request.amount {
+            return Err("Multi-sig request does not cover this renewal");
+        }
+        Ok(Some(request))
+    }
+
+    /// Token amount to charge for `amount`. Subscriptions without FX pricing are
+    /// already in token units; fiat-quoted ones are converted through the oracle.
+    /// Returns the `RenewalFailed` reason code when the oracle cannot price the charge.
+    fn quote_charge(env: &Env, sub_id: u64, amount: i128) -> Result<i128, u32> {
+        let pricing: FxPricing = match env
+            .storage()
+            .persistent()
+            .get(&PersistentKey::FxPricing(sub_id))
+        {
+            Some(pricing) => pricing,
+            None => return Ok(amount),
+        };
+
+        let oracle_addr: Address = env
+            .storage()
+            .instance()
+            .get(&ContractKey::FxOracle)
+            .expect("FX oracle not configured");
+        let oracle = FxOracleContractClient::new(env, &oracle_addr);
+        if oracle.is_paused() {
+            return Err(FAILURE_FX_PAUSED);
+        }
+
+        let rate = match oracle.try_validate_rate(&pricing.quote_currency, &pricing.token_currency)
+        {
+            Ok(Ok(rate_data)) => rate_data.rate,
+            _ => return Err(FAILURE_FX_STALE),
+        };
 
-            false
+        let deviation = (rate - pricing.reference_rate).abs();
+        if deviation * BPS_DENOMINATOR > pricing.reference_rate * pricing.max_slippage_bps as i128 {
+            return Err(FAILURE_FX_SLIPPAGE);
         }
+
+        let token_amount =
+            match oracle.try_convert(&amount, &pricing.quote_currency, &pricing.token_currency) {
+                Ok(Ok(token_amount)) => token_amount,
+                _ => return Err(FAILURE_FX_STALE),
+            };
+
+        FxCharged {
+            sub_id,
+            quoted_amount: amount,
+            token_amount,
+            rate,
+        }
+        .publish(env);
+
+        Ok(token_amount)
+    }
+
+    /// Move `amount` from the owner to the merchant, through the payment adapter when
+    /// one is configured. Returns the token amount moved in the token's own units
+    /// (the adapter takes display units), or the `RenewalFailed` reason code.
+    fn settle_charge(
+        env: &Env,
+        data: &SubscriptionData,
+        payee: &Address,
+        amount: i128,
+    ) -> Result<i128, u32> {
+        if let Some(adapter) = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&ContractKey::PaymentAdapter)
+        {
+            let adapter_client = PaymentAdapterContractClient::new(env, &adapter);
+            return match adapter_client.try_settle_renewal(
+                &env.current_contract_address(),
+                &data.owner,
+                payee,
+                &data.token,
+                &amount,
+            ) {
+                Ok(Ok(raw_amount)) => Ok(raw_amount),
+                Err(Ok(err)) => Err(FAILURE_ADAPTER_BASE + err as u32),
+                _ => Err(FAILURE_TRANSFER),
+            };
+        }
+
+        // No adapter: pull directly with this contract as spender
+        let token_client = token::Client::new(env, &data.token);
+        match token_client.try_transfer_from(
+            &env.current_contract_address(),
+            &data.owner,
+            payee,
+            &amount,
+        ) {
+            Ok(Ok(())) => Ok(amount),
+            _ => Err(FAILURE_TRANSFER),
+        }
+    }
+
+    /// Settle `charge` with the keeper bounty split off: the merchant receives the rest.
+    /// Only the merchant leg decides success; if the bounty leg then fails, no bounty
+    /// is paid and the charge still stands. Returns the token amount moved in total.
+    fn settle_with_bounty(
+        env: &Env,
+        sub_id: u64,
+        data: &SubscriptionData,
+        keeper: &Address,
+        charge: i128,
+    ) -> Result<i128, u32> {
+        let bounty_bps = Self::get_keeper_bounty(env.clone()) as i128;
+        let bounty = charge * bounty_bps / BPS_DENOMINATOR;
+        let mut settled = Self::settle_charge(env, data, &data.merchant, charge - bounty)?;
+
+        if bounty > 0 {
+            if let Ok(bounty_settled) = Self::settle_charge(env, data, keeper, bounty) {
+                settled += bounty_settled;
+                KeeperBountyPaid {
+                    sub_id,
+                    keeper: keeper.clone(),
+                    amount: bounty,
+                }
+                .publish(env);
+            }
+        }
+        Ok(settled)
     }
 
     pub fn get_sub(env: Env, sub_id: u64) -> SubscriptionData {
@@ -906,6 +2858,57 @@ impl SubscriptionRenewalContract {
             .expect("Subscription not found")
     }
 
+    /// Subscription ids of `owner` in creation order, starting at position `cursor`.
+    /// Returns at most `limit` ids (capped at 100); pass `cursor + len` to get the next page.
+    pub fn list_by_owner(env: Env, owner: Address, cursor: u32, limit: u32) -> Vec<u64> {
+        let count = env
+            .storage()
+            .persistent()
+            .get(&PersistentKey::OwnerSubCount(owner.clone()))
+            .unwrap_or(0);
+        Self::page(&env, count, cursor, limit, |position| {
+            PersistentKey::OwnerSub(owner.clone(), position)
+        })
+    }
+
+    /// Subscription ids of `merchant` in creation order; paginated like `list_by_owner`.
+    pub fn list_by_merchant(env: Env, merchant: Address, cursor: u32, limit: u32) -> Vec<u64> {
+        let count = env
+            .storage()
+            .persistent()
+            .get(&PersistentKey::MerchantSubCount(merchant.clone()))
+            .unwrap_or(0);
+        Self::page(&env, count, cursor, limit, |position| {
+            PersistentKey::MerchantSub(merchant.clone(), position)
+        })
+    }
+
+    /// Active count, MRR and failed count over a merchant's subscriptions.
+    pub fn get_merchant_stats(env: Env, merchant: Address) -> MerchantStats {
+        env.storage()
+            .persistent()
+            .get(&PersistentKey::MerchantStats(merchant))
+            .unwrap_or_default()
+    }
+
+    /// Read positions `[cursor, cursor + limit)` of an index holding `count` entries.
+    fn page(
+        env: &Env,
+        count: u32,
+        cursor: u32,
+        limit: u32,
+        entry: impl Fn(u32) -> PersistentKey,
+    ) -> Vec<u64> {
+        let mut ids = Vec::new(env);
+        let end = cursor.saturating_add(limit.min(MAX_PAGE_SIZE)).min(count);
+        for position in cursor..end {
+            if let Some(sub_id) = env.storage().persistent().get(&entry(position)) {
+                ids.push_back(sub_id);
+            }
+        }
+        ids
+    }
+
     pub fn get_lifecycle(env: Env, sub_id: u64) -> LifecycleTimestamps {
         let lc_key = PersistentKey::Lifecycle(sub_id);
         env.storage()
@@ -940,13 +2943,87 @@ impl SubscriptionRenewalContract {
         env.storage().persistent().get(&key)
     }
 
-    /// Set global spending cap for a user. Admin only.
-    pub fn set_user_cap(env: Env, user: Address, cap: i128) {
+    /// Price a subscription in a fiat currency. Owner only.
+    /// `amount`, approvals and spending caps are then denominated in `quote_currency`;
+    /// each renewal converts through the FX oracle and fails if the live rate deviates
+    /// from the current rate (snapshotted here) by more than `max_slippage_bps`.
+    /// Calling again refreshes the reference rate.
+    pub fn set_fx_pricing(
+        env: Env,
+        sub_id: u64,
+        quote_currency: String,
+        token_currency: String,
+        max_slippage_bps: u32,
+    ) {
+        let data: SubscriptionData = env
+            .storage()
+            .persistent()
+            .get(&PersistentKey::Subscription(sub_id))
+            .expect("Subscription not found");
+        data.owner.require_auth();
+
+        if max_slippage_bps as i128 > BPS_DENOMINATOR {
+            panic!("Slippage bound exceeds 100%");
+        }
+
+        let oracle_addr: Address = env
+            .storage()
+            .instance()
+            .get(&ContractKey::FxOracle)
+            .expect("FX oracle not configured");
+        let reference_rate = FxOracleContractClient::new(&env, &oracle_addr)
+            .validate_rate(&quote_currency, &token_currency)
+            .rate;
+
+        let pricing = FxPricing {
+            quote_currency: quote_currency.clone(),
+            token_currency,
+            reference_rate,
+            max_slippage_bps,
+        };
+        env.storage()
+            .persistent()
+            .set(&PersistentKey::FxPricing(sub_id), &pricing);
+
+        FxPricingUpdated {
+            sub_id,
+            quote_currency,
+            reference_rate,
+            max_slippage_bps,
+        }
+        .publish(&env);
+    }
+
+    /// Get the fiat pricing terms for a subscription, if it is fiat-quoted.
+    pub fn get_fx_pricing(env: Env, sub_id: u64) -> Option<FxPricing> {
+        env.storage()
+            .persistent()
+            .get(&PersistentKey::FxPricing(sub_id))
+    }
+
+    /// Set a user's spending cap across all subscriptions. Admin only.
+    /// The cap applies per window of `period_secs` (e.g. 30 days); windows roll
+    /// over automatically. A `period_secs` of 0 makes it a lifetime cap.
+    pub fn set_user_cap(env: Env, user: Address, cap: i128, period_secs: u64) {
         Self::require_admin(&env);
         env.storage()
             .persistent()
             .set(&PersistentKey::UserCap(user.clone()), &cap);
-        UserCapUpdated { user, cap }.publish(&env);
+        env.storage()
+            .persistent()
+            .set(&PersistentKey::UserCapPeriod(user.clone()), &period_secs);
+        // Windows are aligned to when the user's first cap was set
+        let window_key = PersistentKey::UserSpent(user.clone());
+        if !env.storage().persistent().has(&window_key) {
+            let window = Self::current_window(&env, &user);
+            env.storage().persistent().set(&window_key, &window);
+        }
+        UserCapUpdated {
+            user,
+            cap,
+            period_secs,
+        }
+        .publish(&env);
     }
 
     /// Get global spending cap for a user.
@@ -957,14 +3034,132 @@ impl SubscriptionRenewalContract {
             .unwrap_or(0)
     }
 
-    /// Get current global spent amount for a user.
-    pub fn get_user_spent(env: Env, user: Address) -> i128 {
+    /// Length of a user's spending-cap window in seconds (0 for a lifetime cap).
+    pub fn get_user_cap_period(env: Env, user: Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&PersistentKey::UserCapPeriod(user))
+            .unwrap_or(0)
+    }
+
+    /// Cap what a user spends with one merchant in each window of their
+    /// spending cap (or over their lifetime without one). Admin only.
+    pub fn set_merchant_cap(env: Env, user: Address, merchant: Address, cap: i128) {
+        Self::require_admin(&env);
+        env.storage().persistent().set(
+            &PersistentKey::MerchantCap(user.clone(), merchant.clone()),
+            &cap,
+        );
+        MerchantCapUpdated {
+            user,
+            merchant,
+            cap,
+        }
+        .publish(&env);
+    }
+
+    /// Get a user's spending cap for one merchant.
+    pub fn get_merchant_cap(env: Env, user: Address, merchant: Address) -> i128 {
         env.storage()
             .persistent()
-            .get(&PersistentKey::UserSpent(user))
+            .get(&PersistentKey::MerchantCap(user, merchant))
             .unwrap_or(0)
     }
 
+    /// Get what a user has spent in the current window of their spending cap.
+    pub fn get_user_spent(env: Env, user: Address) -> i128 {
+        Self::current_window(&env, &user).spent
+    }
+
+    /// Get what a user has spent with one merchant in the current window.
+    pub fn get_merchant_spent(env: Env, user: Address, merchant: Address) -> i128 {
+        let window = Self::current_window(&env, &user);
+        Self::merchant_spent(&env, &user, &merchant, &window)
+    }
+
+    /// What a user can still spend in the current window, across all
+    /// subscriptions or, with `merchant`, with that merchant (the lower of the
+    /// user cap and the merchant sub-cap). None if no cap applies.
+    pub fn get_remaining_budget(
+        env: Env,
+        user: Address,
+        merchant: Option<Address>,
+    ) -> Option<i128> {
+        let window = Self::current_window(&env, &user);
+        let global_cap = Self::get_user_cap(env.clone(), user.clone());
+        let mut remaining = if global_cap > 0 {
+            Some((global_cap - window.spent).max(0))
+        } else {
+            None
+        };
+
+        if let Some(merchant) = merchant {
+            let merchant_cap = Self::get_merchant_cap(env.clone(), user.clone(), merchant.clone());
+            if merchant_cap > 0 {
+                let spent = Self::merchant_spent(&env, &user, &merchant, &window);
+                let merchant_remaining = (merchant_cap - spent).max(0);
+                remaining =
+                    Some(remaining.map_or(merchant_remaining, |r| r.min(merchant_remaining)));
+            }
+        }
+        remaining
+    }
+
+    /// The user's spending window as of now, rolled over if its period has ended.
+    fn current_window(env: &Env, user: &Address) -> SpendWindow {
+        let mut window = env
+            .storage()
+            .persistent()
+            .get(&PersistentKey::UserSpent(user.clone()))
+            .unwrap_or(SpendWindow {
+                period_start: env.ledger().timestamp(),
+                spent: 0,
+            });
+        let period = Self::get_user_cap_period(env.clone(), user.clone());
+        Self::roll_window(env, &mut window, period);
+        window
+    }
+
+    /// Advance `period_start` and reset `spent` if one or more full periods have
+    /// elapsed since the window began. The new window is aligned to period
+    /// boundaries so caps track fixed cycles rather than drifting with each charge.
+    fn roll_window(env: &Env, window: &mut SpendWindow, period: u64) {
+        if period == 0 {
+            return;
+        }
+        let elapsed = env.ledger().timestamp().saturating_sub(window.period_start);
+        if elapsed >= period {
+            window.period_start += (elapsed / period) * period;
+            window.spent = 0;
+        }
+    }
+
+    /// Amount spent with `merchant` inside the user's current `window`.
+    fn merchant_spent(env: &Env, user: &Address, merchant: &Address, window: &SpendWindow) -> i128 {
+        env.storage()
+            .persistent()
+            .get::<_, SpendWindow>(&PersistentKey::MerchantSpent(user.clone(), merchant.clone()))
+            .filter(|merchant_window| merchant_window.period_start == window.period_start)
+            .map_or(0, |merchant_window| merchant_window.spent)
+    }
+
+    /// Count a settled charge against the user's window and their merchant sub-window.
+    fn record_spend(env: &Env, user: &Address, merchant: &Address, amount: i128) {
+        let mut window = Self::current_window(env, user);
+        let merchant_window = SpendWindow {
+            period_start: window.period_start,
+            spent: Self::merchant_spent(env, user, merchant, &window) + amount,
+        };
+        window.spent += amount;
+        env.storage()
+            .persistent()
+            .set(&PersistentKey::UserSpent(user.clone()), &window);
+        env.storage().persistent().set(
+            &PersistentKey::MerchantSpent(user.clone(), merchant.clone()),
+            &merchant_window,
+        );
+    }
+
     // ── Multi-sig approval management ─────────────────────────────
 
     /// Set the multi-sig approval threshold for a team. Admin only.
@@ -1015,10 +3210,81 @@ impl SubscriptionRenewalContract {
             .unwrap_or(DEFAULT_SIGNING_WINDOW_SECS)
     }
 
+    /// Set the signer set and quorum for a team's multi-sig requests. Admin only.
+    /// Requests must name exactly these signers and are approved after `quorum` signatures.
+    pub fn set_team_signers(
+        env: Env,
+        team_id: u64,
+        signers: soroban_sdk::Vec<Address>,
+        quorum: u32,
+    ) {
+        Self::require_admin(&env);
+        if signers.is_empty() {
+            panic!("At least one signer is required");
+        }
+        if quorum == 0 || quorum > signers.len() {
+            panic!("Quorum must be between 1 and the number of signers");
+        }
+        for i in 0..signers.len() {
+            let signer = signers.get(i).unwrap();
+            if signers.first_index_of(&signer) != Some(i) {
+                panic!("Duplicate signer");
+            }
+        }
+
+        let policy = TeamSignerPolicy {
+            signers: signers.clone(),
+            quorum,
+        };
+        env.storage()
+            .persistent()
+            .set(&PersistentKey::TeamSigners(team_id), &policy);
+
+        TeamSignersUpdated {
+            team_id,
+            signer_count: signers.len(),
+            quorum,
+        }
+        .publish(&env);
+    }
+
+    /// Get the signer policy for a team.
+    pub fn get_team_signers(env: Env, team_id: u64) -> TeamSignerPolicy {
+        env.storage()
+            .persistent()
+            .get(&PersistentKey::TeamSigners(team_id))
+            .expect("Team signer policy not configured")
+    }
+
+    /// Place a subscription under a team's multi-sig policy. Owner only.
+    /// Renewals above the team threshold then need an approved multi-sig request
+    /// whose `request_id` is the renewal's `cycle_id`. A team cannot be changed once set.
+    pub fn assign_team(env: Env, sub_id: u64, team_id: u64) {
+        let key = PersistentKey::Subscription(sub_id);
+        let mut data: SubscriptionData = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("Subscription not found");
+        data.owner.require_auth();
+
+        if data.team_id.is_some() {
+            panic!("Subscription already belongs to a team");
+        }
+        data.team_id = Some(team_id);
+        Self::save_sub(&env, sub_id, &data);
+
+        TeamAssigned { sub_id, team_id }.publish(&env);
+    }
+
     /// Create a multi-sig renewal request.
-    /// Called when a renewal amount exceeds the team threshold.
-    /// The `requester` must be authenticated. `required_signers` is the list
-    /// of team admin addresses that must co-sign before the request is approved.
+    /// Called when a renewal amount exceeds the team threshold. For team
+    /// subscriptions, `request_id` is the billing cycle the request authorizes.
+    /// The `requester` must be authenticated and be the owner, one of the team's
+    /// signers or an agent with the `Renewals` scope. The subscription must belong to
+    /// `team_id`, and `required_signers` must match the team's signer policy
+    /// set by the admin via `set_team_signers`. A cancelled or lapsed request for
+    /// the same `request_id` is replaced.
     pub fn request_multisig_renewal(
         env: Env,
         sub_id: u64,
@@ -1033,15 +3299,24 @@ impl SubscriptionRenewalContract {
         }
         requester.require_auth();
 
-        // Validate subscription exists
-        let _data: SubscriptionData = env
+        let data: SubscriptionData = env
             .storage()
             .persistent()
             .get(&PersistentKey::Subscription(sub_id))
             .expect("Subscription not found");
+        if data.team_id != Some(team_id) {
+            panic!("Subscription does not belong to this team");
+        }
 
-        if required_signers.is_empty() {
-            panic!("At least one signer is required");
+        let policy = Self::get_team_signers(env.clone(), team_id);
+        if required_signers != policy.signers {
+            panic!("Signers do not match team policy");
+        }
+        if requester != data.owner
+            && !policy.signers.contains(&requester)
+            && !Self::has_renewals_scope(&env, &requester)
+        {
+            panic!("Requester cannot open multi-sig requests");
         }
 
         let now = env.ledger().timestamp();
@@ -1050,9 +3325,21 @@ impl SubscriptionRenewalContract {
 
         let ms_key = PersistentKey::MultiSig(sub_id, request_id);
 
-        // Prevent duplicate request IDs
-        if env.storage().persistent().has(&ms_key) {
-            panic!("Multi-sig request already exists");
+        // Prevent duplicate request IDs; only a dead request can be replaced
+        if let Some(existing) = env
+            .storage()
+            .persistent()
+            .get::<_, MultiSigRequest>(&ms_key)
+        {
+            let lapsed = existing.status == MultiSigStatus::Pending && now >= existing.expires_at;
+            if !lapsed
+                && !matches!(
+                    existing.status,
+                    MultiSigStatus::Cancelled | MultiSigStatus::Expired
+                )
+            {
+                panic!("Multi-sig request already exists");
+            }
         }
 
         let request = MultiSigRequest {
@@ -1061,7 +3348,8 @@ impl SubscriptionRenewalContract {
             team_id,
             amount,
             requester: requester.clone(),
-            required_signers: required_signers.clone(),
+            required_signers,
+            quorum: policy.quorum,
             collected_signers: soroban_sdk::Vec::new(&env),
             created_at: now,
             expires_at,
@@ -1092,7 +3380,7 @@ impl SubscriptionRenewalContract {
 
     /// Sign (co-approve) a pending multi-sig renewal request.
     /// The signer must be one of the `required_signers` and must authenticate.
-    /// When all required signatures are collected, the status transitions to Approved.
+    /// Once the team quorum of signatures is collected, the status transitions to Approved.
     pub fn sign_multisig_renewal(
         env: Env,
         sub_id: u64,
@@ -1160,7 +3448,7 @@ impl SubscriptionRenewalContract {
         request.collected_signers.push_back(signer.clone());
 
         let collected = request.collected_signers.len();
-        let required = request.required_signers.len();
+        let required = request.quorum;
 
         MultiSigSigned {
             sub_id,
@@ -1,31 +1,82 @@
 #![cfg(test)]
 
-use soroban_sdk::{testutils::{Address as _, Ledger as _}, Address, Env};
+use agent_registry::{AgentRegistry, AgentRegistryClient, Scope};
+use fx_oracle::{FxOracleContract, FxOracleContractClient};
+use payment_adapter::{PaymentAdapterContract, PaymentAdapterContractClient};
+use soroban_sdk::{
+    contract, contractimpl,
+    testutils::{Address as _, Ledger as _, MockAuth, MockAuthInvoke},
+    token::{StellarAssetClient, TokenClient},
+    vec, Address, Bytes, BytesN, Env, IntoVal, String,
+};
+use subscription_refund::{SubscriptionRefundContract, SubscriptionRefundContractClient};
+use subscription_registry::{SubscriptionRegistry, SubscriptionRegistryClient};
 
 use super::{
-    SubscriptionRenewalContract, SubscriptionRenewalContractClient, SubscriptionState,
+    hash_blinding_salt, CancelMode, DunningPolicy, FinalAction, IntroOffer, MultiSigStatus,
+    RetryBackoff, SubscriptionRenewalContract, SubscriptionRenewalContractClient,
+    SubscriptionState, UsageTier,
 };
 
-fn setup() -> (Env, Address, Address) {
+/// Returns (env, renewal contract, admin, token, agent with the `Renewals` scope).
+fn setup() -> (Env, Address, Address, Address, Address) {
     let env = Env::default();
     env.mock_all_auths();
     let id = env.register_contract(None, SubscriptionRenewalContract);
     let admin = Address::generate(&env);
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     client.init(&admin);
-    (env, id, admin)
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
+    let agent = renewals_agent(&env, &id, &admin);
+    client.set_default_dunning_policy(&dunning_policy(&env, 4));
+    (env, id, admin, token, agent)
+}
+
+/// `max_attempts` failed charges 10 ledgers apart, then suspension.
+fn dunning_policy(env: &Env, max_attempts: u32) -> DunningPolicy {
+    DunningPolicy {
+        max_attempts,
+        backoff: RetryBackoff::Fixed(vec![env, 10]),
+        grace_secs: 0,
+        final_action: FinalAction::Suspend,
+    }
+}
+
+/// Register an agent registry on the renewal contract with one `Renewals` agent.
+fn renewals_agent(env: &Env, id: &Address, admin: &Address) -> Address {
+    let registry_id = env.register(AgentRegistry, ());
+    let registry = AgentRegistryClient::new(env, &registry_id);
+    registry.init(admin);
+    let agent = Address::generate(env);
+    registry.register(&agent);
+    registry.update_scopes(&agent, &(Scope::Renewals as u32));
+    SubscriptionRenewalContractClient::new(env, id).set_agent_registry(&registry_id);
+    agent
+}
+
+/// Hash of a fixed owner salt, as registered at `init_sub` for audit commitment blinding.
+fn salt(env: &Env) -> BytesN<32> {
+    hash_blinding_salt(env, &BytesN::from_array(env, &[7u8; 32]))
+}
+
+/// Mint `amount` to `owner` and let the renewal contract pull it.
+fn fund(env: &Env, token: &Address, renewal: &Address, owner: &Address, amount: i128) {
+    StellarAssetClient::new(env, token).mint(owner, &amount);
+    TokenClient::new(env, token).approve(owner, renewal, &amount, &1_000_000u32);
 }
 
 #[test]
 fn test_renew_works_after_unpause() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 101;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &1, &1000, &100);
 
     // Pause then unpause
@@ -33,15 +84,15 @@ fn test_renew_works_after_unpause() {
     client.set_paused(&false);
 
     // Should succeed now
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &20260101, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260101);
     assert!(result);
 }
 
 #[test]
 #[should_panic(expected = "Already initialized")]
 fn test_cannot_init_twice() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, _token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let another = Address::generate(&env);
     client.init(&another);
@@ -51,18 +102,18 @@ fn test_cannot_init_twice() {
 
 #[test]
 fn test_renewal_success() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 123;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &1, &1000, &100);
 
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &20260115, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260115);
     assert!(result);
 
     let data = client.get_sub(&sub_id);
@@ -70,31 +121,68 @@ fn test_renewal_success() {
     assert_eq!(data.failure_count, 0);
 }
 
+#[test]
+fn test_renewal_transfers_amount_to_merchant() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 800);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &500, &20260115));
+
+    assert_eq!(token_client.balance(&user), 300);
+    assert_eq!(token_client.balance(&merchant), 500);
+    assert_eq!(client.get_user_spent(&user), 500);
+}
+
+#[test]
+fn test_insufficient_allowance_fails_without_moving_funds() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    // Balance is there, but the allowance only covers part of the charge
+    StellarAssetClient::new(&env, &token).mint(&user, &1000);
+    token_client.approve(&user, &id, &100, &1_000_000u32);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(!client.renew(&agent, &sub_id, &1, &500, &20260115));
+
+    let data = client.get_sub(&sub_id);
+    assert_eq!(data.state, SubscriptionState::Retrying);
+    assert_eq!(data.failure_count, 1);
+    assert_eq!(token_client.balance(&user), 1000);
+    assert_eq!(token_client.balance(&merchant), 0);
+    assert_eq!(client.get_user_spent(&user), 0);
+}
+
 #[test]
 fn test_retry_logic() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 456;
-    let max_retries = 2;
-    let cooldown = 10;
+    client.set_default_dunning_policy(&dunning_policy(&env, 3));
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
     // First failure (cycle_id same for retries — allowed because failure doesn't store cycle)
     client.approve_renewal(&sub_id, &1, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(
-        &sub_id,
-        &1,
-        &500,
-        &max_retries,
-        &cooldown,
-        &20260201,
-        &false,
-    );
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260201);
     assert!(!result);
 
     let data = client.get_sub(&sub_id);
@@ -108,34 +196,18 @@ fn test_retry_logic() {
 
     // renewal attempt but fail again (ledger 100)
     client.approve_renewal(&sub_id, &2, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(
-        &sub_id,
-        &2,
-        &500,
-        &max_retries,
-        &cooldown,
-        &20260201,
-        &false,
-    );
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &2, &500, &20260201);
 
     // Advance past cooldown
     env.ledger().with_mut(|li| {
         li.sequence_number = 120;
     });
 
-    // Third failure (count becomes 3 > max_retries 2) -> Should fail
+    // Third failure (count reaches max_attempts 3) -> Should fail
     client.approve_renewal(&sub_id, &3, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(
-        &sub_id,
-        &3,
-        &500,
-        &max_retries,
-        &cooldown,
-        &20260201,
-        &false,
-    );
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &3, &500, &20260201);
 
     let data = client.get_sub(&sub_id);
     assert_eq!(data.state, SubscriptionState::Failed);
@@ -145,41 +217,40 @@ fn test_retry_logic() {
 #[test]
 #[should_panic(expected = "Cooldown period active")]
 fn test_cooldown_enforcement() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 789;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
     // Fail once
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &1, &500, &3, &10, &20260301, &false);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &500, &20260301);
 
     // Try again immediately (cooldown not met)
     client.approve_renewal(&sub_id, &2, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &2, &500, &3, &10, &20260301, &false);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &2, &500, &20260301);
 }
 
 #[test]
 fn test_event_emission_on_success() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 999;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &1, &1000, &100);
 
     // Successful renewal should emit RenewalSuccess event
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &20260315, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260315);
     assert!(result);
 
     // Verify event was emitted by checking subscription data
@@ -189,21 +260,20 @@ fn test_event_emission_on_success() {
 }
 
 #[test]
-fn test_zero_max_retries() {
-    let (env, id, _admin) = setup();
+fn test_single_attempt_policy() {
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 111;
-    let max_retries = 0;
+    client.set_default_dunning_policy(&dunning_policy(&env, 1));
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
     client.approve_renewal(&sub_id, &1, &1000, &100);
 
-    // First failure with max_retries = 0 should immediately fail
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &1, &500, &max_retries, &10, &20260401, &false);
+    // First failure with max_attempts = 1 should immediately fail
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260401);
     assert!(!result);
 
     let data = client.get_sub(&sub_id);
@@ -213,29 +283,18 @@ fn test_zero_max_retries() {
 
 #[test]
 fn test_multiple_failures_then_success() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 222;
-    let max_retries = 3;
-    let cooldown = 10;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
     // First failure
     client.approve_renewal(&sub_id, &1, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(
-        &sub_id,
-        &1,
-        &500,
-        &max_retries,
-        &cooldown,
-        &20260501,
-        &false,
-    );
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &500, &20260501);
     let data = client.get_sub(&sub_id);
     assert_eq!(data.state, SubscriptionState::Retrying);
     assert_eq!(data.failure_count, 1);
@@ -247,16 +306,8 @@ fn test_multiple_failures_then_success() {
 
     // Second failure
     client.approve_renewal(&sub_id, &2, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(
-        &sub_id,
-        &2,
-        &500,
-        &max_retries,
-        &cooldown,
-        &20260501,
-        &false,
-    );
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &2, &500, &20260501);
     let data = client.get_sub(&sub_id);
     assert_eq!(data.state, SubscriptionState::Retrying);
     assert_eq!(data.failure_count, 2);
@@ -266,10 +317,11 @@ fn test_multiple_failures_then_success() {
         li.sequence_number = 40;
     });
 
-    // Now succeed - should reset failure count and return to Active
+    // Now succeed once the owner has funds - should reset failure count and return to Active
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &3, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &3, &500, &max_retries, &cooldown, &20260501, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &3, &500, &20260501);
     assert!(result);
 
     let data = client.get_sub(&sub_id);
@@ -280,45 +332,27 @@ fn test_multiple_failures_then_success() {
 #[test]
 #[should_panic(expected = "Subscription is in FAILED state")]
 fn test_cannot_renew_failed_subscription() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 333;
-    let max_retries = 1;
-    let cooldown = 10;
+    client.set_default_dunning_policy(&dunning_policy(&env, 2));
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
     // Fail twice to reach Failed state
     client.approve_renewal(&sub_id, &1, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(
-        &sub_id,
-        &1,
-        &500,
-        &max_retries,
-        &cooldown,
-        &20260601,
-        &false,
-    );
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &500, &20260601);
 
     env.ledger().with_mut(|li| {
         li.sequence_number = 20;
     });
 
     client.approve_renewal(&sub_id, &2, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(
-        &sub_id,
-        &2,
-        &500,
-        &max_retries,
-        &cooldown,
-        &20260601,
-        &false,
-    );
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &2, &500, &20260601);
 
     let data = client.get_sub(&sub_id);
     assert_eq!(data.state, SubscriptionState::Failed);
@@ -330,89 +364,89 @@ fn test_cannot_renew_failed_subscription() {
 
     // Try to renew a FAILED subscription - should panic
     client.approve_renewal(&sub_id, &3, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &3, &500, &max_retries, &cooldown, &20260701, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &3, &500, &20260701);
 }
 
 // ── Approval system tests ────────────────────────────────────────
 
 #[test]
 fn test_approval_required_for_renewal() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 500;
     let approval_id = 1;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // Create approval
     client.approve_renewal(&sub_id, &approval_id, &1000, &100);
 
     // Renew with valid approval
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &approval_id, &500, &3, &10, &20260801, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &approval_id, &500, &20260801);
     assert!(result);
 }
 
 #[test]
 #[should_panic(expected = "Invalid or expired approval")]
 fn test_renewal_without_approval_fails() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 501;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // Try to renew without creating approval
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &999, &500, &3, &10, &20260901, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &999, &500, &20260901);
 }
 
 #[test]
 #[should_panic(expected = "Invalid or expired approval")]
 fn test_approval_cannot_be_reused() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 502;
     let approval_id = 2;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &approval_id, &1000, &100);
 
     // First use - should succeed
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &approval_id, &500, &3, &10, &20261001, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &approval_id, &500, &20261001);
 
     env.ledger().with_mut(|li| {
         li.sequence_number = 20;
     });
 
     // Second use - should fail (already used) — use different cycle_id to bypass cycle guard
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &approval_id, &500, &3, &10, &20261101, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &approval_id, &500, &20261101);
 }
 
 #[test]
 #[should_panic(expected = "Invalid or expired approval")]
 fn test_expired_approval_rejected() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 503;
     let approval_id = 3;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // Create approval that expires at ledger 50
     client.approve_renewal(&sub_id, &approval_id, &1000, &50);
@@ -423,57 +457,57 @@ fn test_expired_approval_rejected() {
     });
 
     // Try to use expired approval
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &approval_id, &500, &3, &10, &20261201, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &approval_id, &500, &20261201);
 }
 
 #[test]
 #[should_panic(expected = "Invalid or expired approval")]
 fn test_amount_exceeds_max_spend() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 504;
     let approval_id = 4;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // Create approval with max_spend = 1000
     client.approve_renewal(&sub_id, &approval_id, &1000, &100);
 
     // Try to renew with amount > max_spend
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &approval_id, &1500, &3, &10, &20270101, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &approval_id, &1500, &20270101);
 }
 
 #[test]
 fn test_multiple_approvals_for_same_subscription() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 505;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &5000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &5000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // Create multiple approvals
     client.approve_renewal(&sub_id, &1, &1000, &100);
     client.approve_renewal(&sub_id, &2, &2000, &200);
 
     // Use first approval
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &1, &500, &3, &10, &20270201, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &500, &20270201);
 
     env.ledger().with_mut(|li| {
         li.sequence_number = 20;
     });
 
     // Use second approval — different cycle_id since first succeeded
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &2, &1500, &3, &10, &20270301, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &2, &1500, &20270301);
     assert!(result);
 }
 
@@ -482,44 +516,43 @@ fn test_multiple_approvals_for_same_subscription() {
 #[test]
 #[should_panic(expected = "Duplicate renewal for cycle")]
 fn test_duplicate_cycle_rejected_after_success() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 600;
     let cycle_id = 20260315;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // First renewal succeeds — stores cycle_id
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &cycle_id, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &cycle_id);
     assert!(result);
 
     // Second renewal with same cycle_id — should panic
     client.approve_renewal(&sub_id, &2, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &2, &500, &3, &10, &cycle_id, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &2, &500, &cycle_id);
 }
 
 #[test]
 fn test_retry_same_cycle_allowed_after_failure() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 601;
     let cycle_id = 20260315;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
-    // First attempt fails — does NOT store cycle_id
+    // First attempt fails (no allowance) — does NOT store cycle_id
     client.approve_renewal(&sub_id, &1, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &cycle_id, &false);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &cycle_id);
     assert!(!result);
 
     // Advance ledger past cooldown
@@ -528,51 +561,52 @@ fn test_retry_same_cycle_allowed_after_failure() {
     });
 
     // Retry with same cycle_id — should succeed because failure didn't record cycle
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &2, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &2, &500, &3, &10, &cycle_id, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &2, &500, &cycle_id);
     assert!(result);
 }
 
 #[test]
 fn test_different_cycle_allowed_after_success() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 602;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // First cycle succeeds
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &20260315, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260315);
     assert!(result);
 
     // Different cycle_id — should succeed
     client.approve_renewal(&sub_id, &2, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &2, &500, &3, &10, &20260415, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &2, &500, &20260415);
     assert!(result);
 }
 
 #[test]
 fn test_first_renewal_always_allowed() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 603;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // First renewal ever — no stored cycle, guard passes
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &20260101, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260101);
     assert!(result);
 
     let data = client.get_sub(&sub_id);
@@ -581,17 +615,16 @@ fn test_first_renewal_always_allowed() {
 
 #[test]
 fn test_cancel_sub() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 600;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
     // Cancel subscription
-    client.cancel_sub(&sub_id);
+    client.cancel_sub(&sub_id, &CancelMode::Immediate);
 
     let data = client.get_sub(&sub_id);
     assert_eq!(data.state, SubscriptionState::Cancelled);
@@ -600,84 +633,298 @@ fn test_cancel_sub() {
 #[test]
 #[should_panic(expected = "Subscription already cancelled")]
 fn test_cannot_cancel_twice() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 601;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+
+    client.cancel_sub(&sub_id, &CancelMode::Immediate);
+    client.cancel_sub(&sub_id, &CancelMode::Immediate);
+}
+
+#[test]
+#[should_panic(expected = "Amount must be positive")]
+fn test_init_sub_rejects_zero_amount() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    client.init_sub(&owner, &merchant, &token, &0, &86400, &0, &salt(&env));
+}
 
-    client.cancel_sub(&sub_id);
-    client.cancel_sub(&sub_id);
+#[test]
+#[should_panic(expected = "Frequency must be positive")]
+fn test_init_sub_rejects_zero_frequency() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    client.init_sub(&owner, &merchant, &token, &1000, &0, &0, &salt(&env));
 }
 
 #[test]
 #[should_panic(expected = "Subscription not found")]
 fn test_cancel_non_existent_sub() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, _token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    client.cancel_sub(&999, &CancelMode::Immediate);
+}
+
+/// Register a refund contract and point the renewal contract at it.
+fn refund_contract(env: &Env, id: &Address, admin: &Address) -> Address {
+    let refund_id = env.register(SubscriptionRefundContract, ());
+    SubscriptionRefundContractClient::new(env, &refund_id).init(admin, admin);
+    SubscriptionRenewalContractClient::new(env, id).set_refund_contract(&refund_id);
+    refund_id
+}
+
+/// Sub with one 1000 charge, cancelled immediately a quarter into the paid period.
+/// Returns (sub_id, owner, merchant).
+fn cancel_after_first_charge(
+    env: &Env,
+    id: &Address,
+    token: &Address,
+    agent: &Address,
+) -> (u64, Address, Address) {
+    let client = SubscriptionRenewalContractClient::new(env, id);
+    let owner = Address::generate(env);
+    let merchant = Address::generate(env);
+    let sub_id = client.init_sub(&owner, &merchant, token, &1000, &86400, &0, &salt(env));
+    fund(env, token, id, &owner, 1000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(agent, &sub_id, &200);
+    assert!(client.renew(agent, &sub_id, &1, &1000, &1));
+
+    env.ledger().with_mut(|li| li.timestamp += 21600);
+    client.cancel_sub(&sub_id, &CancelMode::Immediate);
+    (sub_id, owner, merchant)
+}
+
+#[test]
+fn test_cancel_immediately_refunds_unused_period() {
+    let (env, id, admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+    refund_contract(&env, &id, &admin);
+    let (sub_id, owner, merchant) = cancel_after_first_charge(&env, &id, &token, &agent);
+
+    // A quarter of the paid period used: three quarters are queued for the owner
+    assert_eq!(client.get_pending_refund(&sub_id), 750);
+    assert_eq!(token_client.balance(&merchant), 1000);
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Cancelled);
+    let lc = client.get_lifecycle(&sub_id);
+    assert_eq!(lc.cancel_mode, CancelMode::Immediate as u32);
+    assert_eq!(lc.canceled_at, env.ledger().timestamp());
+
+    client.settle_refund(&sub_id);
+    assert_eq!(token_client.balance(&owner), 750);
+    assert_eq!(token_client.balance(&merchant), 250);
+    assert_eq!(client.get_pending_refund(&sub_id), 0);
+}
+
+#[test]
+fn test_cancel_immediately_needs_only_owner_auth() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 1000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &1));
+
+    // No refund contract and no merchant signature: the owner can still cancel
+    env.mock_auths(&[MockAuth {
+        address: &owner,
+        invoke: &MockAuthInvoke {
+            contract: &id,
+            fn_name: "cancel_sub",
+            args: (sub_id, CancelMode::Immediate).into_val(&env),
+            sub_invokes: &[],
+        },
+    }]);
+    client.cancel_sub(&sub_id, &CancelMode::Immediate);
+
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Cancelled);
+    assert_eq!(client.get_pending_refund(&sub_id), 1000);
+}
+
+#[test]
+#[should_panic(expected = "Refund contract not configured")]
+fn test_settle_refund_requires_refund_contract() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, _owner, _merchant) = cancel_after_first_charge(&env, &id, &token, &agent);
+    client.settle_refund(&sub_id);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_settle_refund_requires_merchant_auth() {
+    let (env, id, admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    refund_contract(&env, &id, &admin);
+    let (sub_id, _owner, _merchant) = cancel_after_first_charge(&env, &id, &token, &agent);
+    env.set_auths(&[]);
+    client.settle_refund(&sub_id);
+}
+
+#[test]
+fn test_cancel_at_period_end_stays_active_then_cancels_without_charging() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 5000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &1));
+
+    let period_end = client.next_due_at(&sub_id);
+    client.cancel_sub(&sub_id, &CancelMode::AtPeriodEnd);
+
+    // Still active and entitled for the rest of the paid period
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Active);
+    assert!(client.is_entitled(&sub_id));
+    let lc = client.get_lifecycle(&sub_id);
+    assert_eq!(lc.cancel_mode, CancelMode::AtPeriodEnd as u32);
+    assert_eq!(lc.cancel_at, period_end);
+
+    // At the end of the period the keeper cancels instead of charging
+    env.ledger().with_mut(|li| li.timestamp = period_end);
+    assert!(!client.is_entitled(&sub_id));
+    assert_eq!(client.process_due(&keeper, &vec![&env, sub_id]), 0);
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Cancelled);
+    let lc = client.get_lifecycle(&sub_id);
+    assert_eq!(lc.canceled_at, period_end);
+    assert_eq!(lc.cancel_at, 0);
+    assert_eq!(token_client.balance(&owner), 4000);
+    assert_eq!(token_client.balance(&merchant), 1000);
+}
+
+#[test]
+fn test_renew_at_period_end_completes_scheduled_cancellation() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 5000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &1));
+    client.cancel_sub(&sub_id, &CancelMode::AtPeriodEnd);
+
+    // At the period end a renewal attempt cancels instead of charging
+    let period_end = client.get_lifecycle(&sub_id).cancel_at;
+    env.ledger().with_mut(|li| li.timestamp = period_end);
+    client.approve_renewal(&sub_id, &2, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(!client.renew(&agent, &sub_id, &2, &1000, &2));
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Cancelled);
+    assert_eq!(client.get_lifecycle(&sub_id).canceled_at, period_end);
+    assert_eq!(token_client.balance(&merchant), 1000);
+}
+
+#[test]
+#[should_panic(expected = "Subscription cancels at period end")]
+fn test_renew_rejected_once_cancellation_is_scheduled() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 2000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &1000, &1);
+    client.cancel_sub(&sub_id, &CancelMode::AtPeriodEnd);
+
+    client.approve_renewal(&sub_id, &2, &1000, &100);
+    client.renew(&agent, &sub_id, &2, &1000, &2);
+}
+
+#[test]
+fn test_cancel_at_period_end_without_paid_period_cancels_now() {
+    let (env, id, _admin, token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
-    client.cancel_sub(&999);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+
+    // The first period is charged in advance, so nothing has been paid for yet
+    client.cancel_sub(&sub_id, &CancelMode::AtPeriodEnd);
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Cancelled);
+    assert_eq!(client.get_lifecycle(&sub_id).canceled_at, 1_000);
 }
 
 #[test]
 #[should_panic(expected = "Per-subscription spending cap exceeded")]
 fn test_per_subscription_spending_cap() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let user = Address::generate(&env);
     let merchant = Address::generate(&env);
-    let sub_id = 700;
 
     // Cap is 1000
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &1, &2000, &100);
 
     // Try to renew with 1500 (exceeds 1000 cap)
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &1, &1500, &3, &10, &20270101, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &1500, &20270101);
 }
 
 #[test]
 #[should_panic(expected = "Global user spending cap exceeded")]
 fn test_global_user_spending_cap() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let user = Address::generate(&env);
     let merchant = Address::generate(&env);
 
     // Set global cap for user to 2000
-    client.set_user_cap(&user, &2000);
+    client.set_user_cap(&user, &2000, &0);
 
-    let sub_id_1 = 701;
-    let sub_id_2 = 702;
 
-    client.init_sub(&user, &merchant, &1500, &86400, &5000, &sub_id_1);
-    client.init_sub(&user, &merchant, &1000, &86400, &5000, &sub_id_2);
+    let sub_id_1 = client.init_sub(&user, &merchant, &token, &1500, &86400, &5000, &salt(&env));
+    let sub_id_2 = client.init_sub(&user, &merchant, &token, &1000, &86400, &5000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     client.approve_renewal(&sub_id_1, &1, &2000, &100);
     client.approve_renewal(&sub_id_2, &1, &2000, &100);
 
     // First renewal: 1500. Total spent: 1500 / 2000
-    client.acquire_renewal_lock(&sub_id_1, &200);
-    client.renew(&sub_id_1, &1, &1500, &3, &10, &20260101, &true);
+    client.acquire_renewal_lock(&agent, &sub_id_1, &200);
+    client.renew(&agent, &sub_id_1, &1, &1500, &20260101);
 
     // Second renewal: 1000. Total would be 2500 / 2000 -> Should panic
-    client.acquire_renewal_lock(&sub_id_2, &200);
-    client.renew(&sub_id_2, &1, &1000, &3, &10, &20260101, &true);
+    client.acquire_renewal_lock(&agent, &sub_id_2, &200);
+    client.renew(&agent, &sub_id_2, &1, &1000, &20260101);
 }
 
 // ── Renewal lock tests ──────────────────────────────────────────
 
 #[test]
 fn test_acquire_renewal_lock() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, _token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let sub_id = 700;
 
-    client.acquire_renewal_lock(&sub_id, &200);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
 
     let lock = client.get_renewal_lock(&sub_id);
     assert!(lock.is_some());
@@ -689,24 +936,24 @@ fn test_acquire_renewal_lock() {
 #[test]
 #[should_panic(expected = "Renewal lock active")]
 fn test_lock_prevents_concurrent_acquisition() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, _token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let sub_id = 701;
 
-    client.acquire_renewal_lock(&sub_id, &200);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
     // Second acquire should panic
-    client.acquire_renewal_lock(&sub_id, &200);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
 }
 
 #[test]
 fn test_lock_auto_expires_and_reacquirable() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, _token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let sub_id = 702;
 
-    client.acquire_renewal_lock(&sub_id, &50);
+    client.acquire_renewal_lock(&agent, &sub_id, &50);
 
     // Advance ledger past lock timeout
     env.ledger().with_mut(|li| {
@@ -714,7 +961,7 @@ fn test_lock_auto_expires_and_reacquirable() {
     });
 
     // Should succeed — old lock expired
-    client.acquire_renewal_lock(&sub_id, &200);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
 
     let lock = client.get_renewal_lock(&sub_id);
     assert!(lock.is_some());
@@ -725,12 +972,12 @@ fn test_lock_auto_expires_and_reacquirable() {
 
 #[test]
 fn test_release_renewal_lock() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, _token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let sub_id = 703;
 
-    client.acquire_renewal_lock(&sub_id, &200);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
     assert!(client.get_renewal_lock(&sub_id).is_some());
 
     client.release_renewal_lock(&sub_id);
@@ -740,46 +987,101 @@ fn test_release_renewal_lock() {
 #[test]
 #[should_panic(expected = "No renewal lock to release")]
 fn test_release_nonexistent_lock_panics() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, _token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let sub_id = 704;
     client.release_renewal_lock(&sub_id);
 }
 
+#[test]
+fn test_lock_records_holder() {
+    let (env, id, _admin, _token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+
+    client.acquire_renewal_lock(&agent, &705, &200);
+    assert_eq!(client.get_renewal_lock(&705).unwrap().holder, agent);
+}
+
+#[test]
+#[should_panic(expected = "Agent missing Renewals scope")]
+fn test_acquire_lock_requires_renewals_scope() {
+    let (env, id, _admin, _token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+
+    client.acquire_renewal_lock(&Address::generate(&env), &706, &200);
+}
+
+#[test]
+#[should_panic(expected = "Invalid lock timeout")]
+fn test_acquire_lock_rejects_unbounded_timeout() {
+    let (env, id, _admin, _token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+
+    // A lock that never expires would block `process_due` for the subscription
+    client.acquire_renewal_lock(&agent, &706, &u32::MAX);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_release_lock_requires_holder_auth() {
+    let (env, id, _admin, _token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+
+    client.acquire_renewal_lock(&agent, &707, &200);
+    env.set_auths(&[]);
+    client.release_renewal_lock(&707);
+}
+
+#[test]
+#[should_panic(expected = "Caller is not the lock holder")]
+fn test_renew_by_non_holder_panics() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&Address::generate(&env), &sub_id, &1, &500, &20260101);
+}
+
 #[test]
 #[should_panic(expected = "Renewal lock required")]
 fn test_renew_without_lock_panics() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 705;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &1, &1000, &100);
 
     // Renew without acquiring lock — should panic
-    client.renew(&sub_id, &1, &500, &3, &10, &20260101, &true);
+    client.renew(&agent, &sub_id, &1, &500, &20260101);
 }
 
 #[test]
 fn test_renew_with_lock_succeeds_and_auto_releases() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 706;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &1, &1000, &100);
 
-    client.acquire_renewal_lock(&sub_id, &200);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
     assert!(client.get_renewal_lock(&sub_id).is_some());
 
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &20260101, &true);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260101);
     assert!(result);
 
     // Lock should be auto-released after renew
@@ -788,20 +1090,19 @@ fn test_renew_with_lock_succeeds_and_auto_releases() {
 
 #[test]
 fn test_renew_failure_also_releases_lock() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 707;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
     client.approve_renewal(&sub_id, &1, &1000, &200);
 
-    client.acquire_renewal_lock(&sub_id, &200);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
     assert!(client.get_renewal_lock(&sub_id).is_some());
 
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &20260101, &false);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260101);
     assert!(!result);
 
     // Lock should be auto-released even after failure
@@ -811,17 +1112,17 @@ fn test_renew_failure_also_releases_lock() {
 #[test]
 #[should_panic(expected = "Renewal lock expired")]
 fn test_renew_with_expired_lock_panics() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let user = Address::generate(&env);
-    let sub_id = 708;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &1, &1000, &200);
 
-    client.acquire_renewal_lock(&sub_id, &50);
+    client.acquire_renewal_lock(&agent, &sub_id, &50);
 
     // Advance ledger past lock timeout
     env.ledger().with_mut(|li| {
@@ -829,27 +1130,27 @@ fn test_renew_with_expired_lock_panics() {
     });
 
     // Renew with expired lock — should panic
-    client.renew(&sub_id, &1, &500, &3, &10, &20260101, &true);
+    client.renew(&agent, &sub_id, &1, &500, &20260101);
 }
 
 #[test]
 #[should_panic(expected = "Protocol is paused")]
 fn test_acquire_lock_blocked_when_paused() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, _token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     let sub_id = 709;
 
     client.set_paused(&true);
     // Should panic because protocol is paused
-    client.acquire_renewal_lock(&sub_id, &200);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
 }
 
 // ── Lifecycle timestamp tests ─────────────────────────────────────
 
 #[test]
 fn test_lifecycle_created_on_init() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     env.ledger().with_mut(|li| {
@@ -857,10 +1158,9 @@ fn test_lifecycle_created_on_init() {
     });
 
     let user = Address::generate(&env);
-    let sub_id = 800;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
     let lc = client.get_lifecycle(&sub_id);
     assert_eq!(lc.created_at, 1700000000);
@@ -871,7 +1171,7 @@ fn test_lifecycle_created_on_init() {
 
 #[test]
 fn test_lifecycle_renewed_at_updated_on_success() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     env.ledger().with_mut(|li| {
@@ -879,18 +1179,18 @@ fn test_lifecycle_renewed_at_updated_on_success() {
     });
 
     let user = Address::generate(&env);
-    let sub_id = 801;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     env.ledger().with_mut(|li| {
         li.timestamp = 1700100000;
     });
 
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &1, &500, &3, &10, &20260101, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &500, &20260101);
 
     let lc = client.get_lifecycle(&sub_id);
     assert_eq!(lc.created_at, 1700000000);
@@ -901,7 +1201,7 @@ fn test_lifecycle_renewed_at_updated_on_success() {
 
 #[test]
 fn test_lifecycle_canceled_at_set_on_cancel() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     env.ledger().with_mut(|li| {
@@ -909,16 +1209,15 @@ fn test_lifecycle_canceled_at_set_on_cancel() {
     });
 
     let user = Address::generate(&env);
-    let sub_id = 802;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
     env.ledger().with_mut(|li| {
         li.timestamp = 1700200000;
     });
 
-    client.cancel_sub(&sub_id);
+    client.cancel_sub(&sub_id, &CancelMode::Immediate);
 
     let lc = client.get_lifecycle(&sub_id);
     assert_eq!(lc.created_at, 1700000000);
@@ -927,7 +1226,7 @@ fn test_lifecycle_canceled_at_set_on_cancel() {
 
 #[test]
 fn test_lifecycle_activated_at_updated_on_recovery_from_retrying() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     env.ledger().with_mut(|li| {
@@ -935,18 +1234,17 @@ fn test_lifecycle_activated_at_updated_on_recovery_from_retrying() {
     });
 
     let user = Address::generate(&env);
-    let sub_id = 803;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
     // Fail once to enter Retrying
     env.ledger().with_mut(|li| {
         li.timestamp = 1700100000;
     });
     client.approve_renewal(&sub_id, &1, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &1, &500, &3, &10, &20260201, &false);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &500, &20260201);
 
     let data = client.get_sub(&sub_id);
     assert_eq!(data.state, SubscriptionState::Retrying);
@@ -956,9 +1254,10 @@ fn test_lifecycle_activated_at_updated_on_recovery_from_retrying() {
         li.sequence_number = 20;
         li.timestamp = 1700200000;
     });
+    fund(&env, &token, &id, &user, 10_000);
     client.approve_renewal(&sub_id, &2, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &2, &500, &3, &10, &20260201, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &2, &500, &20260201);
 
     let lc = client.get_lifecycle(&sub_id);
     assert_eq!(lc.created_at, 1700000000);
@@ -969,7 +1268,7 @@ fn test_lifecycle_activated_at_updated_on_recovery_from_retrying() {
 
 #[test]
 fn test_lifecycle_not_updated_on_renewal_failure() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     env.ledger().with_mut(|li| {
@@ -977,17 +1276,16 @@ fn test_lifecycle_not_updated_on_renewal_failure() {
     });
 
     let user = Address::generate(&env);
-    let sub_id = 804;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
     env.ledger().with_mut(|li| {
         li.timestamp = 1700100000;
     });
     client.approve_renewal(&sub_id, &1, &1000, &200);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &1, &500, &3, &10, &20260301, &false);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &500, &20260301);
 
     let lc = client.get_lifecycle(&sub_id);
     assert_eq!(lc.last_renewed_at, 0); // unchanged on failure
@@ -996,7 +1294,7 @@ fn test_lifecycle_not_updated_on_renewal_failure() {
 
 #[test]
 fn test_lifecycle_multiple_renewals_update_last_renewed() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
 
     env.ledger().with_mut(|li| {
@@ -1004,18 +1302,18 @@ fn test_lifecycle_multiple_renewals_update_last_renewed() {
     });
 
     let user = Address::generate(&env);
-    let sub_id = 805;
 
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // First renewal
     env.ledger().with_mut(|li| {
         li.timestamp = 1700100000;
     });
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &1, &500, &3, &10, &20260401, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &500, &20260401);
 
     let lc = client.get_lifecycle(&sub_id);
     assert_eq!(lc.last_renewed_at, 1700100000);
@@ -1025,8 +1323,8 @@ fn test_lifecycle_multiple_renewals_update_last_renewed() {
         li.timestamp = 1700200000;
     });
     client.approve_renewal(&sub_id, &2, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &2, &500, &3, &10, &20260501, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &2, &500, &20260501);
 
     let lc = client.get_lifecycle(&sub_id);
     assert_eq!(lc.last_renewed_at, 1700200000);
@@ -1036,7 +1334,7 @@ fn test_lifecycle_multiple_renewals_update_last_renewed() {
 #[test]
 #[should_panic(expected = "Lifecycle data not found")]
 fn test_get_lifecycle_nonexistent_sub() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, _token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     client.get_lifecycle(&999);
 }
@@ -1047,12 +1345,11 @@ fn test_get_lifecycle_nonexistent_sub() {
 #[test]
 #[should_panic(expected = "Invalid window: start must be before end")]
 fn test_window_start_must_be_before_end() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let user = Address::generate(&env);
-    let sub_id = 900u64;
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
     // start == end — should panic
     client.set_window(&sub_id, &1735689600u64, &1735689600u64);
 }
@@ -1061,12 +1358,11 @@ fn test_window_start_must_be_before_end() {
 #[test]
 #[should_panic(expected = "Invalid window: start must be before end")]
 fn test_window_start_after_end_rejected() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let user = Address::generate(&env);
-    let sub_id = 901u64;
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
     // start > end — should panic
     client.set_window(&sub_id, &1735862400u64, &1735689600u64);
 }
@@ -1074,12 +1370,12 @@ fn test_window_start_after_end_rejected() {
 /// I-W2: renew() succeeds when current timestamp is within the window.
 #[test]
 fn test_renew_within_window_succeeds() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let user = Address::generate(&env);
-    let sub_id = 902u64;
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // Window: [1000, 2000]
     client.set_window(&sub_id, &1000u64, &2000u64);
@@ -1090,8 +1386,8 @@ fn test_renew_within_window_succeeds() {
     });
 
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &20260101u64, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260101u64);
     assert!(result);
 }
 
@@ -1099,12 +1395,12 @@ fn test_renew_within_window_succeeds() {
 #[test]
 #[should_panic(expected = "Outside renewal window")]
 fn test_renew_before_window_panics() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let user = Address::generate(&env);
-    let sub_id = 903u64;
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // Window: [1000, 2000]
     client.set_window(&sub_id, &1000u64, &2000u64);
@@ -1115,20 +1411,20 @@ fn test_renew_before_window_panics() {
     });
 
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &1, &500, &3, &10, &20260101u64, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &500, &20260101u64);
 }
 
 /// I-W2: renew() panics when current timestamp is after billing_end.
 #[test]
 #[should_panic(expected = "Outside renewal window")]
 fn test_renew_after_window_panics() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let user = Address::generate(&env);
-    let sub_id = 904u64;
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // Window: [1000, 2000]
     client.set_window(&sub_id, &1000u64, &2000u64);
@@ -1139,19 +1435,19 @@ fn test_renew_after_window_panics() {
     });
 
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    client.renew(&sub_id, &1, &500, &3, &10, &20260101u64, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &500, &20260101u64);
 }
 
 /// I-W3: renew() succeeds with no time restriction when no window is set.
 #[test]
 fn test_renew_without_window_has_no_time_restriction() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let user = Address::generate(&env);
-    let sub_id = 905u64;
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // No set_window call — window is optional
     env.ledger().with_mut(|li| {
@@ -1159,20 +1455,19 @@ fn test_renew_without_window_has_no_time_restriction() {
     });
 
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.renew(&sub_id, &1, &500, &3, &10, &20260101u64, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.renew(&agent, &sub_id, &1, &500, &20260101u64);
     assert!(result);
 }
 
 /// I-W4: Only the subscription owner can set the window.
 #[test]
 fn test_set_window_owner_only() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, _agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let user = Address::generate(&env);
-    let sub_id = 906u64;
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
 
     // Owner sets window — should succeed
     client.set_window(&sub_id, &1000u64, &2000u64);
@@ -1187,12 +1482,12 @@ fn test_set_window_owner_only() {
 /// I-W5: renew() fails outside the window and succeeds when moved inside.
 #[test]
 fn test_approval_consumed_before_window_check() {
-    let (env, id, _admin) = setup();
+    let (env, id, _admin, token, agent) = setup();
     let client = SubscriptionRenewalContractClient::new(&env, &id);
     let user = Address::generate(&env);
-    let sub_id = 907u64;
     let merchant = Address::generate(&env);
-    client.init_sub(&user, &merchant, &500, &86400, &1000, &sub_id);
+    let sub_id = client.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt(&env));
+    fund(&env, &token, &id, &user, 10_000);
 
     // Window: [1000, 2000]
     client.set_window(&sub_id, &1000u64, &2000u64);
@@ -1202,8 +1497,8 @@ fn test_approval_consumed_before_window_check() {
         li.timestamp = 500;
     });
     client.approve_renewal(&sub_id, &1, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result = client.try_renew(&sub_id, &1, &500, &3, &10, &20260101u64, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result = client.try_renew(&agent, &sub_id, &1, &500, &20260101u64);
     assert!(result.is_err());
 
     // Release the lock (held from before the failed renew) and move inside the window
@@ -1213,7 +1508,1568 @@ fn test_approval_consumed_before_window_check() {
     });
     // Renew with a fresh approval inside the window — should succeed
     client.approve_renewal(&sub_id, &2, &1000, &100);
-    client.acquire_renewal_lock(&sub_id, &200);
-    let result2 = client.renew(&sub_id, &2, &500, &3, &10, &20260102u64, &true);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    let result2 = client.renew(&agent, &sub_id, &2, &500, &20260102u64);
     assert!(result2);
-}
\ No newline at end of file
+}
+// ── Payment adapter routing tests ────────────────────────────────
+
+fn setup_adapter(env: &Env, id: &Address, admin: &Address, token: &Address, cap: i128) -> Address {
+    let adapter_id = env.register(PaymentAdapterContract, ());
+    let adapter = PaymentAdapterContractClient::new(env, &adapter_id);
+    adapter.init(admin);
+    adapter.allow_token(token, &cap);
+    adapter.set_caller(id, &true);
+    adapter_id
+}
+
+#[test]
+fn test_renewal_settles_through_payment_adapter() {
+    let (env, id, admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+    let adapter_id = setup_adapter(&env, &id, &admin, &token, 10);
+    client.set_payment_adapter(&adapter_id);
+    assert_eq!(client.get_payment_adapter(), Some(adapter_id.clone()));
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let factor = 10i128.pow(token_client.decimals());
+
+    // Amount is in adapter display units; the adapter is the token spender
+    let sub_id = client.init_sub(&user, &merchant, &token, &4, &86400, &0, &salt(&env));
+    fund(&env, &token, &adapter_id, &user, 10 * factor);
+    client.approve_renewal(&sub_id, &1, &4, &100);
+
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &4, &20260101));
+
+    assert_eq!(token_client.balance(&merchant), 4 * factor);
+    let adapter = PaymentAdapterContractClient::new(&env, &adapter_id);
+    assert_eq!(adapter.available(&token), 6);
+}
+
+#[test]
+fn test_cancel_refund_through_adapter_is_in_token_units() {
+    let (env, id, admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let adapter_id = setup_adapter(&env, &id, &admin, &token, 10);
+    client.set_payment_adapter(&adapter_id);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let factor = 10i128.pow(TokenClient::new(&env, &token).decimals());
+
+    let sub_id = client.init_sub(&user, &merchant, &token, &4, &86400, &0, &salt(&env));
+    fund(&env, &token, &adapter_id, &user, 10 * factor);
+    client.approve_renewal(&sub_id, &1, &4, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &4, &20260101));
+
+    // Three quarters of the 4 display units the merchant received in raw units
+    env.ledger().with_mut(|li| li.timestamp += 21600);
+    client.cancel_sub(&sub_id, &CancelMode::Immediate);
+    assert_eq!(client.get_pending_refund(&sub_id), 3 * factor);
+}
+
+#[test]
+fn test_adapter_cap_exceeded_fails_renewal() {
+    let (env, id, admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let adapter_id = setup_adapter(&env, &id, &admin, &token, 3);
+    client.set_payment_adapter(&adapter_id);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let factor = 10i128.pow(TokenClient::new(&env, &token).decimals());
+
+    let sub_id = client.init_sub(&user, &merchant, &token, &4, &86400, &0, &salt(&env));
+    fund(&env, &token, &adapter_id, &user, 10 * factor);
+    client.approve_renewal(&sub_id, &1, &4, &100);
+
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(!client.renew(&agent, &sub_id, &1, &4, &20260101));
+
+    let data = client.get_sub(&sub_id);
+    assert_eq!(data.state, SubscriptionState::Retrying);
+    assert_eq!(data.failure_count, 1);
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 0);
+}
+
+#[test]
+fn test_revoked_token_stops_renewals() {
+    let (env, id, admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let adapter_id = setup_adapter(&env, &id, &admin, &token, 100);
+    client.set_payment_adapter(&adapter_id);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let factor = 10i128.pow(TokenClient::new(&env, &token).decimals());
+
+    let sub_id = client.init_sub(&user, &merchant, &token, &4, &86400, &0, &salt(&env));
+    fund(&env, &token, &adapter_id, &user, 10 * factor);
+
+    client.approve_renewal(&sub_id, &1, &4, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &4, &20260101));
+
+    PaymentAdapterContractClient::new(&env, &adapter_id).revoke_token(&token);
+
+    client.approve_renewal(&sub_id, &2, &4, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(!client.renew(&agent, &sub_id, &2, &4, &20260201));
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Retrying);
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 4 * factor);
+}
+
+// ── Fiat-quoted (FX oracle) renewal tests ────────────────────────
+
+/// Registers an FX oracle quoting 1 USD = `rate` XLM (8 decimals) and a fiat-priced sub.
+/// Returns (oracle, oracle signer, merchant, sub id).
+fn setup_fx_sub(
+    env: &Env,
+    id: &Address,
+    admin: &Address,
+    token: &Address,
+    rate: i128,
+) -> (Address, Address, Address, u64) {
+    let client = SubscriptionRenewalContractClient::new(env, id);
+    let oracle_id = env.register(FxOracleContract, ());
+    let oracle = FxOracleContractClient::new(env, &oracle_id);
+    oracle.init(admin);
+    let signer = Address::generate(env);
+    oracle.add_signer(&signer);
+    let usd = String::from_str(env, "USD");
+    let xlm = String::from_str(env, "XLM");
+    oracle.update_rate(&usd, &xlm, &rate, &env.ledger().timestamp(), &signer);
+    client.set_fx_oracle(&oracle_id);
+
+    let user = Address::generate(env);
+    let merchant = Address::generate(env);
+    let sub_id = client.init_sub(&user, &merchant, token, &100, &86400, &0, &salt(env));
+    client.set_fx_pricing(&sub_id, &usd, &xlm, &500);
+    fund(env, token, id, &user, 10_000);
+    (oracle_id, signer, merchant, sub_id)
+}
+
+#[test]
+fn test_fiat_sub_charges_converted_token_amount() {
+    let (env, id, admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (oracle_id, _signer, merchant, sub_id) =
+        setup_fx_sub(&env, &id, &admin, &token, 250_000_000);
+    assert_eq!(client.get_fx_oracle(), Some(oracle_id));
+
+    let pricing = client.get_fx_pricing(&sub_id).unwrap();
+    assert_eq!(pricing.reference_rate, 250_000_000);
+    assert_eq!(pricing.max_slippage_bps, 500);
+
+    // 100 USD at 2.5 XLM/USD settles 250 token units
+    client.approve_renewal(&sub_id, &1, &100, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &100, &20260101));
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 250);
+}
+
+#[test]
+fn test_fiat_sub_fails_on_paused_oracle() {
+    let (env, id, admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (oracle_id, _signer, merchant, sub_id) =
+        setup_fx_sub(&env, &id, &admin, &token, 250_000_000);
+    FxOracleContractClient::new(&env, &oracle_id).set_paused(&true);
+
+    client.approve_renewal(&sub_id, &1, &100, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(!client.renew(&agent, &sub_id, &1, &100, &20260101));
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Retrying);
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 0);
+}
+
+#[test]
+fn test_fiat_sub_fails_on_stale_rate() {
+    let (env, id, admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (_oracle_id, _signer, merchant, sub_id) =
+        setup_fx_sub(&env, &id, &admin, &token, 250_000_000);
+
+    // Past the oracle's default one-hour staleness bound
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.approve_renewal(&sub_id, &1, &100, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(!client.renew(&agent, &sub_id, &1, &100, &20260101));
+    assert_eq!(client.get_sub(&sub_id).failure_count, 1);
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 0);
+}
+
+#[test]
+fn test_fiat_sub_fails_beyond_slippage_bound() {
+    let (env, id, admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (oracle_id, signer, merchant, sub_id) =
+        setup_fx_sub(&env, &id, &admin, &token, 250_000_000);
+    let oracle = FxOracleContractClient::new(&env, &oracle_id);
+    let usd = String::from_str(&env, "USD");
+    let xlm = String::from_str(&env, "XLM");
+
+    // +10% move exceeds the 5% bound
+    oracle.update_rate(&usd, &xlm, &275_000_000, &env.ledger().timestamp(), &signer);
+    client.approve_renewal(&sub_id, &1, &100, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(!client.renew(&agent, &sub_id, &1, &100, &20260101));
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 0);
+
+    // +4% move is within the bound (retry after the cooldown)
+    env.ledger().with_mut(|li| li.sequence_number += 20);
+    oracle.update_rate(&usd, &xlm, &260_000_000, &env.ledger().timestamp(), &signer);
+    client.approve_renewal(&sub_id, &2, &100, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &2, &100, &20260102));
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 260);
+}
+
+#[test]
+#[should_panic(expected = "Governance not set")]
+fn test_upgrade_requires_governance() {
+    let (env, id, _admin, _token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    client.upgrade(&BytesN::from_array(&env, &[1u8; 32]));
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_upgrade_rejects_non_governance_caller() {
+    let (env, id, _admin, _token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let governance = Address::generate(&env);
+    client.set_governance(&governance);
+    assert_eq!(client.get_governance(), Some(governance));
+
+    // Without the governance contract's authorization the call is rejected
+    env.set_auths(&[]);
+    client.upgrade(&BytesN::from_array(&env, &[1u8; 32]));
+}
+
+/// Authorize only `signer` for `set_governance(governance)` on `id`.
+fn mock_set_governance(env: &Env, id: &Address, signer: &Address, governance: &Address) {
+    env.mock_auths(&[MockAuth {
+        address: signer,
+        invoke: &MockAuthInvoke {
+            contract: id,
+            fn_name: "set_governance",
+            args: (governance.clone(),).into_val(env),
+            sub_invokes: &[],
+        },
+    }]);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_admin_cannot_replace_governance() {
+    let (env, id, admin, _token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    client.set_governance(&Address::generate(&env));
+
+    // Pointing governance at the admin would let it skip the guardian timelock
+    mock_set_governance(&env, &id, &admin, &admin);
+    client.set_governance(&admin);
+}
+
+#[test]
+fn test_governance_hands_over_to_new_governance() {
+    let (env, id, _admin, _token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let governance = Address::generate(&env);
+    client.set_governance(&governance);
+
+    let next = Address::generate(&env);
+    mock_set_governance(&env, &id, &governance, &next);
+    client.set_governance(&next);
+    assert_eq!(client.get_governance(), Some(next));
+}
+
+// ── Team multi-sig ───────────────────────────────────────────────
+
+/// Team sub (team 7, threshold 1000) with a funded owner, an approval and a lock.
+/// Returns (sub_id, signer).
+fn setup_team_sub(env: &Env, id: &Address, token: &Address, agent: &Address) -> (u64, Address) {
+    let client = SubscriptionRenewalContractClient::new(env, id);
+    let owner = Address::generate(env);
+    let merchant = Address::generate(env);
+    let sub_id = client.init_sub(&owner, &merchant, token, &5000, &86400, &0, &salt(env));
+    client.assign_team(&sub_id, &7);
+    client.set_team_threshold(&7, &1000);
+    let signer = Address::generate(env);
+    client.set_team_signers(&7, &vec![env, signer.clone()], &1);
+    fund(env, token, id, &owner, 10_000);
+    client.approve_renewal(&sub_id, &1, &5000, &100);
+    client.acquire_renewal_lock(agent, &sub_id, &200);
+    (sub_id, signer)
+}
+
+#[test]
+#[should_panic(expected = "Multi-sig approval required")]
+fn test_team_renewal_above_threshold_requires_multisig() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, _signer) = setup_team_sub(&env, &id, &token, &agent);
+    client.renew(&agent, &sub_id, &1, &5000, &20260101);
+}
+
+#[test]
+fn test_team_renewal_consumes_approved_multisig() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, signer) = setup_team_sub(&env, &id, &token, &agent);
+    let cycle_id = 20260101;
+    let requester = signer.clone();
+
+    let signers = vec![&env, signer.clone()];
+    client.request_multisig_renewal(&sub_id, &cycle_id, &7, &5000, &requester, &signers);
+    client.sign_multisig_renewal(&sub_id, &cycle_id, &signer);
+    assert!(client.renew(&agent, &sub_id, &1, &5000, &cycle_id));
+
+    let request = client.get_multisig_request(&sub_id, &cycle_id);
+    assert_eq!(request.status, MultiSigStatus::Consumed);
+}
+
+#[test]
+#[should_panic(expected = "Multi-sig request is not approved")]
+fn test_team_renewal_rejects_cancelled_multisig() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, signer) = setup_team_sub(&env, &id, &token, &agent);
+    let cycle_id = 20260101;
+
+    let requester = signer.clone();
+    let signers = vec![&env, signer];
+    client.request_multisig_renewal(&sub_id, &cycle_id, &7, &5000, &requester, &signers);
+    client.cancel_multisig_renewal(&sub_id, &cycle_id);
+    client.renew(&agent, &sub_id, &1, &5000, &cycle_id);
+}
+
+#[test]
+#[should_panic(expected = "Multi-sig request has expired")]
+fn test_team_renewal_rejects_expired_multisig() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, signer) = setup_team_sub(&env, &id, &token, &agent);
+    let cycle_id = 20260101;
+
+    let requester = signer.clone();
+    let signers = vec![&env, signer.clone()];
+    client.request_multisig_renewal(&sub_id, &cycle_id, &7, &5000, &requester, &signers);
+    client.sign_multisig_renewal(&sub_id, &cycle_id, &signer);
+    env.ledger().with_mut(|li| li.timestamp += 86_400);
+    client.renew(&agent, &sub_id, &1, &5000, &cycle_id);
+}
+
+#[test]
+#[should_panic(expected = "Signers do not match team policy")]
+fn test_multisig_request_rejects_self_nominated_signer() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, _signer) = setup_team_sub(&env, &id, &token, &agent);
+
+    let requester = Address::generate(&env);
+    let signers = vec![&env, requester.clone()];
+    client.request_multisig_renewal(&sub_id, &20260101, &7, &5000, &requester, &signers);
+}
+
+#[test]
+#[should_panic(expected = "Subscription does not belong to this team")]
+fn test_multisig_request_rejects_other_team() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, _signer) = setup_team_sub(&env, &id, &token, &agent);
+
+    let requester = Address::generate(&env);
+    let signers = vec![&env, requester.clone()];
+    client.set_team_signers(&8, &signers, &1);
+    client.request_multisig_renewal(&sub_id, &20260101, &8, &5000, &requester, &signers);
+}
+
+#[test]
+fn test_multisig_request_approves_at_team_quorum() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, _signer) = setup_team_sub(&env, &id, &token, &agent);
+    let cycle_id = 20260101;
+
+    let signers = vec![
+        &env,
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    ];
+    client.set_team_signers(&7, &signers, &2);
+    let requester = signers.get(1).unwrap();
+    client.request_multisig_renewal(&sub_id, &cycle_id, &7, &5000, &requester, &signers);
+
+    client.sign_multisig_renewal(&sub_id, &cycle_id, &signers.get(0).unwrap());
+    let request = client.get_multisig_request(&sub_id, &cycle_id);
+    assert_eq!(request.status, MultiSigStatus::Pending);
+    client.sign_multisig_renewal(&sub_id, &cycle_id, &signers.get(2).unwrap());
+    assert!(client.renew(&agent, &sub_id, &1, &5000, &cycle_id));
+}
+
+#[test]
+#[should_panic(expected = "Quorum must be between 1 and the number of signers")]
+fn test_set_team_signers_rejects_quorum_above_signer_count() {
+    let (env, id, _admin, _token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    client.set_team_signers(&7, &vec![&env, Address::generate(&env)], &2);
+}
+
+#[test]
+#[should_panic(expected = "Requester cannot open multi-sig requests")]
+fn test_multisig_request_rejects_unrelated_requester() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, signer) = setup_team_sub(&env, &id, &token, &agent);
+
+    let squatter = Address::generate(&env);
+    let signers = vec![&env, signer];
+    client.request_multisig_renewal(&sub_id, &20260101, &7, &5000, &squatter, &signers);
+}
+
+#[test]
+fn test_multisig_request_replaces_cancelled_request() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, signer) = setup_team_sub(&env, &id, &token, &agent);
+    let cycle_id = 20260101;
+    let signers = vec![&env, signer.clone()];
+
+    client.request_multisig_renewal(&sub_id, &cycle_id, &7, &5000, &signer, &signers);
+    client.cancel_multisig_renewal(&sub_id, &cycle_id);
+    client.request_multisig_renewal(&sub_id, &cycle_id, &7, &5000, &signer, &signers);
+    assert_eq!(
+        client.get_multisig_request(&sub_id, &cycle_id).status,
+        MultiSigStatus::Pending
+    );
+
+    client.sign_multisig_renewal(&sub_id, &cycle_id, &signer);
+    assert!(client.renew(&agent, &sub_id, &1, &5000, &cycle_id));
+}
+
+#[test]
+fn test_multisig_request_replaces_lapsed_request() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, signer) = setup_team_sub(&env, &id, &token, &agent);
+    let cycle_id = 20260101;
+    let signers = vec![&env, signer.clone()];
+    let owner = client.get_sub(&sub_id).owner;
+
+    // Never signed within its window
+    client.request_multisig_renewal(&sub_id, &cycle_id, &7, &5000, &owner, &signers);
+    env.ledger().with_mut(|li| li.timestamp += 86_400);
+    client.request_multisig_renewal(&sub_id, &cycle_id, &7, &5000, &owner, &signers);
+
+    client.sign_multisig_renewal(&sub_id, &cycle_id, &signer);
+    assert!(client.renew(&agent, &sub_id, &1, &5000, &cycle_id));
+}
+
+#[test]
+#[should_panic(expected = "Multi-sig request already exists")]
+fn test_multisig_request_cannot_replace_live_request() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, signer) = setup_team_sub(&env, &id, &token, &agent);
+    let signers = vec![&env, signer.clone()];
+
+    client.request_multisig_renewal(&sub_id, &20260101, &7, &5000, &signer, &signers);
+    client.request_multisig_renewal(&sub_id, &20260101, &7, &5000, &signer, &signers);
+}
+
+#[test]
+fn test_team_renewal_below_threshold_needs_no_multisig() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let (sub_id, _signer) = setup_team_sub(&env, &id, &token, &agent);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &20260101));
+}
+
+// ── Keeper batch processing ──────────────────────────────────────
+
+#[test]
+fn test_process_due_renews_due_subs_and_pays_bounty() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &10_000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+    let batch = vec![&env, sub_id];
+
+    // The first period is charged in advance, at creation
+    assert_eq!(client.next_due_at(&sub_id), env.ledger().timestamp());
+    assert_eq!(client.process_due(&keeper, &batch), 1);
+    // Default bounty is 10 bps of the charge
+    assert_eq!(token_client.balance(&merchant), 9_990);
+    assert_eq!(token_client.balance(&keeper), 10);
+
+    // Renewed for this period: a second keeper call does nothing
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+    assert_eq!(client.next_due_at(&sub_id), env.ledger().timestamp() + 86400);
+
+    // Next period uses the next contract-computed cycle
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    assert_eq!(client.process_due(&keeper, &batch), 1);
+    assert_eq!(token_client.balance(&merchant), 19_980);
+}
+
+#[test]
+fn test_process_due_skips_locked_cancelled_and_unknown_subs() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    fund(&env, &token, &id, &owner, 100_000);
+    for _ in 0..3 {
+        client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    }
+    client.acquire_renewal_lock(&agent, &1, &200);
+    client.cancel_sub(&2, &CancelMode::Immediate);
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+
+    assert_eq!(client.process_due(&keeper, &vec![&env, 1, 2, 3, 99]), 1);
+    assert_eq!(client.get_lifecycle(&1).last_renewed_at, 0);
+    assert_eq!(client.get_lifecycle(&3).last_renewed_at, env.ledger().timestamp());
+}
+
+#[test]
+fn test_process_due_failed_charge_retries_after_cooldown() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    let batch = vec![&env, sub_id];
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+
+    // No allowance yet: the charge fails and the sub moves to Retrying
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Retrying);
+
+    // Cooling down even once funded
+    fund(&env, &token, &id, &owner, 10_000);
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+
+    env.ledger().with_mut(|li| li.sequence_number += 720);
+    assert_eq!(client.process_due(&keeper, &batch), 1);
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Active);
+}
+
+#[test]
+#[should_panic(expected = "Keeper bounty too high")]
+fn test_keeper_bounty_is_bounded() {
+    let (env, id, _admin, _token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    client.set_keeper_bounty(&501);
+}
+
+// ── Plan changes ─────────────────────────────────────────────────
+
+#[test]
+fn test_change_plan_upgrade_charges_prorated_difference() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &20260101));
+
+    // A quarter of the period left: a quarter of the 2000 difference is due now
+    env.ledger().with_mut(|li| li.timestamp += 64800);
+    client.change_plan(&sub_id, &3000, &86400);
+    assert_eq!(token_client.balance(&merchant), 1500);
+    assert_eq!(client.get_user_spent(&owner), 1500);
+    assert_eq!(client.get_sub(&sub_id).amount, 3000);
+    assert_eq!(client.get_pending_plan(&sub_id), None);
+
+    // The recomputed integrity hash lets the new amount renew
+    client.approve_renewal(&sub_id, &2, &3000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &2, &3000, &20260201));
+    assert_eq!(token_client.balance(&merchant), 4500);
+}
+
+#[test]
+fn test_change_plan_upgrade_before_first_charge_is_not_prorated() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+
+    // No period has been paid for, so the first charge simply uses the new price
+    client.change_plan(&sub_id, &3000, &86400);
+    assert_eq!(token_client.balance(&merchant), 0);
+    assert_eq!(client.process_due(&keeper, &vec![&env, sub_id]), 1);
+    assert_eq!(client.get_user_spent(&owner), 3000);
+}
+
+#[test]
+fn test_change_plan_downgrade_applies_at_period_end() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &20260101));
+
+    client.change_plan(&sub_id, &400, &86400);
+    let pending = client.get_pending_plan(&sub_id).unwrap();
+    assert_eq!(pending.amount, 400);
+    assert_eq!(pending.effective_at, client.next_due_at(&sub_id));
+    // Nothing charged and the current terms still hold
+    assert_eq!(token_client.balance(&merchant), 1000);
+    assert_eq!(client.get_sub(&sub_id).amount, 1000);
+
+    // An early renewal still runs on the current plan
+    client.approve_renewal(&sub_id, &2, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &2, &1000, &20260201));
+    assert!(client.get_pending_plan(&sub_id).is_some());
+
+    // The first renewal after the period ends switches to the new plan
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    client.approve_renewal(&sub_id, &3, &400, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &3, &400, &20260301));
+    assert_eq!(client.get_sub(&sub_id).amount, 400);
+    assert_eq!(client.get_pending_plan(&sub_id), None);
+    assert_eq!(token_client.balance(&merchant), 2400);
+}
+
+#[test]
+fn test_change_plan_frequency_applied_by_process_due() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+
+    // A new billing frequency always waits for the period end, even if pricier
+    client.change_plan(&sub_id, &5000, &604800);
+    assert_eq!(client.get_sub(&sub_id).frequency, 86400);
+
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    assert_eq!(client.process_due(&keeper, &vec![&env, sub_id]), 1);
+    let data = client.get_sub(&sub_id);
+    assert_eq!(data.amount, 5000);
+    assert_eq!(data.frequency, 604800);
+    assert_eq!(client.get_user_spent(&owner), 5000);
+    assert_eq!(client.next_due_at(&sub_id), env.ledger().timestamp() + 604800);
+}
+
+#[test]
+fn test_change_plan_team_upgrade_needing_multisig_is_scheduled() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &500, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+    client.approve_renewal(&sub_id, &1, &500, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &500, &20260101));
+    client.assign_team(&sub_id, &7);
+    client.set_team_threshold(&7, &1000);
+    client.set_team_signers(&7, &vec![&env, Address::generate(&env)], &1);
+
+    // The 1875 prorated charge is above the team threshold: nothing is charged now
+    env.ledger().with_mut(|li| li.timestamp += 21600);
+    client.change_plan(&sub_id, &3000, &86400);
+    assert_eq!(token_client.balance(&merchant), 500);
+    assert_eq!(client.get_sub(&sub_id).amount, 500);
+    let pending = client.get_pending_plan(&sub_id).unwrap();
+    assert_eq!(pending.amount, 3000);
+    assert_eq!(pending.effective_at, client.next_due_at(&sub_id));
+}
+
+#[test]
+#[should_panic(expected = "Subscription cancels at period end")]
+fn test_change_plan_rejected_once_cancellation_is_scheduled() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &20260101));
+    client.cancel_sub(&sub_id, &CancelMode::AtPeriodEnd);
+
+    client.change_plan(&sub_id, &3000, &86400);
+}
+
+#[test]
+#[should_panic(expected = "Only active subscriptions can be upgraded")]
+fn test_change_plan_upgrade_rejected_while_paused() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &20260101));
+    client.pause_sub(&sub_id, &3600);
+
+    client.change_plan(&sub_id, &3000, &86400);
+}
+
+#[test]
+#[should_panic(expected = "Plan exceeds spending cap")]
+fn test_change_plan_above_spending_cap_fails() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &2000, &salt(&env));
+    client.change_plan(&sub_id, &2500, &86400);
+}
+
+#[test]
+#[should_panic(expected = "Global user spending cap exceeded")]
+fn test_change_plan_prorated_charge_respects_user_cap() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    client.set_user_cap(&owner, &1400, &(30 * 86400));
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &1000, &20260101);
+
+    // The 500 prorated charge would break the 1400 window cap
+    env.ledger().with_mut(|li| li.timestamp += 64800);
+    client.change_plan(&sub_id, &3000, &86400);
+}
+
+#[test]
+#[should_panic(expected = "Merchant spending cap exceeded")]
+fn test_change_plan_prorated_charge_respects_merchant_cap() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    client.set_merchant_cap(&owner, &merchant, &1400);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &1000, &20260101);
+
+    env.ledger().with_mut(|li| li.timestamp += 64800);
+    client.change_plan(&sub_id, &3000, &86400);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_change_plan_requires_owner_auth() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    env.set_auths(&[]);
+    client.change_plan(&sub_id, &400, &86400);
+}
+
+// ── Pause and grace period ───────────────────────────────────────
+
+#[test]
+fn test_pause_blocks_renewals_until_auto_resume() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+    let batch = vec![&env, sub_id];
+
+    let paused_at = env.ledger().timestamp();
+    client.pause_sub(&sub_id, &(3 * 86400));
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Paused);
+    assert!(!client.is_entitled(&sub_id));
+    let lifecycle = client.get_lifecycle(&sub_id);
+    assert_eq!(lifecycle.paused_at, paused_at);
+    assert_eq!(lifecycle.resume_at, paused_at + 3 * 86400);
+
+    // Due, but paused
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+
+    // The pause runs out and the next keeper run resumes and charges it
+    env.ledger().with_mut(|li| li.timestamp += 2 * 86400);
+    assert!(client.is_entitled(&sub_id));
+    assert_eq!(client.process_due(&keeper, &batch), 1);
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Active);
+    let lifecycle = client.get_lifecycle(&sub_id);
+    assert_eq!(lifecycle.resumed_at, env.ledger().timestamp());
+    assert_eq!(lifecycle.resume_at, 0);
+}
+
+#[test]
+#[should_panic(expected = "Subscription is paused")]
+fn test_renew_while_paused_fails() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    client.pause_sub(&sub_id, &86400);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &1000, &20260101);
+}
+
+#[test]
+fn test_resume_sub_restores_active() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 10_000);
+
+    client.pause_sub(&sub_id, &86400);
+    client.resume_sub(&sub_id);
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Active);
+    assert!(client.is_entitled(&sub_id));
+
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &20260101));
+}
+
+#[test]
+#[should_panic(expected = "Invalid pause duration")]
+fn test_pause_longer_than_max_fails() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    client.set_max_pause_duration(&(7 * 86400));
+    client.pause_sub(&sub_id, &(7 * 86400 + 1));
+}
+
+#[test]
+fn test_failed_charge_enters_grace_period() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let mut policy = dunning_policy(&env, 4);
+    policy.grace_secs = 2 * 86400;
+    client.set_default_dunning_policy(&policy);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    let batch = vec![&env, sub_id];
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+
+    // Unfunded: the charge fails but the owner stays entitled
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::GracePeriod);
+    assert!(client.is_entitled(&sub_id));
+    let grace_ends_at = client.get_lifecycle(&sub_id).grace_ends_at;
+    assert_eq!(grace_ends_at, env.ledger().timestamp() + 2 * 86400);
+
+    // A failed retry inside the grace period does not extend it
+    env.ledger().with_mut(|li| {
+        li.timestamp += 86400;
+        li.sequence_number += 720;
+    });
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+    assert_eq!(client.get_lifecycle(&sub_id).grace_ends_at, grace_ends_at);
+
+    // Once it ends the subscription is no longer entitled and keeps retrying
+    env.ledger().with_mut(|li| {
+        li.timestamp += 86400;
+        li.sequence_number += 720;
+    });
+    assert!(!client.is_entitled(&sub_id));
+    fund(&env, &token, &id, &owner, 10_000);
+    assert_eq!(client.process_due(&keeper, &batch), 1);
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Active);
+    assert_eq!(client.get_lifecycle(&sub_id).grace_ends_at, 0);
+}
+
+// ── Trials and introductory pricing ──────────────────────────────
+
+fn intro_offer(trial_secs: u64, intro_amount: i128, intro_cycles: u32) -> IntroOffer {
+    IntroOffer {
+        trial_secs,
+        intro_amount,
+        intro_cycles,
+    }
+}
+
+#[test]
+fn test_trial_converts_with_approval_then_intro_price() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let token_client = TokenClient::new(&env, &token);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let offer = intro_offer(7 * 86400, 500, 2);
+    let sub_id = client.init_sub_with_intro(
+        &owner, &merchant, &token, &1000, &86400, &0, &salt(&env), &offer,
+    );
+    fund(&env, &token, &id, &owner, 100_000);
+    assert!(client.has_used_trial(&owner, &merchant));
+    let batch = vec![&env, sub_id];
+
+    // First due at the end of the trial, but keepers cannot convert it
+    assert_eq!(client.next_due_at(&sub_id), env.ledger().timestamp() + 7 * 86400);
+    env.ledger().with_mut(|li| li.timestamp += 7 * 86400);
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+
+    // The owner's approval converts it at the introductory price
+    client.approve_renewal(&sub_id, &1, &500, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &500, &20260101));
+    assert_eq!(client.get_sub(&sub_id).paid_cycles, 1);
+
+    // Keepers charge the remaining intro cycle, then the full amount
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    assert_eq!(client.process_due(&keeper, &batch), 1);
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    assert_eq!(client.process_due(&keeper, &batch), 1);
+    // 500 + 500 + 1000, less the keeper bounty on the two keeper charges
+    assert_eq!(token_client.balance(&merchant), 500 + 500 + 1000 - 1);
+}
+
+#[test]
+#[should_panic(expected = "Subscription is in its trial period")]
+fn test_renew_during_trial_fails() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let offer = intro_offer(86400, 0, 0);
+    let sub_id = client.init_sub_with_intro(
+        &owner, &merchant, &token, &1000, &86400, &0, &salt(&env), &offer,
+    );
+    fund(&env, &token, &id, &owner, 10_000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &1000, &20260101);
+}
+
+#[test]
+#[should_panic(expected = "Amount exceeds introductory price")]
+fn test_renew_above_intro_price_fails() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let offer = intro_offer(0, 300, 1);
+    let sub_id = client.init_sub_with_intro(
+        &owner, &merchant, &token, &1000, &86400, &0, &salt(&env), &offer,
+    );
+    fund(&env, &token, &id, &owner, 10_000);
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &1000, &20260101);
+}
+
+#[test]
+#[should_panic(expected = "Trial already used with this merchant")]
+fn test_one_trial_per_owner_and_merchant() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let offer = intro_offer(86400, 0, 0);
+    client.init_sub_with_intro(
+        &owner, &merchant, &token, &1000, &86400, &0, &salt(&env), &offer,
+    );
+    // Another merchant's trial is fine
+    let other_merchant = Address::generate(&env);
+    client.init_sub_with_intro(
+        &owner, &other_merchant, &token, &1000, &86400, &0, &salt(&env), &offer,
+    );
+    client.init_sub_with_intro(
+        &owner, &merchant, &token, &1000, &86400, &0, &salt(&env), &offer,
+    );
+}
+
+// ── Rolling spending caps ────────────────────────────────────────
+
+#[test]
+fn test_user_cap_rolls_over_each_window() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let month = 30 * 86400;
+    client.set_user_cap(&owner, &1500, &month);
+    // Weekly subscription: four charges a month would exceed the monthly cap
+    let sub_id = client.init_sub(&owner, &merchant, &token, &500, &(7 * 86400), &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 100_000);
+    let batch = vec![&env, sub_id];
+
+    for _ in 0..3 {
+        env.ledger().with_mut(|li| li.timestamp += 7 * 86400);
+        assert_eq!(client.process_due(&keeper, &batch), 1);
+    }
+    assert_eq!(client.get_user_spent(&owner), 1500);
+    assert_eq!(client.get_remaining_budget(&owner, &None), Some(0));
+
+    // Day 28: still the same window
+    env.ledger().with_mut(|li| li.timestamp += 7 * 86400);
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+
+    // Day 30: a new window opens and the charge goes through
+    env.ledger().with_mut(|li| li.timestamp += 2 * 86400);
+    assert_eq!(client.get_user_spent(&owner), 0);
+    assert_eq!(client.process_due(&keeper, &batch), 1);
+    assert_eq!(client.get_remaining_budget(&owner, &None), Some(1000));
+}
+
+#[test]
+fn test_merchant_sub_cap_and_remaining_budget() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let streaming = Address::generate(&env);
+    let gaming = Address::generate(&env);
+    client.set_user_cap(&owner, &2000, &(30 * 86400));
+    client.set_merchant_cap(&owner, &gaming, &600);
+    assert_eq!(client.get_remaining_budget(&owner, &None), Some(2000));
+    assert_eq!(client.get_remaining_budget(&owner, &Some(gaming.clone())), Some(600));
+
+    let streaming_sub = client.init_sub(&owner, &streaming, &token, &1000, &86400, &0, &salt(&env));
+    let gaming_sub = client.init_sub(&owner, &gaming, &token, &500, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 10_000);
+    for sub_id in [streaming_sub, gaming_sub] {
+        client.approve_renewal(&sub_id, &1, &1000, &100);
+        client.acquire_renewal_lock(&agent, &sub_id, &200);
+        assert!(client.renew(&agent, &sub_id, &1, &client.get_sub(&sub_id).amount, &1));
+    }
+
+    assert_eq!(client.get_user_spent(&owner), 1500);
+    assert_eq!(client.get_merchant_spent(&owner, &gaming), 500);
+    assert_eq!(client.get_remaining_budget(&owner, &None), Some(500));
+    assert_eq!(client.get_remaining_budget(&owner, &Some(gaming.clone())), Some(100));
+    assert_eq!(client.get_remaining_budget(&owner, &Some(streaming)), Some(500));
+}
+
+#[test]
+#[should_panic(expected = "Merchant spending cap exceeded")]
+fn test_merchant_sub_cap_exceeded() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    client.set_merchant_cap(&owner, &merchant, &800);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &500, &86400, &0, &salt(&env));
+    fund(&env, &token, &id, &owner, 10_000);
+
+    client.approve_renewal(&sub_id, &1, &500, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &500, &1));
+
+    client.approve_renewal(&sub_id, &2, &500, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &2, &500, &2);
+}
+
+// ── Dunning policy ───────────────────────────────────────────────
+
+#[test]
+fn test_merchant_dunning_policy_backs_off_exponentially() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let policy = DunningPolicy {
+        max_attempts: 3,
+        backoff: RetryBackoff::Exponential(100),
+        grace_secs: 0,
+        final_action: FinalAction::Suspend,
+    };
+    client.set_dunning_policy(&merchant, &policy);
+    assert_eq!(client.get_dunning_policy(&merchant), policy);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    let batch = vec![&env, sub_id];
+    assert_eq!(client.next_retry_at(&sub_id), None);
+
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    let first_failure = env.ledger().sequence();
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+    assert_eq!(client.next_retry_at(&sub_id), Some(first_failure + 100));
+
+    // Not before the backoff has passed
+    env.ledger().with_mut(|li| li.sequence_number += 99);
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+    assert_eq!(client.get_sub(&sub_id).failure_count, 1);
+
+    // Second failure doubles the delay
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    let second_failure = env.ledger().sequence();
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+    assert_eq!(client.next_retry_at(&sub_id), Some(second_failure + 200));
+
+    // Third failure exhausts the policy and suspends the subscription
+    env.ledger().with_mut(|li| li.sequence_number += 200);
+    assert_eq!(client.process_due(&keeper, &batch), 0);
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Failed);
+    assert_eq!(client.next_retry_at(&sub_id), None);
+}
+
+#[test]
+fn test_dunning_final_action_cancels() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let mut policy = dunning_policy(&env, 1);
+    policy.final_action = FinalAction::Cancel;
+    client.set_dunning_policy(&merchant, &policy);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(!client.renew(&agent, &sub_id, &1, &1000, &20260101));
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Cancelled);
+    assert_eq!(client.get_lifecycle(&sub_id).canceled_at, env.ledger().timestamp());
+}
+
+#[test]
+#[should_panic(expected = "Dunning policy needs at least one delay")]
+fn test_dunning_policy_without_delays_rejected() {
+    let (env, id, _admin, _token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let merchant = Address::generate(&env);
+    let mut policy = dunning_policy(&env, 3);
+    policy.backoff = RetryBackoff::Fixed(vec![&env]);
+    client.set_dunning_policy(&merchant, &policy);
+}
+
+// ── Subscription indexes and merchant stats ──────────────────────
+
+#[test]
+fn test_init_sub_assigns_sequential_ids() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+
+    let first = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    let second = client.init_sub(&owner, &merchant, &token, &2000, &86400, &0, &salt(&env));
+    assert_eq!((first, second), (1, 2));
+    assert_eq!(client.get_sub(&first).amount, 1000);
+    assert_eq!(client.get_sub(&second).amount, 2000);
+}
+
+#[test]
+fn test_list_by_owner_and_merchant_paginate() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let other_owner = Address::generate(&env);
+    let merchant_a = Address::generate(&env);
+    let merchant_b = Address::generate(&env);
+
+    let s1 = client.init_sub(&owner, &merchant_a, &token, &1000, &86400, &0, &salt(&env));
+    let s2 = client.init_sub(&other_owner, &merchant_a, &token, &1000, &86400, &0, &salt(&env));
+    let s3 = client.init_sub(&owner, &merchant_b, &token, &1000, &86400, &0, &salt(&env));
+    let s4 = client.init_sub(&owner, &merchant_a, &token, &1000, &86400, &0, &salt(&env));
+
+    assert_eq!(client.list_by_owner(&owner, &0, &2), vec![&env, s1, s3]);
+    assert_eq!(client.list_by_owner(&owner, &2, &2), vec![&env, s4]);
+    assert_eq!(client.list_by_owner(&owner, &3, &2), vec![&env]);
+    assert_eq!(client.list_by_merchant(&merchant_a, &0, &10), vec![&env, s1, s2, s4]);
+    assert_eq!(client.list_by_merchant(&merchant_b, &0, &10), vec![&env, s3]);
+}
+
+#[test]
+fn test_merchant_stats_follow_state_transitions() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    client.set_dunning_policy(&merchant, &dunning_policy(&env, 1));
+
+    // 1000 per day and 7000 per week: 30000 + 30000 per 30-day month
+    let daily = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    let weekly = client.init_sub(&owner, &merchant, &token, &7000, &604800, &0, &salt(&env));
+    let stats = client.get_merchant_stats(&merchant);
+    assert_eq!((stats.active_count, stats.mrr, stats.failed_count), (2, 60_000, 0));
+
+    // Pausing takes a subscription out of the active count and MRR
+    client.pause_sub(&weekly, &86400);
+    let stats = client.get_merchant_stats(&merchant);
+    assert_eq!((stats.active_count, stats.mrr), (1, 30_000));
+
+    // An unfunded charge exhausts the single-attempt policy
+    client.approve_renewal(&daily, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &daily, &200);
+    assert!(!client.renew(&agent, &daily, &1, &1000, &1));
+    let stats = client.get_merchant_stats(&merchant);
+    assert_eq!((stats.active_count, stats.mrr, stats.failed_count), (0, 0, 1));
+
+    client.resume_sub(&weekly);
+    client.cancel_sub(&weekly, &CancelMode::Immediate);
+    let stats = client.get_merchant_stats(&merchant);
+    assert_eq!((stats.active_count, stats.mrr, stats.failed_count), (0, 0, 1));
+}
+
+// ── Metered usage ────────────────────────────────────────────────
+
+/// 1 per unit up to 100 units, 2 per unit above.
+fn usage_tiers(env: &Env) -> soroban_sdk::Vec<UsageTier> {
+    vec![
+        env,
+        UsageTier {
+            up_to: 100,
+            unit_price: 1,
+        },
+        UsageTier {
+            up_to: 1000,
+            unit_price: 2,
+        },
+    ]
+}
+
+#[test]
+fn test_metered_renewal_charges_base_plus_reported_usage() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    client.set_usage_tiers(&sub_id, &usage_tiers(&env));
+    fund(&env, &token, &id, &owner, 10_000);
+
+    // 100 units at 1 and 50 at 2; units past the last tier keep its price
+    assert_eq!(client.report_usage(&sub_id, &1, &150), 200);
+    assert_eq!(client.get_usage_report(&sub_id, &1).unwrap().units, 150);
+
+    client.approve_renewal(&sub_id, &1, &1200, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &1));
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 1200);
+    assert_eq!(client.get_user_spent(&owner), 1200);
+
+    // Unreported cycles are charged the base amount only (less the keeper bounty)
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    assert_eq!(
+        client.process_due(&Address::generate(&env), &vec![&env, sub_id]),
+        1
+    );
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 2199);
+    assert_eq!(client.report_usage(&sub_id, &3, &1500), 2900);
+}
+
+#[test]
+#[should_panic(expected = "Invalid or expired approval")]
+fn test_metered_charge_bounded_by_max_spend() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    client.set_usage_tiers(&sub_id, &usage_tiers(&env));
+    fund(&env, &token, &id, &owner, 10_000);
+    client.report_usage(&sub_id, &1, &150);
+
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &1000, &1);
+}
+
+#[test]
+#[should_panic(expected = "Per-subscription spending cap exceeded")]
+fn test_metered_charge_bounded_by_spending_cap() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &1100, &salt(&env));
+    client.set_usage_tiers(&sub_id, &usage_tiers(&env));
+    fund(&env, &token, &id, &owner, 10_000);
+    client.report_usage(&sub_id, &1, &150);
+
+    client.approve_renewal(&sub_id, &1, &5000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    client.renew(&agent, &sub_id, &1, &1000, &1);
+}
+
+#[test]
+#[should_panic(expected = "Cycle already charged")]
+fn test_usage_cannot_be_reported_for_charged_cycle() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    client.set_usage_tiers(&sub_id, &usage_tiers(&env));
+    fund(&env, &token, &id, &owner, 10_000);
+
+    client.approve_renewal(&sub_id, &1, &1000, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &1));
+    client.report_usage(&sub_id, &1, &10);
+}
+
+#[test]
+fn test_process_due_skips_cycle_with_usage_report() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    client.set_usage_tiers(&sub_id, &usage_tiers(&env));
+    fund(&env, &token, &id, &owner, 1_000_000);
+
+    // An uncapped report is not charged by a keeper
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    client.report_usage(&sub_id, &1, &100_000);
+    assert_eq!(
+        client.process_due(&Address::generate(&env), &vec![&env, sub_id]),
+        0
+    );
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 0);
+
+    // The owner approves the total through `renew`
+    client.approve_renewal(&sub_id, &1, &200_900, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &1000, &1));
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 200_900);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_set_usage_tiers_requires_merchant_auth() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    let tiers = usage_tiers(&env);
+    env.mock_auths(&[MockAuth {
+        address: &owner,
+        invoke: &MockAuthInvoke {
+            contract: &id,
+            fn_name: "set_usage_tiers",
+            args: (sub_id, tiers.clone()).into_val(&env),
+            sub_invokes: &[],
+        },
+    }]);
+    client.set_usage_tiers(&sub_id, &tiers);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_report_usage_requires_merchant_auth() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &1000, &86400, &0, &salt(&env));
+    client.set_usage_tiers(&sub_id, &usage_tiers(&env));
+    env.set_auths(&[]);
+    client.report_usage(&sub_id, &1, &10);
+}
+
+// ── Registry linkage ─────────────────────────────────────────────
+
+/// Create a registry entry for `owner` due at t=86400 and link it to `sub_id`.
+fn link_registry(env: &Env, id: &Address, owner: &Address, sub_id: u64) -> (Address, BytesN<32>) {
+    let registry_id = env.register(SubscriptionRegistry, ());
+    let registry = SubscriptionRegistryClient::new(env, &registry_id);
+    let subscription_id = registry.create_subscription(
+        owner,
+        &String::from_str(env, "netflix"),
+        &86400,
+        &500,
+        &86400,
+        &Bytes::new(env),
+    );
+    SubscriptionRenewalContractClient::new(env, id).link_registry(
+        &sub_id,
+        &registry_id,
+        &subscription_id,
+    );
+    (registry_id, subscription_id)
+}
+
+#[test]
+fn test_renewal_advances_linked_registry_next_renewal() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &500, &86400, &0, &salt(&env));
+    let (registry_id, subscription_id) = link_registry(&env, &id, &owner, sub_id);
+    let registry = SubscriptionRegistryClient::new(&env, &registry_id);
+
+    let link = client.get_registry_link(&sub_id).unwrap();
+    assert_eq!(link.subscription_id, subscription_id);
+    let metadata = registry.get_subscription(&subscription_id).unwrap();
+    assert_eq!(metadata.renewal_contract, Some(id.clone()));
+    assert_eq!(metadata.renewal_sub_id, sub_id);
+
+    fund(&env, &token, &id, &owner, 500);
+    client.approve_renewal(&sub_id, &1, &500, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(client.renew(&agent, &sub_id, &1, &500, &1));
+    let metadata = registry.get_subscription(&subscription_id).unwrap();
+    assert_eq!(metadata.next_renewal, 2 * 86400);
+    assert!(metadata.is_active);
+
+    // A failed charge leaves the registry untouched until the subscription fails for good
+    client.approve_renewal(&sub_id, &2, &500, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(!client.renew(&agent, &sub_id, &2, &500, &2));
+    let metadata = registry.get_subscription(&subscription_id).unwrap();
+    assert_eq!(metadata.next_renewal, 2 * 86400);
+    assert!(metadata.is_active);
+}
+
+#[test]
+fn test_cancel_deactivates_linked_registry_entry() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &500, &86400, &0, &salt(&env));
+    let (registry_id, subscription_id) = link_registry(&env, &id, &owner, sub_id);
+
+    client.cancel_sub(&sub_id, &CancelMode::Immediate);
+    let registry = SubscriptionRegistryClient::new(&env, &registry_id);
+    let metadata = registry.get_subscription(&subscription_id).unwrap();
+    assert!(!metadata.is_active);
+}
+
+#[test]
+fn test_final_failure_deactivates_linked_registry_entry() {
+    let (env, id, _admin, token, agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    client.set_default_dunning_policy(&dunning_policy(&env, 1));
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &500, &86400, &0, &salt(&env));
+    let (registry_id, subscription_id) = link_registry(&env, &id, &owner, sub_id);
+
+    client.approve_renewal(&sub_id, &1, &500, &100);
+    client.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(!client.renew(&agent, &sub_id, &1, &500, &1));
+    assert_eq!(client.get_sub(&sub_id).state, SubscriptionState::Failed);
+    let registry = SubscriptionRegistryClient::new(&env, &registry_id);
+    let metadata = registry.get_subscription(&subscription_id).unwrap();
+    assert!(!metadata.is_active);
+}
+
+/// Registry that accepts links but fails every renewal hook.
+#[contract]
+struct BrokenRegistry;
+
+#[contractimpl]
+impl BrokenRegistry {
+    pub fn link_renewal(
+        _env: Env,
+        _subscription_id: BytesN<32>,
+        _user: Address,
+        _renewal_contract: Address,
+        _renewal_sub_id: u64,
+    ) {
+    }
+
+    pub fn record_renewal(_env: Env, _subscription_id: BytesN<32>) {
+        panic!("registry unavailable");
+    }
+
+    pub fn record_deactivation(_env: Env, _subscription_id: BytesN<32>) {
+        panic!("registry unavailable");
+    }
+}
+
+#[test]
+fn test_broken_registry_does_not_block_renewals_or_cancel() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let broken_sub = client.init_sub(&owner, &merchant, &token, &500, &86400, &0, &salt(&env));
+    let other_sub = client.init_sub(&owner, &merchant, &token, &500, &86400, &0, &salt(&env));
+    let registry_id = env.register(BrokenRegistry, ());
+    client.link_registry(
+        &broken_sub,
+        &registry_id,
+        &BytesN::from_array(&env, &[1u8; 32]),
+    );
+    fund(&env, &token, &id, &owner, 10_000);
+
+    // The failing hook neither reverts the charge nor aborts the batch
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    assert_eq!(
+        client.process_due(&keeper, &vec![&env, broken_sub, other_sub]),
+        2
+    );
+    assert_eq!(client.get_sub(&broken_sub).paid_cycles, 1);
+
+    client.cancel_sub(&broken_sub, &CancelMode::Immediate);
+    assert_eq!(
+        client.get_sub(&broken_sub).state,
+        SubscriptionState::Cancelled
+    );
+}
+
+#[test]
+#[should_panic(expected = "not subscription owner")]
+fn test_link_registry_rejects_entry_of_another_user() {
+    let (env, id, _admin, token, _agent) = setup();
+    let client = SubscriptionRenewalContractClient::new(&env, &id);
+    let owner = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let sub_id = client.init_sub(&owner, &merchant, &token, &500, &86400, &0, &salt(&env));
+
+    // Registry entry created by someone else
+    let registry_id = env.register(SubscriptionRegistry, ());
+    let subscription_id = SubscriptionRegistryClient::new(&env, &registry_id).create_subscription(
+        &Address::generate(&env),
+        &String::from_str(&env, "netflix"),
+        &86400,
+        &500,
+        &86400,
+        &Bytes::new(&env),
+    );
+    client.link_registry(&sub_id, &registry_id, &subscription_id);
+}
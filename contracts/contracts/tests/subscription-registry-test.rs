@@ -1,5 +1,11 @@
-use soroban_sdk::{testutils::Address as _, Address, Bytes, BytesN, Env, String};
-use subscription_registry::{SubscriptionRegistry, SubscriptionRegistryClient};
+use soroban_sdk::{
+    testutils::{storage::Persistent as _, Address as _, Ledger},
+    vec, Address, Bytes, BytesN, Env, String,
+};
+use subscription_registry::{
+    DataKey, RecipientKey, RecipientRole, SubscriptionRegistry, SubscriptionRegistryClient,
+    TTL_EXTEND_TO, TTL_THRESHOLD,
+};
 
 #[test]
 fn test_create_subscription() {
@@ -419,3 +425,409 @@ fn test_update_subscription_unauthorized() {
         &None,
     );
 }
+
+fn create_netflix(env: &Env, client: &SubscriptionRegistryClient, user: &Address) -> BytesN<32> {
+    client.create_subscription(
+        user,
+        &String::from_str(env, "netflix"),
+        &2592000u64,
+        &1599i128,
+        &1735689600u64,
+        &Bytes::from_slice(env, &[1u8, 2, 3]),
+    )
+}
+
+#[test]
+fn test_subscription_stored_per_key_in_persistent_storage() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+
+    env.as_contract(&contract_id, || {
+        let key = DataKey::Subscription(subscription_id.clone());
+        assert!(env.storage().persistent().has(&key));
+        assert!(!env.storage().instance().has(&key));
+        assert_eq!(env.storage().persistent().get_ttl(&key), TTL_EXTEND_TO);
+        let user_key = DataKey::UserSubscriptions(user.clone());
+        assert_eq!(env.storage().persistent().get_ttl(&user_key), TTL_EXTEND_TO);
+    });
+}
+
+#[test]
+fn test_access_extends_ttl_below_threshold() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    let key = DataKey::Subscription(subscription_id.clone());
+    let ttl = || env.as_contract(&contract_id, || env.storage().persistent().get_ttl(&key));
+
+    // Still above the threshold: reads leave the TTL alone
+    env.ledger().with_mut(|li| li.sequence_number += 1_000);
+    client.get_subscription(&subscription_id);
+    assert_eq!(ttl(), TTL_EXTEND_TO - 1_000);
+
+    // Below the threshold: the next read extends it again
+    env.ledger()
+        .with_mut(|li| li.sequence_number += TTL_EXTEND_TO - TTL_THRESHOLD);
+    client.get_subscription(&subscription_id);
+    assert_eq!(ttl(), TTL_EXTEND_TO);
+}
+
+#[test]
+fn test_owner_extends_subscription_ttl() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.extend_subscription_ttl(&subscription_id, &user, &u32::MAX);
+
+    // Capped at the network maximum
+    env.as_contract(&contract_id, || {
+        let max_ttl = env.storage().max_ttl();
+        let key = DataKey::Subscription(subscription_id.clone());
+        assert_eq!(env.storage().persistent().get_ttl(&key), max_ttl);
+        let user_key = DataKey::UserSubscriptions(user.clone());
+        assert_eq!(env.storage().persistent().get_ttl(&user_key), max_ttl);
+    });
+}
+
+#[test]
+#[should_panic(expected = "not subscription owner")]
+fn test_extend_ttl_requires_owner() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.extend_subscription_ttl(&subscription_id, &Address::generate(&env), &1_000_000);
+}
+
+#[test]
+fn test_archive_and_restore_subscription() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.cancel_subscription(&subscription_id, &user);
+    let metadata = client.get_subscription(&subscription_id).unwrap();
+
+    client.archive_subscription(&subscription_id, &user);
+    assert!(client.get_subscription(&subscription_id).is_none());
+    assert!(client.get_archived(&subscription_id).is_some());
+    assert_eq!(client.get_user_subscriptions(&user).len(), 1);
+
+    client.restore_subscription(&subscription_id, &user, &metadata);
+    assert_eq!(client.get_subscription(&subscription_id), Some(metadata));
+    assert!(client.get_archived(&subscription_id).is_none());
+}
+
+#[test]
+#[should_panic(expected = "only cancelled subscriptions can be archived")]
+fn test_archive_active_subscription_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.archive_subscription(&subscription_id, &user);
+}
+
+#[test]
+#[should_panic(expected = "metadata does not match archive")]
+fn test_restore_with_altered_metadata_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.cancel_subscription(&subscription_id, &user);
+    let mut metadata = client.get_subscription(&subscription_id).unwrap();
+    client.archive_subscription(&subscription_id, &user);
+
+    metadata.is_active = true;
+    client.restore_subscription(&subscription_id, &user, &metadata);
+}
+
+#[test]
+#[should_panic(expected = "subscription not linked")]
+fn test_renewal_hook_requires_link() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.record_renewal(&subscription_id);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_renewal_hook_requires_linked_contract_auth() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let renewal_contract = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.link_renewal(&subscription_id, &user, &renewal_contract, &1);
+    let metadata = client.get_subscription(&subscription_id).unwrap();
+    assert_eq!(metadata.renewal_contract, Some(renewal_contract));
+    assert_eq!(metadata.renewal_sub_id, 1);
+
+    // Only the linked renewal contract may deactivate the entry
+    env.set_auths(&[]);
+    client.record_deactivation(&subscription_id);
+}
+
+#[test]
+fn test_renewal_hook_skips_cancelled_subscription() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.link_renewal(&subscription_id, &user, &Address::generate(&env), &1);
+    client.cancel_subscription(&subscription_id, &user);
+
+    client.record_renewal(&subscription_id);
+    let metadata = client.get_subscription(&subscription_id).unwrap();
+    assert_eq!(metadata.next_renewal, 1735689600);
+    assert!(!metadata.is_active);
+}
+
+#[test]
+#[should_panic(expected = "not subscription owner")]
+fn test_update_subscription_requires_owner() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.update_subscription(
+        &subscription_id,
+        &Address::generate(&env),
+        &None,
+        &None,
+        &Some(1i128),
+        &None,
+        &None,
+    );
+}
+
+#[test]
+#[should_panic(expected = "not subscription owner")]
+fn test_cancel_subscription_requires_owner() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.cancel_subscription(&subscription_id, &Address::generate(&env));
+}
+
+#[test]
+#[should_panic(expected = "not subscription owner")]
+fn test_store_encrypted_subscription_requires_owner() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    client.store_encrypted_subscription(
+        &subscription_id,
+        &Address::generate(&env),
+        &String::from_str(&env, "ciphertext"),
+    );
+}
+
+fn recipient_key(
+    env: &Env,
+    recipient: &Address,
+    role: RecipientRole,
+    key_version: u32,
+) -> RecipientKey {
+    RecipientKey {
+        recipient: recipient.clone(),
+        role,
+        key_version,
+        wrapped_key: Bytes::from_slice(env, &[key_version as u8; 48]),
+    }
+}
+
+#[test]
+fn test_envelope_shared_with_merchant_and_auditor() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let auditor = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    let ciphertext = Bytes::from_slice(&env, &[9u8; 64]);
+    let recipients = vec![
+        &env,
+        recipient_key(&env, &user, RecipientRole::Owner, 1),
+        recipient_key(&env, &merchant, RecipientRole::Merchant, 1),
+    ];
+    client.store_envelope(&subscription_id, &user, &ciphertext, &1, &recipients);
+
+    let envelope = client.get_envelope(&subscription_id).unwrap();
+    assert_eq!(envelope.ciphertext, ciphertext);
+    assert_eq!(envelope.key_version, 1);
+    assert_eq!(
+        client.get_wrapped_key(&subscription_id, &merchant),
+        Some(recipient_key(&env, &merchant, RecipientRole::Merchant, 1))
+    );
+    assert!(client.get_wrapped_key(&subscription_id, &auditor).is_none());
+
+    // Owner shares with an accountant, then takes the merchant off
+    let auditor_key = recipient_key(&env, &auditor, RecipientRole::Auditor, 1);
+    client.grant_recipient(&subscription_id, &user, &auditor_key);
+    client.revoke_recipient(&subscription_id, &user, &merchant);
+    assert_eq!(
+        client.get_recipients(&subscription_id),
+        vec![&env, user.clone(), auditor.clone()]
+    );
+    assert!(client
+        .get_wrapped_key(&subscription_id, &merchant)
+        .is_none());
+    assert_eq!(
+        client.get_wrapped_key(&subscription_id, &auditor),
+        Some(auditor_key)
+    );
+}
+
+#[test]
+fn test_envelope_rotation_drops_previous_keys() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    let recipients = vec![
+        &env,
+        recipient_key(&env, &user, RecipientRole::Owner, 1),
+        recipient_key(&env, &merchant, RecipientRole::Merchant, 1),
+    ];
+    client.store_envelope(
+        &subscription_id,
+        &user,
+        &Bytes::from_slice(&env, &[1u8; 32]),
+        &1,
+        &recipients,
+    );
+
+    // Rotate to version 2 for the owner only
+    let recipients = vec![&env, recipient_key(&env, &user, RecipientRole::Owner, 2)];
+    client.store_envelope(
+        &subscription_id,
+        &user,
+        &Bytes::from_slice(&env, &[2u8; 32]),
+        &2,
+        &recipients,
+    );
+
+    assert_eq!(
+        client.get_envelope(&subscription_id).unwrap().key_version,
+        2
+    );
+    assert_eq!(
+        client.get_recipients(&subscription_id),
+        vec![&env, user.clone()]
+    );
+    assert!(client
+        .get_wrapped_key(&subscription_id, &merchant)
+        .is_none());
+    env.as_contract(&contract_id, || {
+        let old_key = DataKey::WrappedKey(subscription_id.clone(), merchant.clone(), 1);
+        assert!(!env.storage().persistent().has(&old_key));
+    });
+}
+
+#[test]
+#[should_panic(expected = "key_version must increase")]
+fn test_envelope_key_version_cannot_go_back() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    let ciphertext = Bytes::from_slice(&env, &[1u8; 32]);
+    let recipients = vec![&env, recipient_key(&env, &user, RecipientRole::Owner, 2)];
+    client.store_envelope(&subscription_id, &user, &ciphertext, &2, &recipients);
+    client.store_envelope(&subscription_id, &user, &ciphertext, &2, &recipients);
+}
+
+#[test]
+#[should_panic(expected = "owner must be a recipient")]
+fn test_envelope_requires_owner_recipient() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    let recipients = vec![
+        &env,
+        recipient_key(&env, &merchant, RecipientRole::Merchant, 1),
+    ];
+    client.store_envelope(&subscription_id, &user, &Bytes::new(&env), &1, &recipients);
+}
+
+#[test]
+#[should_panic(expected = "not subscription owner")]
+fn test_grant_recipient_requires_owner() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SubscriptionRegistry, ());
+    let client = SubscriptionRegistryClient::new(&env, &contract_id);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let subscription_id = create_netflix(&env, &client, &user);
+    let recipients = vec![&env, recipient_key(&env, &user, RecipientRole::Owner, 1)];
+    client.store_envelope(&subscription_id, &user, &Bytes::new(&env), &1, &recipients);
+
+    let merchant_key = recipient_key(&env, &merchant, RecipientRole::Merchant, 1);
+    client.grant_recipient(&subscription_id, &merchant, &merchant_key);
+}
@@ -1,14 +1,45 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, Bytes, BytesN, Env};
+use soroban_sdk::{contract, contractimpl, contracttype, Address, Bytes, BytesN, Env};
 
 pub mod commitment;
 pub mod nullifier;
+pub mod verifier;
 
 #[contracttype]
 #[derive(Clone)]
 pub enum DataKey {
+    Admin,
+    /// service_id -> allowed flag
+    Service(Bytes),
+    /// Legacy single-map nullifier set (read-only)
     Nullifiers,
+    /// nullifier -> epoch its proof window closes in
+    Nullifier(BytesN<32>),
+    /// proof commitment -> RegisteredPayment
+    Payment(BytesN<32>),
+}
+
+/// What a payment proof claims: a payment of at least `amount_threshold`, made
+/// between `window_start` and `window_end` (inclusive) and presented by `window_end`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PaymentClaim {
+    pub amount_threshold: i128,
+    pub window_start: u64,
+    pub window_end: u64,
+}
+
+/// A payment the admin has attested to, keyed by its proof commitment.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegisteredPayment {
+    /// Only this address may submit proofs against the commitment
+    pub payer: Address,
+    /// Amount paid; proofs may claim any threshold up to it
+    pub amount: i128,
+    /// Ledger time the payment was registered; claim windows must contain it
+    pub paid_at: u64,
 }
 
 #[contract]
@@ -16,8 +47,93 @@ pub struct ZkPaymentVerifier;
 
 #[contractimpl]
 impl ZkPaymentVerifier {
+    pub fn init(env: Env, admin: Address) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
+        env.storage().instance().set(&DataKey::Admin, &admin);
+    }
+
+    fn require_admin(env: &Env) {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("Not initialized");
+        admin.require_auth();
+    }
+
+    /// Set the `ContractUpgradeGovernance` contract allowed to call `upgrade`
+    /// (admin for the first call, the current governance contract afterwards).
+    pub fn set_governance(env: Env, governance: Address) {
+        governed_upgrade::set_governance(&env, &governance, || Self::require_admin(&env));
+    }
+
+    /// Query the configured governance contract, if any.
+    pub fn get_governance(env: Env) -> Option<Address> {
+        governed_upgrade::get_governance(&env)
+    }
+
+    /// Replace this contract's WASM. Only callable by the governance contract.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        governed_upgrade::upgrade(&env, new_wasm_hash);
+    }
+
+    /// Allow proofs for `service_id`. Admin only.
+    pub fn register_service(env: Env, service_id: Bytes) {
+        Self::require_admin(&env);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Service(service_id), &true);
+    }
+
+    /// Stop accepting proofs for `service_id`. Admin only.
+    /// Nullifiers already recorded for it stay used.
+    pub fn remove_service(env: Env, service_id: Bytes) {
+        Self::require_admin(&env);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Service(service_id));
+    }
+
+    /// Whether proofs for `service_id` are accepted.
+    pub fn is_service_allowed(env: Env, service_id: Bytes) -> bool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Service(service_id))
+            .unwrap_or(false)
+    }
+
+    /// Register `commitment` (see [`verifier`]) as a payment of `amount` by `payer`.
+    /// Admin only; the admin attests to the payment having settled, and registers it
+    /// as it settles since the current ledger time is recorded as `paid_at`.
+    pub fn register_payment(env: Env, commitment: BytesN<32>, payer: Address, amount: i128) {
+        Self::require_admin(&env);
+        if amount <= 0 {
+            panic!("Invalid payment amount");
+        }
+        let key = DataKey::Payment(commitment);
+        if env.storage().persistent().has(&key) {
+            panic!("Payment already registered");
+        }
+        let payment = RegisteredPayment {
+            payer,
+            amount,
+            paid_at: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&key, &payment);
+    }
+
+    /// Payment registered under `commitment`, if any.
+    pub fn get_payment(env: Env, commitment: BytesN<32>) -> Option<RegisteredPayment> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Payment(commitment))
+    }
+
     /// Verify a payment commitment and record its nullifier.
-    /// Returns true if the commitment is valid and the nullifier is fresh.
+    /// Returns true if `service_id` is registered, the commitment is valid and
+    /// the nullifier is fresh.
     pub fn verify_and_record(
         env: Env,
         user_id: Bytes,
@@ -27,6 +143,10 @@ impl ZkPaymentVerifier {
         blinding_factor: BytesN<32>,
         expected_commitment: BytesN<32>,
     ) -> bool {
+        if !Self::is_service_allowed(env.clone(), service_id.clone()) {
+            return false;
+        }
+
         let computed = commitment::compute_commitment(
             &env,
             &user_id,
@@ -40,14 +160,89 @@ impl ZkPaymentVerifier {
             return false;
         }
 
-        let computed_nullifier = nullifier::compute_nullifier(&env, &blinding_factor, &service_id);
+        // Secrets spent under the v1 (deployment-agnostic) domain stay spent
+        let legacy_nullifier =
+            nullifier::compute_legacy_nullifier(&env, &blinding_factor, &service_id);
+        if nullifier::is_used(&env, &legacy_nullifier) {
+            return false;
+        }
+
+        let computed_nullifier = nullifier::compute_nullifier(
+            &env,
+            &env.current_contract_address(),
+            &blinding_factor,
+            &service_id,
+        );
         nullifier::record(&env, computed_nullifier)
     }
 
+    /// Verify a Fiat-Shamir payment proof (see [`verifier`]) and record its nullifier.
+    /// `commitment` must be registered (`register_payment`) to `submitter` with an
+    /// amount of at least `claim.amount_threshold`; the proof itself only shows
+    /// knowledge of the commitment's opening. The proof is bound to `submitter`
+    /// and the claim. The payment must have been registered inside the claim's time
+    /// window, and the proof submitted inside it too.
+    /// Unlike `verify_and_record`, the payment's user and blinding factor are
+    /// never revealed.
+    /// Returns true if `service_id` is registered, the proof is valid and the
+    /// nullifier is fresh.
+    pub fn verify_payment_proof(
+        env: Env,
+        submitter: Address,
+        service_id: Bytes,
+        proof: Bytes,
+        commitment: BytesN<32>,
+        nullifier: BytesN<32>,
+        claim: PaymentClaim,
+    ) -> bool {
+        submitter.require_auth();
+
+        if !Self::is_service_allowed(env.clone(), service_id.clone()) {
+            return false;
+        }
+
+        // Verify before touching the nullifier set: proofs for closed windows are
+        // rejected here, so archived nullifiers from expired epochs are never read.
+        if !verifier::verify_proof(
+            &env,
+            &submitter,
+            &service_id,
+            &proof,
+            &commitment,
+            &nullifier,
+            &claim,
+        ) {
+            return false;
+        }
+
+        match Self::get_payment(env.clone(), commitment) {
+            Some(payment)
+                if payment.payer == submitter
+                    && payment.amount >= claim.amount_threshold
+                    && payment.paid_at >= claim.window_start
+                    && payment.paid_at <= claim.window_end => {}
+            _ => return false,
+        }
+
+        nullifier::record_until(&env, nullifier, claim.window_end)
+    }
+
     /// Check if a nullifier has already been used.
     pub fn is_nullifier_used(env: Env, nullifier: BytesN<32>) -> bool {
         nullifier::is_used(&env, &nullifier)
     }
+
+    /// Epoch in which a used nullifier's proof window closes
+    /// (`nullifier::PERMANENT_EPOCH` if it has none).
+    pub fn get_nullifier_epoch(env: Env, nullifier: BytesN<32>) -> Option<u64> {
+        nullifier::epoch(&env, &nullifier)
+    }
+
+    /// Current nullifier epoch. Nullifiers from earlier epochs belong to closed
+    /// windows and may have been archived.
+    pub fn current_epoch(env: Env) -> u64 {
+        nullifier::epoch_of(env.ledger().timestamp())
+    }
 }
 
 #[cfg(test)]
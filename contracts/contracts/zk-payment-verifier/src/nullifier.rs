@@ -2,24 +2,62 @@
 //! (a `blinding_factor` + `service_id` pair) from being used to prove the
 //! same payment twice.
 //!
-//! All submitted nullifiers live in a single persistent `Map`, so every
-//! lookup/insert reads and rewrites the whole set -- storage and per-call
-//! cost grow linearly with the number of nullifiers ever submitted. That's
-//! an accepted MVP tradeoff (see the issue this module was built for);
-//! revisit if proof volume grows large enough to make it a bottleneck.
+//! Each nullifier lives under its own persistent key, so lookups and inserts
+//! touch a single entry regardless of how many proofs have been submitted.
+//! Entries carry the epoch (`EPOCH_SECONDS` bucket) in which the proof's time
+//! window closes and their TTL is extended to the end of that epoch. Once a
+//! window has closed its proof can never verify again, so the entry is left
+//! for Soroban state archival rather than being kept live forever. Nullifiers
+//! without a time window use `PERMANENT_EPOCH` and are kept at max TTL.
+//!
+//! Nullifiers recorded before per-key storage (the single `Nullifiers` map)
+//! are still honoured on lookup but never written to. Those were derived with
+//! the deployment-agnostic v1 domain (`compute_legacy_nullifier`).
 
-use soroban_sdk::{Bytes, BytesN, Env, Map};
+use soroban_sdk::{xdr::ToXdr, Address, Bytes, BytesN, Env, Map};
 
 use crate::DataKey;
 
-/// Compute a nullifier: `SHA-256("syncro:payment:v1" || blinding_factor || service_id)`.
+/// Length of a nullifier epoch: one week.
+pub const EPOCH_SECONDS: u64 = 604_800;
+
+/// Epoch for nullifiers whose proof has no closing time window.
+pub const PERMANENT_EPOCH: u64 = u64::MAX;
+
+/// Expected ledger close time, used to convert epoch ends into TTLs.
+const SECONDS_PER_LEDGER: u64 = 5;
+
+/// Compute a nullifier:
+/// `SHA-256("syncro:payment:v2" || XDR(contract) || network_id || blinding_factor || service_id)`.
 ///
-/// Deterministic per `(blinding_factor, service_id)` pair, so the same
-/// secret can never produce two different nullifiers. It reveals nothing
-/// about the underlying payment (amount, user, timestamp) -- it's a
-/// one-way hash of values only the prover knows, so an observer learns
-/// only "some proof already used this nullifier," never which payment.
+/// Deterministic per `(blinding_factor, service_id)` pair within one
+/// deployment, so the same secret can never produce two different
+/// nullifiers there. `contract` and the network id (SHA-256 of the network
+/// passphrase) bind it to a single verifier deployment, so a proof cannot be
+/// replayed against another one. It reveals nothing about the underlying
+/// payment (amount, user, timestamp) -- it's a one-way hash of values only
+/// the prover knows, so an observer learns only "some proof already used
+/// this nullifier," never which payment.
 pub fn compute_nullifier(
+    env: &Env,
+    contract: &Address,
+    blinding_factor: &BytesN<32>,
+    service_id: &Bytes,
+) -> BytesN<32> {
+    let mut payload = Bytes::from_slice(env, b"syncro:payment:v2");
+    payload.append(&contract.clone().to_xdr(env));
+    payload.append(&env.ledger().network_id().into());
+    payload.append(&Bytes::from_slice(env, &blinding_factor.to_array()));
+    payload.append(&service_id.clone());
+
+    env.crypto().sha256(&payload).into()
+}
+
+/// v1 nullifier: `SHA-256("syncro:payment:v1" || blinding_factor || service_id)`.
+///
+/// Only used to look up entries in the legacy `Nullifiers` map, so secrets
+/// spent before the v2 domain cannot be spent again.
+pub fn compute_legacy_nullifier(
     env: &Env,
     blinding_factor: &BytesN<32>,
     service_id: &Bytes,
@@ -31,30 +69,73 @@ pub fn compute_nullifier(
     env.crypto().sha256(&payload).into()
 }
 
-fn load(env: &Env) -> Map<BytesN<32>, bool> {
+/// Epoch containing `timestamp`.
+pub fn epoch_of(timestamp: u64) -> u64 {
+    timestamp / EPOCH_SECONDS
+}
+
+/// Epoch recorded for `nullifier`, if it has been used.
+pub fn epoch(env: &Env, nullifier: &BytesN<32>) -> Option<u64> {
     env.storage()
         .persistent()
-        .get(&DataKey::Nullifiers)
-        .unwrap_or(Map::new(env))
+        .get(&DataKey::Nullifier(nullifier.clone()))
+}
+
+fn in_legacy_map(env: &Env, nullifier: &BytesN<32>) -> bool {
+    env.storage()
+        .persistent()
+        .get::<_, Map<BytesN<32>, bool>>(&DataKey::Nullifiers)
+        .map(|nullifiers| nullifiers.contains_key(nullifier.clone()))
+        .unwrap_or(false)
 }
 
 /// Whether `nullifier` has already been recorded.
 pub fn is_used(env: &Env, nullifier: &BytesN<32>) -> bool {
-    load(env).contains_key(nullifier.clone())
+    epoch(env, nullifier).is_some() || in_legacy_map(env, nullifier)
 }
 
-/// Record `nullifier` as used.
+/// Record `nullifier` as used, with no expiry.
 ///
 /// Returns `true` if it was freshly recorded, `false` if it was already
 /// present (a duplicate -- the existing entry is left untouched).
 pub fn record(env: &Env, nullifier: BytesN<32>) -> bool {
-    let mut nullifiers = load(env);
-    if nullifiers.contains_key(nullifier.clone()) {
+    record_in_epoch(env, nullifier, PERMANENT_EPOCH)
+}
+
+/// Record `nullifier` for a proof whose time window closes at `window_end`.
+///
+/// The entry is kept live until the end of `window_end`'s epoch; after that
+/// the proof is unverifiable anyway and the entry may be archived.
+pub fn record_until(env: &Env, nullifier: BytesN<32>, window_end: u64) -> bool {
+    record_in_epoch(env, nullifier, epoch_of(window_end))
+}
+
+fn record_in_epoch(env: &Env, nullifier: BytesN<32>, epoch: u64) -> bool {
+    if is_used(env, &nullifier) {
         return false;
     }
-    nullifiers.set(nullifier, true);
-    env.storage()
-        .persistent()
-        .set(&DataKey::Nullifiers, &nullifiers);
+
+    let key = DataKey::Nullifier(nullifier);
+    env.storage().persistent().set(&key, &epoch);
+
+    let ttl = ttl_for_epoch(env, epoch);
+    env.storage().persistent().extend_ttl(&key, ttl, ttl);
     true
 }
+
+/// Ledgers needed to keep an entry live until the end of `epoch`, capped at max TTL.
+fn ttl_for_epoch(env: &Env, epoch: u64) -> u32 {
+    let max_ttl = env.storage().max_ttl();
+    if epoch == PERMANENT_EPOCH {
+        return max_ttl;
+    }
+
+    let epoch_end = epoch.saturating_add(1).saturating_mul(EPOCH_SECONDS);
+    let remaining = epoch_end.saturating_sub(env.ledger().timestamp());
+    let ledgers = remaining / SECONDS_PER_LEDGER + 1;
+    if ledgers > max_ttl as u64 {
+        max_ttl
+    } else {
+        ledgers as u32
+    }
+}
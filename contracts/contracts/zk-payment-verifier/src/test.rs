@@ -1,12 +1,83 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Bytes, BytesN, Env};
+use soroban_sdk::{
+    testutils::{storage::Persistent as _, Address as _, Ledger as _, MockAuth, MockAuthInvoke},
+    xdr::ToXdr,
+    Address, Bytes, BytesN, Env, IntoVal, Map,
+};
+
+/// SHA256(domain_32 || key_32), mirroring the verifier's key derivation.
+fn hash_domain_key(env: &Env, domain: &[u8; 32], key: &BytesN<32>) -> BytesN<32> {
+    let mut input = Bytes::from_slice(env, domain);
+    input.extend_from_slice(&key.to_array());
+    env.crypto().sha256(&input).into()
+}
+
+/// Registers an initialized verifier that accepts `services`.
+fn setup(env: &Env, services: &[&[u8]]) -> Address {
+    env.mock_all_auths();
+    let contract_id = env.register(ZkPaymentVerifier, ());
+    let client = ZkPaymentVerifierClient::new(env, &contract_id);
+    client.init(&Address::generate(env));
+    for service in services {
+        client.register_service(&Bytes::from_slice(env, service));
+    }
+    contract_id
+}
+
+/// Service the payment-proof tests prove against.
+const SERVICE: &[u8] = b"service_netflix";
+
+fn claim(amount_threshold: i128, window_start: u64, window_end: u64) -> PaymentClaim {
+    PaymentClaim {
+        amount_threshold,
+        window_start,
+        window_end,
+    }
+}
+
+/// Off-chain prover: returns (proof, commitment, nullifier) for `proof_key`,
+/// bound to `submitter`, `SERVICE` and the verifier deployment at `contract`.
+fn build_proof(
+    env: &Env,
+    contract: &Address,
+    submitter: &Address,
+    proof_key: &BytesN<32>,
+    amount_threshold: i128,
+    window_start: u64,
+    window_end: u64,
+) -> (Bytes, BytesN<32>, BytesN<32>) {
+    let commitment = hash_domain_key(env, verifier::COMMIT_DOMAIN, proof_key);
+    let service_id = Bytes::from_slice(env, SERVICE);
+    let nullifier = nullifier::compute_nullifier(env, contract, proof_key, &service_id);
+
+    let mut params = [0u8; 32];
+    params[0..16].copy_from_slice(&amount_threshold.to_be_bytes());
+    params[16..24].copy_from_slice(&window_start.to_be_bytes());
+    params[24..32].copy_from_slice(&window_end.to_be_bytes());
+
+    let mut ctx_input = Bytes::from_slice(env, &commitment.to_array());
+    ctx_input.extend_from_slice(&nullifier.to_array());
+    ctx_input.extend_from_slice(&params);
+    ctx_input.append(&submitter.clone().to_xdr(env));
+    ctx_input.append(&contract.clone().to_xdr(env));
+    ctx_input.extend_from_slice(&env.ledger().network_id().to_array());
+    let context: BytesN<32> = env.crypto().sha256(&ctx_input).into();
+
+    let mut s_input = Bytes::from_slice(env, &proof_key.to_array());
+    s_input.extend_from_slice(&context.to_array());
+    let s: BytesN<32> = env.crypto().sha256(&s_input).into();
+
+    let mut proof = Bytes::from_slice(env, &proof_key.to_array());
+    proof.extend_from_slice(&s.to_array());
+    (proof, commitment, nullifier)
+}
 
 #[test]
 fn test_verify_and_record_valid_commitment() {
     let env = Env::default();
-    let contract_id = env.register_contract(None, ZkPaymentVerifier);
+    let contract_id = setup(&env, &[b"service_netflix"]);
     let client = ZkPaymentVerifierClient::new(&env, &contract_id);
 
     let user_id = Bytes::from_slice(&env, b"user_alice");
@@ -38,7 +109,7 @@ fn test_verify_and_record_valid_commitment() {
 #[test]
 fn test_nullifier_prevents_double_proof() {
     let env = Env::default();
-    let contract_id = env.register_contract(None, ZkPaymentVerifier);
+    let contract_id = setup(&env, &[b"service_netflix"]);
     let client = ZkPaymentVerifierClient::new(&env, &contract_id);
 
     let user_id = Bytes::from_slice(&env, b"user_alice");
@@ -80,7 +151,7 @@ fn test_nullifier_prevents_double_proof() {
 #[test]
 fn test_wrong_commitment_rejected() {
     let env = Env::default();
-    let contract_id = env.register_contract(None, ZkPaymentVerifier);
+    let contract_id = setup(&env, &[b"service_netflix"]);
     let client = ZkPaymentVerifierClient::new(&env, &contract_id);
 
     let user_id = Bytes::from_slice(&env, b"user_alice");
@@ -102,7 +173,7 @@ fn test_wrong_commitment_rejected() {
 #[test]
 fn test_different_services_independent_nullifiers() {
     let env = Env::default();
-    let contract_id = env.register_contract(None, ZkPaymentVerifier);
+    let contract_id = setup(&env, &[b"service_netflix", b"service_spotify"]);
     let client = ZkPaymentVerifierClient::new(&env, &contract_id);
 
     let user_id = Bytes::from_slice(&env, b"user_alice");
@@ -131,13 +202,13 @@ fn test_different_services_independent_nullifiers() {
 #[test]
 fn test_is_nullifier_used() {
     let env = Env::default();
-    let contract_id = env.register_contract(None, ZkPaymentVerifier);
+    let contract_id = setup(&env, &[b"service_netflix"]);
     let client = ZkPaymentVerifierClient::new(&env, &contract_id);
 
     let service_id = Bytes::from_slice(&env, b"service_netflix");
     let blinding_factor = BytesN::from_array(&env, &[42u8; 32]);
 
-    let nullifier = nullifier::compute_nullifier(&env, &blinding_factor, &service_id);
+    let nullifier = nullifier::compute_nullifier(&env, &contract_id, &blinding_factor, &service_id);
 
     assert!(!client.is_nullifier_used(&nullifier));
 
@@ -151,3 +222,702 @@ fn test_is_nullifier_used() {
 
     assert!(client.is_nullifier_used(&nullifier));
 }
+
+#[test]
+fn test_verify_payment_proof_records_nullifier() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    let contract_id = setup(&env, &[SERVICE]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    let proof_key = BytesN::from_array(&env, &[9u8; 32]);
+    let payer = Address::generate(&env);
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &proof_key,
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    client.register_payment(&commitment, &payer, &1000);
+
+    assert!(!client.is_nullifier_used(&nullifier));
+    assert!(client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_700_000_000, 1_700_001_000),
+    ));
+    assert!(client.is_nullifier_used(&nullifier));
+
+    // Replaying the same proof is rejected
+    assert!(!client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_700_000_000, 1_700_001_000),
+    ));
+}
+
+#[test]
+fn test_verify_payment_proof_bound_to_threshold_and_window() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    let contract_id = setup(&env, &[SERVICE]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    let proof_key = BytesN::from_array(&env, &[9u8; 32]);
+    let payer = Address::generate(&env);
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &proof_key,
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    client.register_payment(&commitment, &payer, &1000);
+
+    // A higher claimed threshold does not match the proof transcript
+    assert!(!client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(5000, 1_700_000_000, 1_700_001_000),
+    ));
+    // Nor does a different window
+    assert!(!client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_600_000_000, 1_700_001_000),
+    ));
+    // Rejections leave the nullifier unused
+    assert!(!client.is_nullifier_used(&nullifier));
+}
+
+#[test]
+fn test_verify_payment_proof_outside_window() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_002_000);
+    let contract_id = setup(&env, &[SERVICE]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    let proof_key = BytesN::from_array(&env, &[9u8; 32]);
+    let payer = Address::generate(&env);
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &proof_key,
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    client.register_payment(&commitment, &payer, &1000);
+
+    assert!(!client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_700_000_000, 1_700_001_000),
+    ));
+}
+
+#[test]
+fn test_verify_payment_proof_rejects_payment_made_before_window() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_699_999_000);
+    let contract_id = setup(&env, &[SERVICE]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    let proof_key = BytesN::from_array(&env, &[9u8; 32]);
+    let payer = Address::generate(&env);
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &proof_key,
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    client.register_payment(&commitment, &payer, &1000);
+
+    // Submitted inside the window, but paid before it opened
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    assert!(!client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_700_000_000, 1_700_001_000),
+    ));
+    assert!(!client.is_nullifier_used(&nullifier));
+}
+
+#[test]
+fn test_verify_payment_proof_rejects_mismatched_nullifier() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    let contract_id = setup(&env, &[SERVICE]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    let payer = Address::generate(&env);
+    let (proof, commitment, _) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &BytesN::from_array(&env, &[9u8; 32]),
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    client.register_payment(&commitment, &payer, &1000);
+    let other_nullifier = BytesN::from_array(&env, &[1u8; 32]);
+
+    assert!(!client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &other_nullifier,
+        &claim(1000, 1_700_000_000, 1_700_001_000),
+    ));
+}
+
+#[test]
+fn test_verify_payment_proof_requires_registered_payment() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    let contract_id = setup(&env, &[SERVICE]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    // A well-formed proof over a freshly chosen key proves no payment
+    let payer = Address::generate(&env);
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &BytesN::from_array(&env, &[9u8; 32]),
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    assert!(!client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_700_000_000, 1_700_001_000),
+    ));
+    assert!(!client.is_nullifier_used(&nullifier));
+}
+
+#[test]
+fn test_verify_payment_proof_threshold_capped_by_registered_amount() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    let contract_id = setup(&env, &[SERVICE]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    let payer = Address::generate(&env);
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &BytesN::from_array(&env, &[9u8; 32]),
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    client.register_payment(&commitment, &payer, &999);
+
+    assert!(!client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_700_000_000, 1_700_001_000),
+    ));
+}
+
+#[test]
+fn test_copied_proof_cannot_be_spent_by_watcher() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    let contract_id = setup(&env, &[SERVICE]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    let payer = Address::generate(&env);
+    let proof_key = BytesN::from_array(&env, &[9u8; 32]);
+    let window = claim(1000, 1_700_000_000, 1_700_001_000);
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &proof_key,
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    client.register_payment(&commitment, &payer, &1000);
+
+    // Resubmitting the payer's proof under another address breaks the transcript
+    let watcher = Address::generate(&env);
+    assert!(!client.verify_payment_proof(
+        &watcher,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &window,
+    ));
+
+    // Re-proving with the key seen in the mempool fails the payer check
+    let (watcher_proof, _, _) = build_proof(
+        &env,
+        &contract_id,
+        &watcher,
+        &proof_key,
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    assert!(!client.verify_payment_proof(
+        &watcher,
+        &service,
+        &watcher_proof,
+        &commitment,
+        &nullifier,
+        &window,
+    ));
+
+    // The nullifier is still the payer's to spend
+    assert!(!client.is_nullifier_used(&nullifier));
+    assert!(client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &window,
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_verify_payment_proof_requires_submitter_auth() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    let contract_id = setup(&env, &[SERVICE]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    let payer = Address::generate(&env);
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &BytesN::from_array(&env, &[9u8; 32]),
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    client.register_payment(&commitment, &payer, &1000);
+
+    env.set_auths(&[]);
+    client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_700_000_000, 1_700_001_000),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Payment already registered")]
+fn test_register_payment_twice() {
+    let env = Env::default();
+    let contract_id = setup(&env, &[]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+
+    let commitment = BytesN::from_array(&env, &[5u8; 32]);
+    let payer = Address::generate(&env);
+    client.register_payment(&commitment, &payer, &1000);
+    client.register_payment(&commitment, &payer, &2000);
+}
+
+#[test]
+fn test_verify_payment_proof_requires_registered_service() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    let contract_id = setup(&env, &[SERVICE, b"service_spotify"]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+
+    let payer = Address::generate(&env);
+    let window = claim(1000, 1_700_000_000, 1_700_001_000);
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &BytesN::from_array(&env, &[9u8; 32]),
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    client.register_payment(&commitment, &payer, &1000);
+
+    // The nullifier is scoped to SERVICE, so another registered service rejects it
+    let other = Bytes::from_slice(&env, b"service_spotify");
+    assert!(!client.verify_payment_proof(&payer, &other, &proof, &commitment, &nullifier, &window));
+
+    // Once SERVICE is removed its proofs are rejected too
+    let service = Bytes::from_slice(&env, SERVICE);
+    client.remove_service(&service);
+    assert!(!client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &window,
+    ));
+    assert!(!client.is_nullifier_used(&nullifier));
+
+    client.register_service(&service);
+    assert!(client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &window,
+    ));
+}
+
+#[test]
+fn test_windowed_nullifier_expires_with_its_epoch() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    let contract_id = setup(&env, &[SERVICE]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    let payer = Address::generate(&env);
+    let window_end = 1_700_001_000;
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &contract_id,
+        &payer,
+        &BytesN::from_array(&env, &[9u8; 32]),
+        1000,
+        1_700_000_000,
+        window_end,
+    );
+    client.register_payment(&commitment, &payer, &1000);
+    assert!(client.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_700_000_000, window_end),
+    ));
+
+    let epoch = nullifier::epoch_of(window_end);
+    assert_eq!(client.get_nullifier_epoch(&nullifier), Some(epoch));
+    assert_eq!(client.current_epoch(), epoch);
+
+    // TTL covers the rest of the epoch, not the maximum
+    let epoch_end = (epoch + 1) * nullifier::EPOCH_SECONDS;
+    let expected_ttl = ((epoch_end - 1_700_000_500) / 5 + 1) as u32;
+    let ttl = env.as_contract(&contract_id, || {
+        env.storage()
+            .persistent()
+            .get_ttl(&DataKey::Nullifier(nullifier.clone()))
+    });
+    assert_eq!(ttl, expected_ttl);
+    assert!(ttl < env.storage().max_ttl());
+}
+
+#[test]
+fn test_unwindowed_nullifier_kept_at_max_ttl() {
+    let env = Env::default();
+    let contract_id = setup(&env, &[b"service_netflix"]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+
+    let user_id = Bytes::from_slice(&env, b"user_alice");
+    let service_id = Bytes::from_slice(&env, b"service_netflix");
+    let blinding_factor = BytesN::from_array(&env, &[42u8; 32]);
+    let expected = commitment::compute_commitment(
+        &env,
+        &user_id,
+        &service_id,
+        1500u128,
+        1700000000u64,
+        &blinding_factor,
+    );
+    assert!(client.verify_and_record(
+        &user_id,
+        &service_id,
+        &1500u128,
+        &1700000000u64,
+        &blinding_factor,
+        &expected,
+    ));
+
+    let nullifier = nullifier::compute_nullifier(&env, &contract_id, &blinding_factor, &service_id);
+    assert_eq!(
+        client.get_nullifier_epoch(&nullifier),
+        Some(nullifier::PERMANENT_EPOCH)
+    );
+    let ttl = env.as_contract(&contract_id, || {
+        env.storage()
+            .persistent()
+            .get_ttl(&DataKey::Nullifier(nullifier.clone()))
+    });
+    assert_eq!(ttl, env.storage().max_ttl());
+}
+
+#[test]
+fn test_legacy_map_nullifiers_still_rejected() {
+    let env = Env::default();
+    let contract_id = setup(&env, &[b"service_netflix"]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+
+    let user_id = Bytes::from_slice(&env, b"user_alice");
+    let service_id = Bytes::from_slice(&env, b"service_netflix");
+    let blinding_factor = BytesN::from_array(&env, &[42u8; 32]);
+    let nullifier = nullifier::compute_legacy_nullifier(&env, &blinding_factor, &service_id);
+
+    // Nullifier recorded by a pre-migration deployment
+    env.as_contract(&contract_id, || {
+        let mut legacy = Map::new(&env);
+        legacy.set(nullifier.clone(), true);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Nullifiers, &legacy);
+    });
+
+    assert!(client.is_nullifier_used(&nullifier));
+    assert_eq!(client.get_nullifier_epoch(&nullifier), None);
+
+    let expected = commitment::compute_commitment(
+        &env,
+        &user_id,
+        &service_id,
+        1500u128,
+        1700000000u64,
+        &blinding_factor,
+    );
+    assert!(!client.verify_and_record(
+        &user_id,
+        &service_id,
+        &1500u128,
+        &1700000000u64,
+        &blinding_factor,
+        &expected,
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Already initialized")]
+fn test_cannot_init_twice() {
+    let env = Env::default();
+    let contract_id = setup(&env, &[]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    client.init(&Address::generate(&env));
+}
+
+#[test]
+fn test_unregistered_service_rejected() {
+    let env = Env::default();
+    let contract_id = setup(&env, &[b"service_netflix"]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+
+    let user_id = Bytes::from_slice(&env, b"user_alice");
+    let service_id = Bytes::from_slice(&env, b"service_spotify");
+    let blinding_factor = BytesN::from_array(&env, &[42u8; 32]);
+    let expected = commitment::compute_commitment(
+        &env,
+        &user_id,
+        &service_id,
+        1500u128,
+        1700000000u64,
+        &blinding_factor,
+    );
+
+    assert!(!client.is_service_allowed(&service_id));
+    assert!(!client.verify_and_record(
+        &user_id,
+        &service_id,
+        &1500u128,
+        &1700000000u64,
+        &blinding_factor,
+        &expected,
+    ));
+
+    // Registering the service makes the same proof acceptable
+    client.register_service(&service_id);
+    assert!(client.verify_and_record(
+        &user_id,
+        &service_id,
+        &1500u128,
+        &1700000000u64,
+        &blinding_factor,
+        &expected,
+    ));
+}
+
+#[test]
+fn test_removed_service_rejected() {
+    let env = Env::default();
+    let contract_id = setup(&env, &[b"service_netflix"]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+
+    let user_id = Bytes::from_slice(&env, b"user_alice");
+    let service_id = Bytes::from_slice(&env, b"service_netflix");
+    let blinding_factor = BytesN::from_array(&env, &[42u8; 32]);
+    let expected = commitment::compute_commitment(
+        &env,
+        &user_id,
+        &service_id,
+        1500u128,
+        1700000000u64,
+        &blinding_factor,
+    );
+
+    client.remove_service(&service_id);
+    assert!(!client.is_service_allowed(&service_id));
+    assert!(!client.verify_and_record(
+        &user_id,
+        &service_id,
+        &1500u128,
+        &1700000000u64,
+        &blinding_factor,
+        &expected,
+    ));
+}
+
+#[test]
+fn test_nullifiers_bound_to_deployment() {
+    let env = Env::default();
+    let deployment_a = setup(&env, &[b"service_netflix"]);
+    let deployment_b = setup(&env, &[b"service_netflix"]);
+
+    let service_id = Bytes::from_slice(&env, b"service_netflix");
+    let blinding_factor = BytesN::from_array(&env, &[42u8; 32]);
+
+    let nullifier_a =
+        nullifier::compute_nullifier(&env, &deployment_a, &blinding_factor, &service_id);
+    let nullifier_b =
+        nullifier::compute_nullifier(&env, &deployment_b, &blinding_factor, &service_id);
+    assert_ne!(nullifier_a, nullifier_b);
+}
+
+#[test]
+fn test_payment_proof_not_replayable_on_other_deployment() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_500);
+    let deployment_a = setup(&env, &[SERVICE]);
+    let deployment_b = setup(&env, &[SERVICE]);
+    let service = Bytes::from_slice(&env, SERVICE);
+
+    let payer = Address::generate(&env);
+    let (proof, commitment, nullifier) = build_proof(
+        &env,
+        &deployment_a,
+        &payer,
+        &BytesN::from_array(&env, &[9u8; 32]),
+        1000,
+        1_700_000_000,
+        1_700_001_000,
+    );
+    for deployment in [&deployment_a, &deployment_b] {
+        ZkPaymentVerifierClient::new(&env, deployment).register_payment(&commitment, &payer, &1000);
+    }
+
+    let client_b = ZkPaymentVerifierClient::new(&env, &deployment_b);
+    assert!(!client_b.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_700_000_000, 1_700_001_000),
+    ));
+
+    let client_a = ZkPaymentVerifierClient::new(&env, &deployment_a);
+    assert!(client_a.verify_payment_proof(
+        &payer,
+        &service,
+        &proof,
+        &commitment,
+        &nullifier,
+        &claim(1000, 1_700_000_000, 1_700_001_000),
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_upgrade_rejects_non_governance_caller() {
+    let env = Env::default();
+    let contract_id = setup(&env, &[]);
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    client.set_governance(&Address::generate(&env));
+
+    env.set_auths(&[]);
+    client.upgrade(&BytesN::from_array(&env, &[1u8; 32]));
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_admin_cannot_replace_governance() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(ZkPaymentVerifier, ());
+    let client = ZkPaymentVerifierClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.init(&admin);
+    client.set_governance(&Address::generate(&env));
+    assert!(client.get_governance().is_some());
+
+    // Only the current governance contract may hand over; the admin may not
+    env.mock_auths(&[MockAuth {
+        address: &admin,
+        invoke: &MockAuthInvoke {
+            contract: &contract_id,
+            fn_name: "set_governance",
+            args: (admin.clone(),).into_val(&env),
+            sub_invokes: &[],
+        },
+    }]);
+    client.set_governance(&admin);
+}
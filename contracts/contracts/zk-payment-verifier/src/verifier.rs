@@ -4,51 +4,62 @@
 //!
 //! Domain tags follow the `syncro:payment:*` convention shared with
 //! [`commitment::compute_commitment`] and [`nullifier::compute_nullifier`]
-//! (see `commitment.rs` / `nullifier.rs`).
+//! (see `commitment.rs` / `nullifier.rs`). The nullifier is the v2 nullifier
+//! of `verify_and_record`, with `proof_key` as the blinding factor, so it is
+//! scoped to one service and one verifier deployment.
 //!
 //! The prover holds a secret `w` and derives an ephemeral `proof_key`:
 //!   proof_key = SHA256("syncro:payment:v1" || w)     (off-chain, never revealed)
 //!   commitment = SHA256(COMMIT_DOMAIN || proof_key)  (public, on-chain)
-//!   nullifier  = SHA256(NULL_DOMAIN  || proof_key)   (public, on-chain)
+//!   nullifier  = compute_nullifier(contract, proof_key, service_id)  (public, on-chain)
 //!
 //! Proof construction (64 bytes):
 //!   params  = amount_threshold(16 BE) || time_start(8 BE) || time_end(8 BE)  → padded to 32 B
-//!   context = SHA256(commitment || nullifier || params)
+//!   domain  = XDR(verifier contract address) || network_id
+//!   context = SHA256(commitment || nullifier || params || XDR(submitter) || domain)
 //!   s       = SHA256(proof_key || context)
 //!   proof   = proof_key(32 B) || s(32 B)
 //!
 //! On-chain verification checks:
 //!   1. SHA256(COMMIT_DOMAIN || r) == commitment
-//!   2. SHA256(NULL_DOMAIN   || r) == nullifier
+//!   2. compute_nullifier(contract, r, service_id) == nullifier
 //!   3. SHA256(r || context)       == s
-//!   4. time_window_start ≤ now ≤ time_window_end
+//!   4. window_start ≤ now ≤ window_end
 //!
-//! Security: forging requires finding proof_key s.t. both commitment and
-//! nullifier match — i.e. a SHA-256 preimage — which is computationally
-//! infeasible. The original secret `w` is never revealed.
+//! Security: the proof reveals `proof_key`, so these checks only show that
+//! the submitter knows the opening of `commitment` — anyone can pick a fresh
+//! key and pass them for any `amount_threshold`. They say nothing about a
+//! payment on their own. `ZkPaymentVerifier::verify_payment_proof` adds the
+//! payment binding: the commitment must have been registered by the admin
+//! for the submitter with an amount of at least the threshold, at a time
+//! inside the claim's window (check 4 only bounds the submission), and the
+//! submitter must authorize the call, so a proof copied from the mempool
+//! cannot be spent by anyone else. The original secret `w` is never revealed.
+//! Binding the context to the contract address and network id (SHA-256 of the
+//! network passphrase) stops a proof from being replayed on another deployment.
 
-use soroban_sdk::{Bytes, BytesN, Env};
+use soroban_sdk::{xdr::ToXdr, Address, Bytes, BytesN, Env};
 
-// 32-byte domain separators (tag || zero-padding)
-// Domain tag convention: "syncro:payment:*" (matches commitment.rs & nullifier.rs)
-const COMMIT_DOMAIN: &[u8; 32] =
-    b"syncro:payment:commit\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+use crate::{nullifier, PaymentClaim};
 
-const NULL_DOMAIN: &[u8; 32] =
-    b"syncro:payment:nullifier\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+// 32-byte domain separator (tag || zero-padding)
+// Domain tag convention: "syncro:payment:*" (matches commitment.rs & nullifier.rs)
+pub const COMMIT_DOMAIN: &[u8; 32] =
+    b"syncro:payment:commit\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
 
-/// Verify a ZK payment proof.
+/// Verify a ZK payment proof submitted by `submitter` for `service_id`.
 ///
 /// Returns `true` iff all checks pass.  Call sites are expected to enforce
-/// nullifier deduplication *before* calling this function.
+/// nullifier deduplication and check `commitment` against a registered
+/// payment; this function only checks the proof transcript.
 pub fn verify_proof(
     env: &Env,
+    submitter: &Address,
+    service_id: &Bytes,
     proof_bytes: &Bytes,
     commitment: &BytesN<32>,
     nullifier: &BytesN<32>,
-    amount_threshold: i128,
-    time_window_start: u64,
-    time_window_end: u64,
+    claim: &PaymentClaim,
 ) -> bool {
     // Proof must be exactly 64 bytes.
     if proof_bytes.len() != 64 {
@@ -56,11 +67,11 @@ pub fn verify_proof(
     }
 
     // Time-window sanity and current-ledger check.
-    if time_window_start >= time_window_end {
+    if claim.window_start >= claim.window_end {
         return false;
     }
     let now = env.ledger().timestamp();
-    if now < time_window_start || now > time_window_end {
+    if now < claim.window_start || now > claim.window_end {
         return false;
     }
 
@@ -80,19 +91,20 @@ pub fn verify_proof(
         return false;
     }
 
-    // Check 2: SHA256(NULL_DOMAIN || r) == nullifier
-    let expected_nullifier = hash_domain_key(env, NULL_DOMAIN, &r);
+    // Check 2: v2 nullifier of (r, service_id) == nullifier
+    let expected_nullifier =
+        nullifier::compute_nullifier(env, &env.current_contract_address(), &r, service_id);
     if expected_nullifier != *nullifier {
         return false;
     }
 
     // Build params: amount_threshold(16 BE) || time_start(8 BE) || time_end(8 BE)
     let mut params = [0u8; 32];
-    params[0..16].copy_from_slice(&amount_threshold.to_be_bytes());
-    params[16..24].copy_from_slice(&time_window_start.to_be_bytes());
-    params[24..32].copy_from_slice(&time_window_end.to_be_bytes());
+    params[0..16].copy_from_slice(&claim.amount_threshold.to_be_bytes());
+    params[16..24].copy_from_slice(&claim.window_start.to_be_bytes());
+    params[24..32].copy_from_slice(&claim.window_end.to_be_bytes());
 
-    // context = SHA256(commitment || nullifier || params)
+    // context = SHA256(commitment || nullifier || params || submitter || contract || network_id)
     let mut ctx_input = Bytes::new(env);
     let commitment_bytes: Bytes = commitment.clone().into();
     ctx_input.append(&commitment_bytes);
@@ -100,7 +112,11 @@ pub fn verify_proof(
     ctx_input.append(&nullifier_bytes);
     let params_bytes: Bytes = BytesN::<32>::from_array(env, &params).into();
     ctx_input.append(&params_bytes);
-    let context: BytesN<32> = env.crypto().sha256(&ctx_input);
+    ctx_input.append(&submitter.clone().to_xdr(env));
+    ctx_input.append(&env.current_contract_address().to_xdr(env));
+    let network_id: Bytes = env.ledger().network_id().into();
+    ctx_input.append(&network_id);
+    let context: BytesN<32> = env.crypto().sha256(&ctx_input).into();
 
     // Check 3: SHA256(r || context) == s
     let mut s_input = Bytes::new(env);
@@ -108,7 +124,7 @@ pub fn verify_proof(
     s_input.append(&r_bytes);
     let context_bytes: Bytes = context.into();
     s_input.append(&context_bytes);
-    let expected_s: BytesN<32> = env.crypto().sha256(&s_input);
+    let expected_s: BytesN<32> = env.crypto().sha256(&s_input).into();
 
     expected_s == s
 }
@@ -120,5 +136,5 @@ fn hash_domain_key(env: &Env, domain: &[u8; 32], key: &BytesN<32>) -> BytesN<32>
     input.append(&domain_bytes);
     let key_bytes: Bytes = key.clone().into();
     input.append(&key_bytes);
-    env.crypto().sha256(&input)
+    env.crypto().sha256(&input).into()
 }
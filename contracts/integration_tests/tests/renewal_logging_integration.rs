@@ -1,17 +1,34 @@
 /// Integration test for subscription_renewal cross-contract call to subscription_logging
 /// Acceptance: Integration test invoking renewal that records a commitment in logging.
 
-use soroban_sdk::{testutils::Address as _, Address, Env};
+use soroban_sdk::{
+    testutils::Address as _,
+    token::{StellarAssetClient, TokenClient},
+    xdr::ToXdr,
+    Address, BytesN, Env, String,
+};
 
+use agent_registry::{AgentRegistry, AgentRegistryClient, Scope};
+use subscription_logging::commitment::{self, EventTypeByte};
 use subscription_logging::{SubscriptionLoggingContract, SubscriptionLoggingContractClient};
 use subscription_renewal::{
-    SubscriptionRenewalContract, SubscriptionRenewalContractClient, SubscriptionState,
+    derive_blinding_factor, hash_blinding_salt, CancelMode, SubscriptionRenewalContract,
+    SubscriptionRenewalContractClient, SubscriptionState, UsageTier,
 };
 
+/// The owner's blinding salt; only its hash is registered on-chain.
+fn salt(env: &Env) -> BytesN<32> {
+    BytesN::from_array(env, &[7u8; 32])
+}
+
+fn salt_hash(env: &Env) -> BytesN<32> {
+    hash_blinding_salt(env, &salt(env))
+}
+
 #[test]
 fn test_renewal_with_logging_integration() {
     let env = Env::default();
-    env.mock_all_auths_allowing_non_root_auth();
+    env.mock_all_auths();
 
     // Setup logging contract
     let log_id = env.register(SubscriptionLoggingContract, ());
@@ -26,6 +43,17 @@ fn test_renewal_with_logging_integration() {
     
     // KEY: Configure renewal contract to use logging contract for cross-contract calls
     renew.set_logging_contract(&log_id);
+    // The renewal contract logs as an allowlisted writer, not as the logging admin
+    log.add_writer(&renew_id);
+
+    // Renewal worker with the Renewals scope
+    let registry_id = env.register(AgentRegistry, ());
+    let registry = AgentRegistryClient::new(&env, &registry_id);
+    registry.init(&admin);
+    let agent = Address::generate(&env);
+    registry.register(&agent);
+    registry.update_scopes(&agent, &(Scope::Renewals as u32));
+    renew.set_agent_registry(&registry_id);
 
     // Verify no commitments yet
     assert_eq!(log.get_commitment_count(), 0);
@@ -33,31 +61,37 @@ fn test_renewal_with_logging_integration() {
     // Create subscription - this should automatically record a commitment via cross-contract call
     let user = Address::generate(&env);
     let merchant = Address::generate(&env);
-    renew.init_sub(&user, &merchant, &500, &86400, &1000, &1);
+    let token = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    StellarAssetClient::new(&env, &token).mint(&user, &500);
+    TokenClient::new(&env, &token).approve(&user, &renew_id, &500, &1_000);
+    let sub_id = renew.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt_hash(&env));
 
     // Verify initialization recorded a commitment automatically
     assert_eq!(log.get_commitment_count(), 1);
     let init_commitment = log.get_commitment(&0).unwrap();
     assert_eq!(init_commitment.commitment_index, 0);
+    assert_eq!(log.get_commitment_writer(&0), Some(renew_id.clone()));
 
     // Perform renewal - this should also record a commitment automatically
-    renew.approve_renewal(&1, &1, &1000, &100);
-    renew.acquire_renewal_lock(&1, &200);
-    assert!(renew.renew(&1, &1, &500, &3, &10, &20260101, &true));
+    renew.approve_renewal(&sub_id, &1, &1000, &100);
+    renew.acquire_renewal_lock(&agent, &sub_id, &200);
+    assert!(renew.renew(&agent, &sub_id, &1, &500, &20260101));
 
     // Verify renewal recorded a commitment automatically
     assert_eq!(log.get_commitment_count(), 2);
     let renewal_commitment = log.get_commitment(&1).unwrap();
     assert_eq!(renewal_commitment.commitment_index, 1);
+    assert_eq!(log.get_writer_commitment_count(&renew_id), 2);
 
-    // Verify subscription state
-    assert_eq!(renew.get_sub(&1).state, SubscriptionState::Active);
+    // Verify subscription state and settlement
+    assert_eq!(renew.get_sub(&sub_id).state, SubscriptionState::Active);
+    assert_eq!(TokenClient::new(&env, &token).balance(&merchant), 500);
 }
 
 #[test]
 fn test_cross_contract_call_on_cancellation() {
     let env = Env::default();
-    env.mock_all_auths_allowing_non_root_auth();
+    env.mock_all_auths();
 
     // Setup contracts
     let log_id = env.register(SubscriptionLoggingContract, ());
@@ -69,18 +103,112 @@ fn test_cross_contract_call_on_cancellation() {
     let renew = SubscriptionRenewalContractClient::new(&env, &renew_id);
     renew.init(&admin);
     renew.set_logging_contract(&log_id);
+    log.add_writer(&renew_id);
 
     // Create subscription
     let user = Address::generate(&env);
     let merchant = Address::generate(&env);
-    renew.init_sub(&user, &merchant, &500, &86400, &1000, &2);
+    let token = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    let sub_id = renew.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt_hash(&env));
     
     assert_eq!(log.get_commitment_count(), 1);
 
     // Cancel subscription - should record another commitment
-    renew.cancel_sub(&2);
+    renew.cancel_sub(&sub_id, &CancelMode::Immediate);
     
     // Verify cancellation recorded a commitment
     assert_eq!(log.get_commitment_count(), 2);
-    assert_eq!(renew.get_sub(&2).state, SubscriptionState::Cancelled);
+    assert_eq!(renew.get_sub(&sub_id).state, SubscriptionState::Cancelled);
+}
+
+#[test]
+fn test_owner_opens_blinded_commitment() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let log_id = env.register(SubscriptionLoggingContract, ());
+    let log = SubscriptionLoggingContractClient::new(&env, &log_id);
+    let admin = Address::generate(&env);
+    log.init(&admin);
+
+    let renew_id = env.register(SubscriptionRenewalContract, ());
+    let renew = SubscriptionRenewalContractClient::new(&env, &renew_id);
+    renew.init(&admin);
+    renew.set_logging_contract(&log_id);
+    log.add_writer(&renew_id);
+
+    // Two subscriptions logging the same event under different salts
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let token = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    let other_salt = BytesN::from_array(&env, &[8u8; 32]);
+    let sub_id = renew.init_sub(&user, &merchant, &token, &500, &86400, &1000, &salt_hash(&env));
+    renew.init_sub(&user, &merchant, &token, &500, &86400, &1000, &hash_blinding_salt(&env, &other_salt));
+    renew.cancel_sub(&sub_id, &CancelMode::Immediate);
+    assert_eq!(renew.get_log_nonce(&sub_id), 2);
+
+    // Commitments do not reveal which subscription they belong to
+    let create_a = log.get_commitment(&0).unwrap().commitment_hash;
+    let create_b = log.get_commitment(&1).unwrap().commitment_hash;
+    assert_ne!(create_a, create_b);
+
+    // The owner reconstructs the opening of the cancellation (nonce 1 for the first sub)
+    let data = String::from_str(&env, "Subscription cancelled");
+    let event_data_hash = commitment::hash_event_data(&env, &(sub_id, data).to_xdr(&env));
+    let blinding_factor = derive_blinding_factor(&env, &salt_hash(&env), sub_id, 1);
+    let event_type = EventTypeByte::SubscriptionCancel as u32;
+    assert!(log.verify_disclosure(&2, &event_type, &event_data_hash, &blinding_factor, &None));
+
+    // A wrong salt does not open it
+    let wrong = derive_blinding_factor(&env, &hash_blinding_salt(&env, &other_salt), sub_id, 1);
+    assert!(!log.verify_disclosure(&2, &event_type, &event_data_hash, &wrong, &None));
+
+    // Through the renewal contract, only the owner's salt opens the commitment
+    assert!(renew.verify_log_disclosure(&sub_id, &1, &2, &event_type, &event_data_hash, &salt(&env)));
+    assert!(!renew.verify_log_disclosure(&sub_id, &1, &2, &event_type, &event_data_hash, &other_salt));
+    assert!(!renew.verify_log_disclosure(&sub_id, &1, &2, &event_type, &event_data_hash, &salt_hash(&env)));
+}
+
+#[test]
+fn test_usage_report_commitment_opens_for_dispute() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let log_id = env.register(SubscriptionLoggingContract, ());
+    let log = SubscriptionLoggingContractClient::new(&env, &log_id);
+    let admin = Address::generate(&env);
+    log.init(&admin);
+
+    let renew_id = env.register(SubscriptionRenewalContract, ());
+    let renew = SubscriptionRenewalContractClient::new(&env, &renew_id);
+    renew.init(&admin);
+    renew.set_logging_contract(&log_id);
+    log.add_writer(&renew_id);
+
+    let user = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
+    let sub_id = renew.init_sub(&user, &merchant, &token, &500, &86400, &0, &salt_hash(&env));
+    let tier = UsageTier {
+        up_to: 1000,
+        unit_price: 3,
+    };
+    let tiers = soroban_sdk::vec![&env, tier];
+    renew.set_usage_tiers(&sub_id, &tiers);
+    let amount = renew.report_usage(&sub_id, &1, &40);
+    assert_eq!(amount, 120);
+
+    // Create, tier update, usage report: the report is the third commitment (nonce 2)
+    assert_eq!(log.get_commitment_count(), 3);
+    let event_data = (sub_id, 1u64, 40u64, amount).to_xdr(&env);
+    let event_data_hash = commitment::hash_event_data(&env, &event_data);
+    let blinding_factor = derive_blinding_factor(&env, &salt_hash(&env), sub_id, 2);
+    let event_type = EventTypeByte::UsageReport as u32;
+    assert!(log.verify_disclosure(&2, &event_type, &event_data_hash, &blinding_factor, &None));
+
+    // A different unit count does not open it
+    let disputed = commitment::hash_event_data(&env, &(sub_id, 1u64, 400u64, amount).to_xdr(&env));
+    assert!(!log.verify_disclosure(&2, &event_type, &disputed, &blinding_factor, &None));
 }
@@ -65,7 +65,7 @@ echo "  ContractUpgradeGovernance: $UPGRADE_ID"
 
 echo ""
 echo "==> Running initialization..."
-bash "$(dirname "$0")/init.sh" "$NETWORK" "$SECRET_KEY" "$RENEWAL_ID" "$LOGGING_ID" "$UPGRADE_ID"
+bash "$(dirname "$0")/init.sh" "$NETWORK" "$SECRET_KEY" "$RENEWAL_ID" "$LOGGING_ID" "$UPGRADE_ID" "$ZK_VERIFIER_ID"
 
 echo ""
 echo "==> Add to backend/.env:"
@@ -1,7 +1,7 @@
 #!/bin/bash
 set -e
 
-# Usage: init.sh <network> <secret_key> <renewal_contract_id> <logging_contract_id> [upgrade_contract_id]
+# Usage: init.sh <network> <secret_key> <renewal_contract_id> <logging_contract_id> [upgrade_contract_id] [zk_verifier_contract_id]
 # Can be run standalone after deploy.sh, or called by deploy.sh automatically.
 
 NETWORK=${1:-testnet}
@@ -9,6 +9,7 @@ SECRET_KEY=${2:-${STELLAR_SECRET_KEY:?'STELLAR_SECRET_KEY required'}}
 RENEWAL_ID=${3:-${SOROBAN_RENEWAL_ADDRESS:?'SOROBAN_RENEWAL_ADDRESS required'}}
 LOGGING_ID=${4:-${SOROBAN_LOGGING_ADDRESS:?'SOROBAN_LOGGING_ADDRESS required'}}
 UPGRADE_ID=${5:-${SOROBAN_UPGRADE_ADDRESS:-''}}
+ZK_VERIFIER_ID=${6:-${SOROBAN_ZK_VERIFIER_ADDRESS:-''}}
 
 # Resolve admin address from the deployer key
 ADMIN_ADDRESS=$(stellar keys address "$SECRET_KEY" 2>/dev/null || \
@@ -67,5 +68,18 @@ if [ -n "$UPGRADE_ID" ]; then
   echo "  NOTE: In production, replace guardians with distinct keypairs."
 fi
 
+# Initialize ZkPaymentVerifier (if available)
+if [ -n "$ZK_VERIFIER_ID" ]; then
+  echo "  Initializing ZkPaymentVerifier..."
+  stellar contract invoke \
+    --id "$ZK_VERIFIER_ID" \
+    --source "$SECRET_KEY" \
+    --network "$NETWORK" \
+    -- init \
+    --admin "$ADMIN_ADDRESS"
+  echo "  ZkPaymentVerifier initialized."
+  echo "  NOTE: Register accepted service ids with register_service before submitting proofs."
+fi
+
 echo ""
 echo "==> Initialization complete."
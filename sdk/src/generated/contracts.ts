@@ -14,7 +14,7 @@ export interface SubscriptionLoggingContract {
 }
 
 export interface SubscriptionRenewalContract {
-  renew(args: { arg0: string; arg1: bigint; arg2: bigint; arg3: bigint; arg4: bigint; arg5: bigint; arg6: bigint; arg7: boolean }): Promise<unknown>;
+  renew(args: { arg0: string; arg1: bigint; arg2: bigint; arg3: bigint; arg4: bigint }): Promise<unknown>;
 }
 
 export interface GeneratedContractMap {
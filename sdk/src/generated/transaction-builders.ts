@@ -87,7 +87,7 @@ export function buildSubscriptionLoggingRecordLog(
 export function buildSubscriptionRenewalRenew(
   contractId: string,
   sourceAccount: string,
-  args: { arg0: string; arg1: bigint; arg2: bigint; arg3: bigint; arg4: bigint; arg5: bigint; arg6: bigint; arg7: boolean },
+  args: { arg0: string; arg1: bigint; arg2: bigint; arg3: bigint; arg4: bigint },
 ): BuiltTransaction {
   return {
     contractId,
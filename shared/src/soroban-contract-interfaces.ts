@@ -63,6 +63,10 @@ export const SOROBAN_CONTRACT_INTERFACES: SorobanContractInterface[] = [
         name: 'record_log',
         args: ['U64', 'String', 'String'],
       },
+      {
+        name: 'record_commitment',
+        args: ['Address', 'BytesN32'],
+      },
     ],
   },
   {
@@ -71,16 +75,7 @@ export const SOROBAN_CONTRACT_INTERFACES: SorobanContractInterface[] = [
     functions: [
       {
         name: 'renew',
-        args: [
-          'Address',
-          'U64',
-          'U64',
-          'I128',
-          'U64',
-          'U64',
-          'U64',
-          'Bool',
-        ],
+        args: ['Address', 'U64', 'U64', 'I128', 'U64'],
       },
     ],
   },